@@ -0,0 +1,56 @@
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time;
+
+use vmcircbuffer::sync::Circular;
+
+const MIN_ITEMS: usize = 16384;
+
+fn main() {
+    let n_items: u64 = 100_000_000;
+    let n_readers = std::env::args()
+        .nth(1)
+        .and_then(|a| a.parse().ok())
+        .unwrap_or(4);
+
+    let mut w = Circular::with_capacity::<u32>(MIN_ITEMS).unwrap();
+    let barrier = Arc::new(Barrier::new(n_readers + 1));
+
+    let mut handles = Vec::new();
+    for _ in 0..n_readers {
+        let mut r = w.add_reader();
+        let barrier = Arc::clone(&barrier);
+        handles.push(thread::spawn(move || {
+            barrier.wait();
+            let mut n = 0;
+            while let Some(s) = r.slice() {
+                let l = s.len();
+                n += l as u64;
+                r.consume(l);
+            }
+            n
+        }));
+    }
+
+    barrier.wait();
+    let now = time::Instant::now();
+    let mut produced = 0;
+    while produced < n_items {
+        let s = w.slice();
+        let n = std::cmp::min(s.len() as u64, n_items - produced) as usize;
+        w.produce(n);
+        produced += n as u64;
+    }
+    drop(w);
+
+    for h in handles {
+        assert_eq!(h.join().unwrap(), n_items);
+    }
+    let elapsed = now.elapsed();
+    println!("readers: {n_readers}");
+    println!("runtime (in s): {}", elapsed.as_secs_f64());
+    println!(
+        "throughput (in Mitems/s): {}",
+        n_items as f64 / elapsed.as_secs_f64() / 1e6
+    );
+}
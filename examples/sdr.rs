@@ -5,6 +5,7 @@ use std::thread;
 use std::thread::JoinHandle;
 use std::time;
 
+use vmcircbuffer::generic::Pod;
 use vmcircbuffer::sync::Circular;
 use vmcircbuffer::sync::Reader;
 
@@ -49,7 +50,10 @@ impl<F: FnMut(&mut [A]) -> Option<usize> + Send + Sync + 'static, A: Send + Sync
         }
     }
 
-    pub fn run(&mut self, barrier: Arc<Barrier>) -> (Reader<A>, JoinHandle<()>) {
+    pub fn run(&mut self, barrier: Arc<Barrier>) -> (Reader<A>, JoinHandle<()>)
+    where
+        A: Pod,
+    {
         let mut w = Circular::with_capacity::<A>(MIN_ITEMS).unwrap();
         let r = w.add_reader();
         let mut f = self.f.take().unwrap();
@@ -112,7 +116,10 @@ where
         &mut self,
         mut reader: Reader<A>,
         barrier: Arc<Barrier>,
-    ) -> (Reader<B>, JoinHandle<()>) {
+    ) -> (Reader<B>, JoinHandle<()>)
+    where
+        B: Pod,
+    {
         let mut w = Circular::with_capacity::<B>(MIN_ITEMS).unwrap();
         let r = w.add_reader();
         let mut f = self.f.take().unwrap();
@@ -4,17 +4,80 @@
 //! [Reader](crate::asynchronous::Reader) have async `slice()` functions to
 //! await until buffer space or data becomes available, respectively.
 
-use futures::channel::mpsc::{channel, Receiver, Sender};
-use futures::StreamExt;
+use futures::future::{poll_fn, select};
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use futures::task::AtomicWaker;
+use futures::Stream;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::io::{self, IoSlice, IoSliceMut};
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
 use std::slice;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use crate::double_mapped_buffer::BufferPool;
+use crate::double_mapped_buffer::Builder;
 use crate::generic;
 use crate::generic::CircularError;
+use crate::generic::ConsumeError;
+use crate::generic::Metadata;
 use crate::generic::NoMetadata;
 use crate::generic::Notifier;
+use crate::generic::Pod;
+use crate::generic::ProduceError;
+use crate::generic::ReaderOptions;
+use crate::generic::Stats;
 
+/// Flag that is set by an [AsyncNotifier] and awaited by a reader or writer task.
+#[derive(Default)]
+struct Signal {
+    flag: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl Signal {
+    fn notify(&self) {
+        self.flag.store(true, Ordering::Release);
+        self.waker.wake();
+    }
+
+    fn poll_wait(&self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.flag.swap(false, Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+        self.waker.register(cx.waker());
+        if self.flag.swap(false, Ordering::AcqRel) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn wait<'a>(&'a self, stats: Option<&'a Stats>) -> impl Future<Output = ()> + Unpin + 'a {
+        let mut start = None;
+        poll_fn(move |cx| {
+            let Some(stats) = stats else {
+                return self.poll_wait(cx);
+            };
+            let start = *start.get_or_insert_with(Instant::now);
+            let ret = self.poll_wait(cx);
+            if ret.is_ready() {
+                stats.record_wait(start.elapsed());
+            }
+            ret
+        })
+    }
+}
+
+#[derive(Clone)]
 struct AsyncNotifier {
-    chan: Sender<()>,
+    signal: Arc<Signal>,
     armed: bool,
 }
 
@@ -24,12 +87,126 @@ impl Notifier for AsyncNotifier {
     }
     fn notify(&mut self) {
         if self.armed {
-            let _ = self.chan.try_send(());
+            self.signal.notify();
             self.armed = false;
         }
     }
 }
 
+/// Wakes the tasks of pending [Delay]s from a helper thread.
+///
+/// The thread is started for the first registration and exits, once there are
+/// no registrations left.
+struct Timer {
+    state: Mutex<TimerState>,
+    cond: Condvar,
+}
+
+struct TimerState {
+    delays: BTreeMap<u64, (Instant, Waker)>,
+    next_id: u64,
+    running: bool,
+}
+
+impl Timer {
+    /// Register or update the registration `id` and return its id.
+    fn register(&'static self, id: Option<u64>, deadline: Instant, waker: &Waker) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        let id = id.unwrap_or_else(|| {
+            state.next_id += 1;
+            state.next_id
+        });
+        match state.delays.get_mut(&id) {
+            Some((_, w)) => w.clone_from(waker),
+            None => {
+                state.delays.insert(id, (deadline, waker.clone()));
+            }
+        }
+        if !state.running {
+            state.running = true;
+            std::thread::Builder::new()
+                .name("vmcircbuffer-timer".to_string())
+                .spawn(move || self.run())
+                .expect("vmcircbuffer: failed to spawn timer thread");
+        }
+        self.cond.notify_one();
+        id
+    }
+
+    fn remove(&self, id: u64) {
+        self.state.lock().unwrap().delays.remove(&id);
+        self.cond.notify_one();
+    }
+
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            state.delays.retain(|_, (d, w)| {
+                if *d <= now {
+                    w.wake_by_ref();
+                    false
+                } else {
+                    true
+                }
+            });
+            state = match state.delays.values().map(|(d, _)| *d).min() {
+                Some(d) => self.cond.wait_timeout(state, d - now).unwrap().0,
+                None => {
+                    state.running = false;
+                    return;
+                }
+            };
+        }
+    }
+}
+
+static TIMER: Timer = Timer {
+    state: Mutex::new(TimerState {
+        delays: BTreeMap::new(),
+        next_id: 0,
+        running: false,
+    }),
+    cond: Condvar::new(),
+};
+
+/// Future that resolves once the deadline has passed.
+struct Delay {
+    deadline: Instant,
+    id: Option<u64>,
+}
+
+impl Delay {
+    fn new(deadline: Instant) -> Self {
+        Delay { deadline, id: None }
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            if let Some(id) = self.id.take() {
+                TIMER.remove(id);
+            }
+            Poll::Ready(())
+        } else {
+            let id = TIMER.register(self.id, self.deadline, cx.waker());
+            self.id = Some(id);
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Delay {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            TIMER.remove(id);
+        }
+    }
+}
+
 /// Builder for the *async* circular buffer implementation.
 pub struct Circular;
 
@@ -46,57 +223,242 @@ impl Circular {
     ///
     /// The size is the least common multiple of the page size and the size of `T`.
     pub fn with_capacity<T>(min_items: usize) -> Result<Writer<T>, CircularError> {
-        let writer = generic::Circular::with_capacity(min_items)?;
+        Self::with_capacity_and_metadata(min_items)
+    }
+
+    /// Create a buffer that can hold at least `min_items` items of type `T`,
+    /// which can be annotated with [Metadata] of type `M`.
+    ///
+    /// The size is the least common multiple of the page size and the size of `T`.
+    pub fn with_capacity_and_metadata<T, M: Metadata>(
+        min_items: usize,
+    ) -> Result<Writer<T, M>, CircularError> {
+        Self::with_builder_and_metadata(&Builder::new(min_items))
+    }
+
+    /// Create a buffer, whose underlying [DoubleMappedBuffer](crate::double_mapped_buffer::DoubleMappedBuffer)
+    /// is configured by a [Builder], e.g., to set the [temp directory](Builder::tmp_dir).
+    pub fn with_builder<T>(builder: &Builder) -> Result<Writer<T>, CircularError> {
+        Self::with_builder_and_metadata(builder)
+    }
+
+    /// Create a buffer, whose underlying [DoubleMappedBuffer](crate::double_mapped_buffer::DoubleMappedBuffer)
+    /// is configured by a [Builder] and whose items can be annotated with
+    /// [Metadata] of type `M`.
+    pub fn with_builder_and_metadata<T, M: Metadata>(
+        builder: &Builder,
+    ) -> Result<Writer<T, M>, CircularError> {
+        let writer = generic::Circular::with_builder(builder)?;
+
+        let signal = Arc::new(Signal::default());
+        Ok(Writer {
+            writer,
+            writer_signal: signal.clone(),
+            signal,
+            stats: None,
+        })
+    }
+
+    /// Create a buffer that can hold at least `min_items` items of type `T`,
+    /// whose [DoubleMappedBuffer](crate::double_mapped_buffer::DoubleMappedBuffer)
+    /// is taken from a [BufferPool].
+    ///
+    /// The buffer returns to the pool, once the writer and all readers are
+    /// dropped.
+    pub fn with_pool<T>(
+        pool: &BufferPool<T>,
+        min_items: usize,
+    ) -> Result<Writer<T>, CircularError> {
+        let writer = generic::Circular::with_pool(pool, min_items)?;
+
+        let signal = Arc::new(Signal::default());
+        Ok(Writer {
+            writer,
+            writer_signal: signal.clone(),
+            signal,
+            stats: None,
+        })
+    }
+
+    /// Create a buffer that can hold at least `min_items` items of type `T`,
+    /// where the writer overwrites the oldest data, instead of waiting for the
+    /// readers. It only waits for readers that hold a slice.
+    ///
+    /// All readers of the buffer are [lossy](crate::asynchronous::Writer::add_lossy_reader).
+    ///
+    /// The size is the least common multiple of the page size and the size of `T`.
+    pub fn with_capacity_lossy<T>(min_items: usize) -> Result<Writer<T>, CircularError> {
+        let writer = generic::Circular::with_capacity_lossy(min_items)?;
 
-        let (tx, rx) = channel(1);
+        let signal = Arc::new(Signal::default());
         Ok(Writer {
             writer,
-            writer_sender: tx,
-            chan: rx,
+            writer_signal: signal.clone(),
+            signal,
+            stats: None,
         })
     }
 }
 
-/// Writer for a blocking circular buffer with items of type `T`.
-pub struct Writer<T> {
-    writer_sender: Sender<()>,
-    chan: Receiver<()>,
-    writer: generic::Writer<T, AsyncNotifier, NoMetadata>,
+/// Writer for an async circular buffer with items of type `T` and [Metadata] of type `M`.
+pub struct Writer<T, M: Metadata = NoMetadata> {
+    writer_signal: Arc<Signal>,
+    signal: Arc<Signal>,
+    stats: Option<Arc<Stats>>,
+    writer: generic::Writer<T, AsyncNotifier, M>,
 }
 
-impl<T> Writer<T> {
+impl<T, M: Metadata> fmt::Debug for Writer<T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Writer")
+            .field("writer", &self.writer)
+            .finish()
+    }
+}
+
+impl<T, M: Metadata> Writer<T, M> {
     /// Add a reader to the buffer.
     ///
     /// All readers can block the buffer, i.e., the writer will only overwrite
     /// data, if data was [consume](crate::asynchronous::Reader::consume)ed by
     /// all readers.
-    pub fn add_reader(&self) -> Reader<T> {
+    pub fn add_reader(&self) -> Reader<T, M> {
+        let (r_notifier, w_notifier, signal) = self.notifiers();
+        let reader = self.writer.add_reader(r_notifier, w_notifier);
+        Reader {
+            reader,
+            signal,
+            stats: None,
+        }
+    }
+
+    /// Add a reader to the buffer that is configured by [ReaderOptions],
+    /// e.g., one that never blocks the writer.
+    ///
+    /// See [generic::Writer::add_reader_with_opts].
+    pub fn add_reader_with_opts(&self, opts: &ReaderOptions) -> Reader<T, M> {
+        let (r_notifier, w_notifier, signal) = self.notifiers();
+        let reader = self
+            .writer
+            .add_reader_with_opts(r_notifier, w_notifier, opts);
+        Reader {
+            reader,
+            signal,
+            stats: None,
+        }
+    }
+
+    /// Add a lossy reader to the buffer.
+    ///
+    /// A lossy reader does not hold back the writer. If it falls behind, the
+    /// oldest items are dropped and the reader continues with the most recent
+    /// data that fits into the buffer. The number of dropped items can be
+    /// queried with [take_overrun](crate::asynchronous::Reader::take_overrun). The
+    /// writer only waits for the reader while it holds a slice.
+    pub fn add_lossy_reader(&self) -> Reader<T, M> {
+        let (r_notifier, w_notifier, signal) = self.notifiers();
+        let reader = self.writer.add_lossy_reader(r_notifier, w_notifier);
+        Reader {
+            reader,
+            signal,
+            stats: None,
+        }
+    }
+
+    /// Add a shared reader to the buffer.
+    ///
+    /// Shared readers compete for the items, i.e., each item is delivered to
+    /// exactly one of them. Further readers that share the consumption cursor
+    /// are created with [share](crate::asynchronous::Reader::share). A slice claims the
+    /// returned items for the reader until they are consumed.
+    pub fn add_shared_reader(&self) -> Reader<T, M> {
+        let (r_notifier, w_notifier, signal) = self.notifiers();
+        let reader = self.writer.add_shared_reader(r_notifier, w_notifier);
+        Reader {
+            reader,
+            signal,
+            stats: None,
+        }
+    }
+
+    /// Add a writer that produces into the same buffer.
+    ///
+    /// Each slice reserves the returned space for the writer until it is
+    /// produced. Readers see the items in the order in which the space was
+    /// reserved. The buffer is finished, once all writers are dropped.
+    pub fn add_writer(&mut self) -> Writer<T, M> {
+        let signal = Arc::new(Signal::default());
+        let notifier = AsyncNotifier {
+            signal: signal.clone(),
+            armed: false,
+        };
+        Writer {
+            writer_signal: self.writer_signal.clone(),
+            signal,
+            stats: None,
+            writer: self.writer.add_writer(notifier),
+        }
+    }
+
+    fn notifiers(&self) -> (AsyncNotifier, AsyncNotifier, Arc<Signal>) {
         let w_notifier = AsyncNotifier {
-            chan: self.writer_sender.clone(),
+            signal: self.writer_signal.clone(),
             armed: false,
         };
 
-        let (tx, rx) = channel(1);
+        let signal = Arc::new(Signal::default());
         let r_notififer = AsyncNotifier {
-            chan: tx,
+            signal: signal.clone(),
             armed: false,
         };
 
-        let reader = self.writer.add_reader(r_notififer, w_notifier);
-        Reader { reader, chan: rx }
+        (r_notififer, w_notifier, signal)
+    }
+
+    /// Wait for a notification. With a [stall timeout](Writer::set_stall_timeout),
+    /// wake up after the timeout at the latest to detect stalled readers.
+    async fn wait(&self) {
+        match self
+            .writer
+            .stall_timeout()
+            .and_then(|t| Instant::now().checked_add(t))
+        {
+            Some(deadline) => self.wait_until(deadline).await,
+            None => self.signal.wait(self.stats.as_deref()).await,
+        }
+    }
+
+    /// Like [wait](Writer::wait), but wake up at `deadline` at the latest.
+    async fn wait_until(&self, deadline: Instant) {
+        let deadline = match self
+            .writer
+            .stall_timeout()
+            .and_then(|t| Instant::now().checked_add(t))
+        {
+            Some(stall) => std::cmp::min(stall, deadline),
+            None => deadline,
+        };
+        let _ = select(
+            self.signal.wait(self.stats.as_deref()),
+            Delay::new(deadline),
+        )
+        .await;
     }
 
     /// Get a slice to the available output space.
     ///
     /// The future resolves once output space is available.
     /// The returned slice will never be empty.
-    pub async fn slice(&mut self) -> &mut [T] {
+    pub async fn slice(&mut self) -> &mut [T]
+    where
+        T: Pod,
+    {
         // ugly workaround for borrow-checker problem
         // https://github.com/rust-lang/rust/issues/21906
         let (p, s) = loop {
             match self.writer.slice(true) {
                 [] => {
-                    let _ = self.chan.next().await;
+                    self.wait().await;
                 }
                 s => break (s.as_mut_ptr(), s.len()),
             }
@@ -104,71 +466,1222 @@ impl<T> Writer<T> {
         unsafe { slice::from_raw_parts_mut(p, s) }
     }
 
-    /// Get a slice to the free slots, available for writing.
+    /// Get a slice to at least `n` items of output space.
     ///
-    /// This function return immediately. The slice might be [empty](slice::is_empty).
-    pub fn try_slice(&mut self) -> &mut [T] {
-        self.writer.slice(false)
+    /// The future resolves once enough output space is available.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub async fn space_at_least(&mut self, n: usize) -> &mut [T]
+    where
+        T: Pod,
+    {
+        // ugly workaround for borrow-checker problem
+        // https://github.com/rust-lang/rust/issues/21906
+        let (p, s) = loop {
+            match self.writer.slice_at_least(n, true) {
+                [] => {
+                    self.wait().await;
+                }
+                s => break (s.as_mut_ptr(), s.len()),
+            }
+        };
+        unsafe { slice::from_raw_parts_mut(p, s) }
     }
 
-    /// Indicates that `n` items were written to the output buffer.
+    /// Get a slice to a multiple of `n` items of output space.
     ///
-    /// It is ok if `n` is zero.
+    /// Space that does not fit a complete chunk is held back.
     ///
     /// # Panics
     ///
-    /// If produced more than space was available in the last provided slice.
-    pub fn produce(&mut self, n: usize) {
-        self.writer.produce(n, Vec::new());
+    /// If `n` is zero or exceeds the capacity of the buffer.
+    pub async fn slice_chunks(&mut self, n: usize) -> &mut [T]
+    where
+        T: Pod,
+    {
+        // ugly workaround for borrow-checker problem
+        // https://github.com/rust-lang/rust/issues/21906
+        let (p, s) = loop {
+            match self.writer.slice_chunks(n, true) {
+                [] => {
+                    self.wait().await;
+                }
+                s => break (s.as_mut_ptr(), s.len()),
+            }
+        };
+        unsafe { slice::from_raw_parts_mut(p, s) }
     }
-}
 
-/// Reader for an async circular buffer with items of type `T`.
-pub struct Reader<T> {
-    chan: Receiver<()>,
-    reader: generic::Reader<T, AsyncNotifier, NoMetadata>,
-}
+    /// Reserve `n` items of output space.
+    ///
+    /// The future resolves once enough output space is available. The returned
+    /// guard produces the items when it is dropped.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub async fn reserve(&mut self, n: usize) -> WriteGuard<'_, T, M>
+    where
+        T: Pod,
+    {
+        let ptr = self.space_at_least(n).await.as_mut_ptr();
+        WriteGuard {
+            writer: self,
+            ptr,
+            len: n,
+            n,
+        }
+    }
 
-impl<T> Reader<T> {
-    /// Blocks until there is data to read or until the writer is dropped.
+    /// Get a slice to the available output space, giving up after `timeout`.
     ///
-    /// If all data is read and the writer is dropped, all following calls will
-    /// return `None`. If `Some` is returned, the contained slice is never empty.
-    pub async fn slice(&mut self) -> Option<&[T]> {
+    /// The future resolves once output space is available. If the timeout
+    /// expires before that, the returned slice is empty. A `timeout` that is
+    /// too large to be represented as [Instant] waits without limit.
+    pub async fn slice_timeout(&mut self, timeout: Duration) -> &mut [T]
+    where
+        T: Pod,
+    {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.slice_deadline(deadline).await,
+            None => self.slice().await,
+        }
+    }
+
+    /// Get a slice to the available output space, giving up at `deadline`.
+    ///
+    /// The future resolves once output space is available. If the deadline
+    /// passes before that, the returned slice is empty.
+    pub async fn slice_deadline(&mut self, deadline: Instant) -> &mut [T]
+    where
+        T: Pod,
+    {
         // ugly workaround for borrow-checker problem
         // https://github.com/rust-lang/rust/issues/21906
-        let r = loop {
-            match self.reader.slice(true) {
-                Some(([], _)) => {
-                    let _ = self.chan.next().await;
+        let (p, s) = loop {
+            match self.writer.slice(true) {
+                [] => {
+                    if Instant::now() >= deadline {
+                        break (std::ptr::NonNull::dangling().as_ptr(), 0);
+                    }
+                    self.wait_until(deadline).await;
                 }
-                Some((s, _)) => break Some((s.as_ptr(), s.len())),
-                None => break None,
+                s => break (s.as_mut_ptr(), s.len()),
             }
         };
+        unsafe { slice::from_raw_parts_mut(p, s) }
+    }
 
-        if let Some((p, s)) = r {
-            unsafe { Some(slice::from_raw_parts(p, s)) }
-        } else {
+    /// Get a slice to the free slots, available for writing.
+    ///
+    /// This function return immediately. The slice might be [empty](slice::is_empty).
+    pub fn try_slice(&mut self) -> &mut [T]
+    where
+        T: Pod,
+    {
+        self.writer.slice(false)
+    }
+
+    /// Get a slice to at least `n` free slots, if they are available right
+    /// now.
+    ///
+    /// Returns `None`, if less than `n` slots or no slots at all are free. In
+    /// contrast to [try_slice](Writer::try_slice), the writer never requests a
+    /// notification. This allows producers of fixed-size frames to poll,
+    /// without writing partial frames.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub fn try_slice_min(&mut self, n: usize) -> Option<&mut [T]>
+    where
+        T: Pod,
+    {
+        let s = self.writer.slice_at_least(n, false);
+        if s.len() < n.max(1) {
             None
+        } else {
+            Some(s)
         }
     }
 
-    /// Checks if there is data to read.
+    /// Copy as many `items` as fit into the free slots and produce them.
     ///
-    /// If all data is read and the writer is dropped, all following calls will
-    /// return `None`. If there is no data to read, `Some` is returned with an
-    /// empty slice.
-    pub fn try_slice(&mut self) -> Option<&[T]> {
-        self.reader.slice(false).map(|x| x.0)
+    /// This function returns immediately with the number of produced items.
+    pub fn produce_from_slice(&mut self, items: &[T]) -> usize
+    where
+        T: Pod,
+    {
+        let s = self.writer.slice(false);
+        let n = std::cmp::min(s.len(), items.len());
+        s[0..n].copy_from_slice(&items[0..n]);
+        self.writer.produce(n, Vec::new());
+        n
     }
 
-    /// Indicates that `n` items were read.
+    /// Waits until output space is available, passes it to `f`, and produces
+    /// the number of items that `f` returns.
+    ///
+    /// Returns the number of produced items.
     ///
     /// # Panics
     ///
-    /// If consumed more than space was available in the last provided slice.
-    pub fn consume(&mut self, n: usize) {
-        self.reader.consume(n);
+    /// If `f` returns more than the length of the slice.
+    pub async fn write_with<F>(&mut self, f: F) -> usize
+    where
+        T: Pod,
+        F: FnOnce(&mut [T]) -> usize,
+    {
+        let n = f(self.slice().await);
+        self.produce(n);
+        n
+    }
+
+    /// Absolute number of items produced since the buffer was created.
+    ///
+    /// In contrast to the offsets in the circular buffer, the position does not
+    /// wrap and can be used to correlate items across buffers.
+    pub fn position(&self) -> u64 {
+        self.writer.position()
+    }
+
+    /// Number of items the buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.writer.capacity()
+    }
+
+    /// Grow the buffer, such that it can hold at least `min_items` items.
+    ///
+    /// See [generic::Writer::grow].
+    pub fn grow(&mut self, min_items: usize) -> Result<(), CircularError> {
+        self.writer.grow(min_items)
+    }
+
+    /// Only wake up the writer, once at least `n` items of space are free.
+    ///
+    /// Use `0` to wake up whenever space becomes available.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub fn set_watermark(&mut self, n: usize) {
+        self.writer.set_watermark(n);
+    }
+
+    /// Enable [Stats] for this writer and return them.
+    ///
+    /// Besides the items and occupancy, the stats record the time spent
+    /// waiting for notifications.
+    pub fn enable_stats(&mut self) -> Arc<Stats> {
+        let stats = self.writer.enable_stats();
+        self.stats = Some(stats.clone());
+        stats
+    }
+
+    /// [Stats] of this writer, if they were enabled.
+    pub fn stats(&self) -> Option<Arc<Stats>> {
+        self.writer.stats()
+    }
+
+    /// Detach readers that block the writer for longer than `timeout`.
+    ///
+    /// See [generic::Writer::set_stall_timeout].
+    ///
+    /// While the writer waits for output space in one of its async functions,
+    /// e.g., [slice](Writer::slice), it wakes up at least every `timeout` to
+    /// detect stalled readers.
+    ///
+    /// # Panics
+    ///
+    /// If `timeout` is set for a type that needs drop.
+    pub fn set_stall_timeout(&self, timeout: Option<Duration>) {
+        self.writer.set_stall_timeout(timeout)
+    }
+
+    /// Number of readers of the buffer.
+    ///
+    /// Shared readers count as one reader.
+    pub fn reader_count(&self) -> usize {
+        self.writer.reader_count()
+    }
+
+    /// Whether any reader is attached to the buffer.
+    pub fn has_readers(&self) -> bool {
+        self.writer.has_readers()
+    }
+
+    /// Number of produced items that each reader did not consume yet.
+    ///
+    /// Shared readers count as one reader.
+    pub fn reader_lags(&self) -> Vec<usize> {
+        self.writer.reader_lags()
+    }
+
+    /// [Identifier](crate::asynchronous::Reader::id) and lag of the reader that lags
+    /// the most and, therefore, throttles the writer.
+    ///
+    /// Lossy readers are not considered, since they never block the writer.
+    pub fn slowest_reader(&self) -> Option<(usize, usize)> {
+        self.writer.slowest_reader()
+    }
+
+    /// Number of items that can be written, without getting a slice.
+    pub fn free_items(&self) -> usize {
+        self.writer.free_items()
+    }
+
+    /// Indicates that `n` items were written to the output buffer.
+    ///
+    /// It is ok if `n` is zero.
+    ///
+    /// # Panics
+    ///
+    /// If produced more than space was available in the last provided slice.
+    pub fn produce(&mut self, n: usize) {
+        self.writer.produce(n, Vec::new());
+    }
+
+    /// Indicates that `n` items were written to the output buffer.
+    ///
+    /// In contrast to [produce](Writer::produce), an error is returned if
+    /// produced more than space was available in the last provided slice.
+    pub fn try_produce(&mut self, n: usize) -> Result<(), ProduceError> {
+        self.writer.try_produce(n, Vec::new())
+    }
+
+    /// Indicates that `n` items were written to the output buffer and
+    /// annotates them with `meta`.
+    ///
+    /// It is ok if `n` is zero.
+    ///
+    /// # Panics
+    ///
+    /// If produced more than space was available in the last provided slice.
+    pub fn produce_with_meta(&mut self, n: usize, meta: Vec<M::Item>) {
+        self.writer.produce(n, meta);
+    }
+}
+
+impl<M: Metadata> Writer<u8, M> {
+    /// Read once from `source` directly into the buffer and produce the read
+    /// bytes.
+    ///
+    /// Waits until output space is available. Reads that are
+    /// [Interrupted](io::ErrorKind::Interrupted) are retried. Returns the
+    /// number of produced bytes, which is zero, if `source` reached its end.
+    pub async fn fill_from<R: AsyncRead + Unpin + ?Sized>(
+        &mut self,
+        source: &mut R,
+    ) -> io::Result<usize> {
+        loop {
+            match source.read(self.slice().await).await {
+                Ok(n) => {
+                    self.produce(n);
+                    return Ok(n);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<M: Metadata> AsyncWrite for Writer<u8, M> {
+    /// Waits until output space is available and copies as many bytes as fit
+    /// into the buffer.
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        loop {
+            match this.writer.slice(true) {
+                [] => {
+                    if this.signal.poll_wait(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                }
+                s => {
+                    let n = std::cmp::min(s.len(), buf.len());
+                    s[0..n].copy_from_slice(&buf[0..n]);
+                    this.writer.produce(n, Vec::new());
+                    return Poll::Ready(Ok(n));
+                }
+            }
+        }
+    }
+
+    /// Waits until output space is available and copies as many bytes of
+    /// `bufs` as fit into the buffer, in one pass.
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if bufs.iter().all(|b| b.is_empty()) {
+            return Poll::Ready(Ok(0));
+        }
+        loop {
+            match this.writer.slice(true) {
+                [] => {
+                    if this.signal.poll_wait(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                }
+                s => {
+                    let n = generic::gather(s, bufs);
+                    this.writer.produce(n, Vec::new());
+                    return Poll::Ready(Ok(n));
+                }
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<M: Metadata> tokio::io::AsyncWrite for Writer<u8, M> {
+    /// Waits until output space is available and copies as many bytes as fit
+    /// into the buffer.
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(self, cx, buf)
+    }
+
+    /// Waits until output space is available and copies as many bytes of
+    /// `bufs` as fit into the buffer, in one pass.
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write_vectored(self, cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Output space of a [Writer], which is produced when the guard is dropped.
+///
+/// The guard dereferences to the reserved slice. By default, all reserved
+/// items are produced. Use [commit](WriteGuard::commit) to produce less. If
+/// the guard is dropped during a panic, no items are produced.
+pub struct WriteGuard<'a, T, M: Metadata = NoMetadata> {
+    writer: &'a mut Writer<T, M>,
+    ptr: *mut T,
+    len: usize,
+    n: usize,
+}
+
+impl<T, M: Metadata> WriteGuard<'_, T, M> {
+    /// Produce the first `n` items of the reserved slice.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the number of reserved items.
+    pub fn commit(mut self, n: usize) {
+        assert!(n <= self.len, "vmcircbuffer: produced too much");
+        self.n = n;
+    }
+}
+
+impl<T, M: Metadata> Deref for WriteGuard<'_, T, M> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T, M: Metadata> DerefMut for WriteGuard<'_, T, M> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl<T, M: Metadata> Drop for WriteGuard<'_, T, M> {
+    fn drop(&mut self) {
+        // do not publish a partially written slice
+        let n = if thread::panicking() { 0 } else { self.n };
+        self.writer.produce(n);
+    }
+}
+
+/// Reader for an async circular buffer with items of type `T` and [Metadata] of type `M`.
+pub struct Reader<T, M: Metadata = NoMetadata> {
+    signal: Arc<Signal>,
+    stats: Option<Arc<Stats>>,
+    reader: generic::Reader<T, AsyncNotifier, M>,
+}
+
+impl<T, M: Metadata> fmt::Debug for Reader<T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reader")
+            .field("reader", &self.reader)
+            .finish()
+    }
+}
+
+impl<T, M: Metadata> Reader<T, M> {
+    /// Blocks until there is data to read or until the writer is dropped.
+    ///
+    /// If all data is read and the writer is dropped, all following calls will
+    /// return `None`. If `Some` is returned, the contained slice is never empty.
+    /// A reader that the writer [detached](Reader::is_detached) also gets
+    /// `None`, while the writer is still running.
+    ///
+    /// With a [history](generic::Reader::set_history), the slice starts with
+    /// the history items and the call waits until there are new items.
+    pub async fn slice(&mut self) -> Option<&[T]> {
+        self.wait_slice(0, 1).await.map(|x| x.0)
+    }
+
+    /// Waits until there are new items to read, i.e., items after the
+    /// [history](generic::Reader::set_history), or until the writer is dropped.
+    ///
+    /// Returns the slice, the number of history items at its start, and the
+    /// metadata.
+    async fn wait_slice(&mut self, n: usize, chunk: usize) -> Option<(&[T], usize, M::View)> {
+        // ugly workaround for borrow-checker problem
+        // https://github.com/rust-lang/rust/issues/21906
+        let r = loop {
+            match self.reader.slice_parts(n, chunk, true) {
+                Some((s, h, _)) if s.len() == h => {
+                    self.signal.wait(self.stats.as_deref()).await;
+                }
+                Some((s, h, m)) => break Some((s.as_ptr(), s.len(), h, m)),
+                None => break None,
+            }
+        };
+
+        r.map(|(p, s, h, m)| unsafe { (slice::from_raw_parts(p, s), h, m) })
+    }
+
+    /// Waits until there are new items to read and returns them without the
+    /// [history](generic::Reader::set_history).
+    async fn slice_new(&mut self) -> Option<&[T]> {
+        self.wait_slice(0, 1).await.map(|(s, h, _)| &s[h..])
+    }
+
+    /// Waits until there are at least `n` items to read or until the writer is
+    /// dropped.
+    ///
+    /// If all data is read and the writer is dropped, all following calls will
+    /// return `None`. If `Some` is returned, the contained slice has at least
+    /// `n` items, unless the writer was dropped, in which case it contains the
+    /// remaining items.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer or the
+    /// [maximum chunk size](Reader::set_max_chunk).
+    pub async fn slice_at_least(&mut self, n: usize) -> Option<&[T]> {
+        self.wait_slice(n, 1).await.map(|x| x.0)
+    }
+
+    /// Waits until there is a multiple of `n` items to read.
+    ///
+    /// The remainder that does not form a complete chunk is held back. Returns
+    /// `None`, if the writer is dropped and less than `n` items are left. See
+    /// [generic::Reader::slice_chunks].
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero or exceeds the capacity of the buffer or the
+    /// [maximum chunk size](Reader::set_max_chunk).
+    pub async fn slice_chunks(&mut self, n: usize) -> Option<&[T]> {
+        self.wait_slice(n, n).await.map(|x| x.0)
+    }
+
+    /// Blocks until there is data to read or until the writer is dropped and
+    /// returns it together with its metadata.
+    ///
+    /// If all data is read and the writer is dropped, all following calls will
+    /// return `None`. If `Some` is returned, the contained slice is never empty.
+    pub async fn slice_with_meta(&mut self) -> Option<(&[T], M::View)> {
+        self.wait_slice(0, 1).await.map(|(s, _, m)| (s, m))
+    }
+
+    /// Waits until there is data to read or until the writer is dropped, giving
+    /// up after `timeout`.
+    ///
+    /// If all data is read and the writer is dropped, all following calls will
+    /// return `None`. If the timeout expires before data becomes available,
+    /// `Some` is returned with an empty slice. A `timeout` that is too large
+    /// to be represented as [Instant] waits without limit.
+    pub async fn slice_timeout(&mut self, timeout: Duration) -> Option<&[T]> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.slice_deadline(deadline).await,
+            None => self.slice().await,
+        }
+    }
+
+    /// Waits until there is data to read or until the writer is dropped, giving
+    /// up at `deadline`.
+    ///
+    /// If all data is read and the writer is dropped, all following calls will
+    /// return `None`. If the deadline passes before data becomes available,
+    /// `Some` is returned with a slice that holds only the
+    /// [history](generic::Reader::set_history), i.e., an empty slice without
+    /// history.
+    pub async fn slice_deadline(&mut self, deadline: Instant) -> Option<&[T]> {
+        let mut delay = Delay::new(deadline);
+        // ugly workaround for borrow-checker problem
+        // https://github.com/rust-lang/rust/issues/21906
+        let r = loop {
+            match self.reader.slice_parts(0, 1, true) {
+                Some((s, h, _)) if s.len() == h => {
+                    if Instant::now() >= deadline {
+                        break Some((s.as_ptr(), s.len()));
+                    }
+                    let _ = select(self.signal.wait(self.stats.as_deref()), &mut delay).await;
+                }
+                Some((s, _, _)) => break Some((s.as_ptr(), s.len())),
+                None => break None,
+            }
+        };
+
+        if let Some((p, s)) = r {
+            unsafe { Some(slice::from_raw_parts(p, s)) }
+        } else {
+            None
+        }
+    }
+
+    /// Checks if there is data to read.
+    ///
+    /// If all data is read and the writer is dropped, all following calls will
+    /// return `None`. If there is no data to read, `Some` is returned with an
+    /// empty slice, or with only the [history](generic::Reader::set_history).
+    pub fn try_slice(&mut self) -> Option<&[T]> {
+        self.reader.slice(false).map(|x| x.0)
+    }
+
+    /// Waits until there is data to read and returns a guard that consumes the
+    /// items when it is dropped.
+    ///
+    /// Returns `None` if all data is read and the writer is dropped.
+    pub async fn read(&mut self) -> Option<ReadGuard<'_, T, M>> {
+        let (ptr, len, history) = self
+            .wait_slice(0, 1)
+            .await
+            .map(|(s, h, _)| (s.as_ptr(), s.len(), h))?;
+        Some(ReadGuard {
+            reader: self,
+            ptr,
+            len,
+            history,
+            n: len - history,
+        })
+    }
+
+    /// Convert the reader into a [Stream] of owned chunks with at most
+    /// `chunk_size` items.
+    ///
+    /// The [history](generic::Reader::set_history) is not part of the chunks.
+    ///
+    /// # Panics
+    ///
+    /// If `chunk_size` is zero.
+    pub fn into_stream(self, chunk_size: usize) -> ReaderStream<T, M> {
+        assert!(chunk_size > 0, "vmcircbuffer: chunk size must not be zero");
+        ReaderStream {
+            reader: self,
+            chunk_size,
+        }
+    }
+
+    /// [Stream] of owned frames with exactly `n` items.
+    ///
+    /// Each frame is awaited until it is complete. The
+    /// [history](generic::Reader::set_history) is not part of the frames. Once
+    /// the writer is dropped, the stream ends. Remaining items that do not form a complete frame can
+    /// still be read with [slice](Reader::slice).
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero or exceeds the capacity of the buffer or the
+    /// [maximum chunk size](Reader::set_max_chunk).
+    pub fn frames(&mut self, n: usize) -> Frames<'_, T, M> {
+        assert!(n > 0, "vmcircbuffer: frames have to hold at least one item");
+        Frames { reader: self, n }
+    }
+
+    /// Copy available items into `dst` and consume them.
+    ///
+    /// This function returns immediately with the number of copied items. The
+    /// [history](generic::Reader::set_history) is not copied.
+    pub fn read_into(&mut self, dst: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let n = match self.reader.slice_parts(0, 1, false) {
+            Some((s, h, _)) => {
+                let s = &s[h..];
+                let n = std::cmp::min(s.len(), dst.len());
+                dst[0..n].copy_from_slice(&s[0..n]);
+                n
+            }
+            None => 0,
+        };
+        self.reader.consume(n);
+        n
+    }
+
+    /// Waits until there is data to read, passes it together with its metadata
+    /// to `f`, and consumes the number of items that `f` returns.
+    ///
+    /// Returns the number of consumed items or `None`, if all data is read and
+    /// the writer is dropped.
+    ///
+    /// The slice starts with the [history](generic::Reader::set_history), like
+    /// the one returned by [slice](Reader::slice).
+    ///
+    /// # Panics
+    ///
+    /// If `f` returns more than the number of new items of the slice.
+    pub async fn read_with<F>(&mut self, f: F) -> Option<usize>
+    where
+        F: FnOnce(&[T], M::View) -> usize,
+    {
+        let (s, meta) = self.slice_with_meta().await?;
+        let n = f(s, meta);
+        self.consume(n);
+        Some(n)
+    }
+
+    /// Absolute position of the reader in the stream, i.e., the number of
+    /// items produced before the first item that is read next.
+    ///
+    /// In contrast to the offsets in the circular buffer, the position does not
+    /// wrap and can be used to correlate items across buffers.
+    pub fn position(&self) -> u64 {
+        self.reader.position()
+    }
+
+    /// Number of items the buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.reader.capacity()
+    }
+
+    /// Identifier of the reader.
+    ///
+    /// See [generic::Reader::id].
+    pub fn id(&self) -> usize {
+        self.reader.id()
+    }
+
+    /// Whether all writers of the buffer are dropped.
+    ///
+    /// Items that were produced before can still be read.
+    pub fn writer_dropped(&self) -> bool {
+        self.reader.writer_dropped()
+    }
+
+    /// Whether the writer detached the reader, since it stalled.
+    ///
+    /// See [generic::Reader::is_detached].
+    pub fn is_detached(&self) -> bool {
+        self.reader.is_detached()
+    }
+
+    /// Pause the reader, such that it does not block the writer and skips
+    /// all items until it is resumed.
+    ///
+    /// See [generic::Reader::pause].
+    pub fn pause(&mut self) {
+        self.reader.pause()
+    }
+
+    /// Resume a paused reader. Returns the number of skipped items.
+    pub fn resume(&mut self) -> usize {
+        self.reader.resume()
+    }
+
+    /// Whether the reader is paused.
+    pub fn is_paused(&self) -> bool {
+        self.reader.is_paused()
+    }
+
+    /// Number of items that can be read, without getting a slice.
+    pub fn available(&self) -> usize {
+        self.reader.available()
+    }
+
+    /// Add a reader that shares the consumption cursor with this reader.
+    ///
+    /// See [add_shared_reader](crate::asynchronous::Writer::add_shared_reader).
+    ///
+    /// # Panics
+    ///
+    /// If this reader is not a shared reader.
+    pub fn share(&self) -> Reader<T, M> {
+        let signal = Arc::new(Signal::default());
+        let notifier = AsyncNotifier {
+            signal: signal.clone(),
+            armed: false,
+        };
+        Reader {
+            reader: self.reader.share(notifier),
+            signal,
+            stats: None,
+        }
+    }
+
+    /// Add a reader that starts at the current position of this reader.
+    ///
+    /// In contrast to [add_reader](crate::asynchronous::Writer::add_reader),
+    /// the new reader sees all items (and their metadata) that are not yet
+    /// consumed by this reader.
+    pub fn fork(&self) -> Reader<T, M>
+    where
+        M: Clone,
+    {
+        let signal = Arc::new(Signal::default());
+        let notifier = AsyncNotifier {
+            signal: signal.clone(),
+            armed: false,
+        };
+        Reader {
+            reader: self.reader.fork(notifier),
+            signal,
+            stats: None,
+        }
+    }
+
+    /// Number of items that were dropped since the last call, because the
+    /// reader fell behind.
+    ///
+    /// This is always zero for readers that are not
+    /// [lossy](crate::asynchronous::Writer::add_lossy_reader).
+    pub fn take_overrun(&mut self) -> usize {
+        self.reader.take_overrun()
+    }
+
+    /// Limit the number of items returned by a single slice call.
+    ///
+    /// Even if more data is available, slices will contain at most `n` items.
+    /// Use `usize::MAX` to remove the limit.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero.
+    pub fn set_max_chunk(&mut self, n: usize) {
+        self.reader.set_max_chunk(n);
+    }
+
+    /// Only wake up the reader, once at least `n` items are available or the
+    /// writer is dropped.
+    ///
+    /// Use `0` to wake up whenever items become available.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub fn set_watermark(&mut self, n: usize) {
+        self.reader.set_watermark(n);
+    }
+
+    /// Enable [Stats] for this reader and return them.
+    ///
+    /// Besides the items and occupancy, the stats record the time spent
+    /// waiting for notifications.
+    pub fn enable_stats(&mut self) -> Arc<Stats> {
+        let stats = self.reader.enable_stats();
+        self.stats = Some(stats.clone());
+        stats
+    }
+
+    /// [Stats] of this reader, if they were enabled.
+    pub fn stats(&self) -> Option<Arc<Stats>> {
+        self.reader.stats()
+    }
+
+    /// Checks if there is data to read and returns it together with its
+    /// metadata.
+    ///
+    /// If all data is read and the writer is dropped, all following calls will
+    /// return `None`. If there is no data to read, `Some` is returned with an
+    /// empty slice, or with only the [history](generic::Reader::set_history).
+    pub fn try_slice_with_meta(&mut self) -> Option<(&[T], M::View)> {
+        self.reader.slice(false)
+    }
+
+    /// Indicates that `n` items were read.
+    ///
+    /// # Panics
+    ///
+    /// If consumed more than space was available in the last provided slice.
+    pub fn consume(&mut self, n: usize) {
+        self.reader.consume(n);
+    }
+
+    /// Consume all items of the last provided slice.
+    ///
+    /// This saves storing the length of the slice, before calling
+    /// [consume](Reader::consume). Returns the number of consumed items.
+    pub fn consume_all(&mut self) -> usize {
+        self.reader.consume_all()
+    }
+
+    /// Copy all items that are available right now and consume them.
+    ///
+    /// Does not block. Returns an empty vector, if no items are available. The
+    /// [history](generic::Reader::set_history) is not copied.
+    pub fn drain(&mut self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let v = self
+            .reader
+            .slice_parts(0, 1, false)
+            .map(|(s, h, _)| s[h..].to_vec())
+            .unwrap_or_default();
+        self.consume_all();
+        v
+    }
+
+    /// Indicates that `n` items were read.
+    ///
+    /// In contrast to [consume](Reader::consume), an error is returned if
+    /// consumed more than space was available in the last provided slice.
+    pub fn try_consume(&mut self, n: usize) -> Result<(), ConsumeError> {
+        self.reader.try_consume(n)
+    }
+
+    /// Discard up to `n` items without getting a slice first.
+    ///
+    /// Returns the number of discarded items, which is less than `n`, if less
+    /// items are available.
+    pub fn discard(&mut self, n: usize) -> usize {
+        self.reader.discard(n)
+    }
+
+    /// Discard all available items, i.e., jump to the current position of the
+    /// writer.
+    ///
+    /// Returns the number of discarded items.
+    pub fn skip_to_latest(&mut self) -> usize {
+        self.reader.skip_to_latest()
+    }
+}
+
+impl<M: Metadata> Reader<u8, M> {
+    /// Write all data to `sink`, until the writer is dropped.
+    ///
+    /// Waits until data is available and hands it to `sink` without copying
+    /// it out of the buffer. Bytes are consumed as `sink` accepts them, so
+    /// partial writes are continued with the next call. Returns the number of
+    /// written bytes, once all data is read. `sink` is flushed before
+    /// returning.
+    ///
+    /// On error, the bytes that were accepted by `sink` before are consumed.
+    /// A `sink` that does not accept any bytes results in
+    /// [WriteZero](io::ErrorKind::WriteZero).
+    pub async fn drain_to<W: AsyncWrite + Unpin + ?Sized>(
+        &mut self,
+        sink: &mut W,
+    ) -> io::Result<usize> {
+        let mut total = 0;
+        while let Some(s) = self.slice_new().await {
+            match sink.write(s).await {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write whole buffer",
+                    ))
+                }
+                Ok(n) => {
+                    self.consume(n);
+                    total += n;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        sink.flush().await?;
+        Ok(total)
+    }
+}
+
+impl<M: Metadata> AsyncRead for Reader<u8, M> {
+    /// Waits until data is available and copies as many bytes as fit into
+    /// `buf`. Returns zero, once the writer is dropped and all data is read.
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        loop {
+            match this.reader.slice_parts(0, 1, true) {
+                Some((s, h, _)) if s.len() == h => {
+                    if this.signal.poll_wait(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                }
+                Some((s, h, _)) => {
+                    let s = &s[h..];
+                    let n = std::cmp::min(s.len(), buf.len());
+                    buf[0..n].copy_from_slice(&s[0..n]);
+                    this.reader.consume(n);
+                    return Poll::Ready(Ok(n));
+                }
+                None => return Poll::Ready(Ok(0)),
+            }
+        }
+    }
+
+    /// Waits until data is available and copies as many bytes as fit into
+    /// `bufs`, in one pass.
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if bufs.iter().all(|b| b.is_empty()) {
+            return Poll::Ready(Ok(0));
+        }
+        loop {
+            match this.reader.slice_parts(0, 1, true) {
+                Some((s, h, _)) if s.len() == h => {
+                    if this.signal.poll_wait(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                }
+                Some((s, h, _)) => {
+                    let s = &s[h..];
+                    let n = generic::scatter(s, bufs);
+                    this.reader.consume(n);
+                    return Poll::Ready(Ok(n));
+                }
+                None => return Poll::Ready(Ok(0)),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<M: Metadata> tokio::io::AsyncRead for Reader<u8, M> {
+    /// Waits until data is available and copies as many bytes as fit into
+    /// `buf`. Nothing is filled in, once the writer is dropped and all data is
+    /// read.
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        loop {
+            match this.reader.slice_parts(0, 1, true) {
+                Some((s, h, _)) if s.len() == h => {
+                    if this.signal.poll_wait(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                }
+                Some((s, h, _)) => {
+                    let s = &s[h..];
+                    let n = std::cmp::min(s.len(), buf.remaining());
+                    buf.put_slice(&s[0..n]);
+                    this.reader.consume(n);
+                    return Poll::Ready(Ok(()));
+                }
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<M: Metadata> tokio::io::AsyncBufRead for Reader<u8, M> {
+    /// Waits until data is available and returns it without copying it out of
+    /// the buffer. The slice is empty, once the writer is dropped and all data
+    /// is read.
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        loop {
+            match this.reader.slice_parts(0, 1, true) {
+                Some((s, h, _)) if s.len() == h => {
+                    if this.signal.poll_wait(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                }
+                Some((s, h, _)) => {
+                    let (p, n) = (s[h..].as_ptr(), s.len() - h);
+                    return Poll::Ready(Ok(unsafe { slice::from_raw_parts(p, n) }));
+                }
+                None => return Poll::Ready(Ok(&[])),
+            }
+        }
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        self.get_mut().reader.consume(amt);
+    }
+}
+
+/// [Stream] of owned chunks, returned by [into_stream](Reader::into_stream).
+pub struct ReaderStream<T, M: Metadata = NoMetadata> {
+    reader: Reader<T, M>,
+    chunk_size: usize,
+}
+
+impl<T: Clone, M: Metadata> Stream for ReaderStream<T, M> {
+    type Item = Vec<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Vec<T>>> {
+        let this = self.get_mut();
+        loop {
+            match this.reader.reader.slice_parts(0, 1, true) {
+                Some((s, h, _)) if s.len() == h => {
+                    if this.reader.signal.poll_wait(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                }
+                Some((s, h, _)) => {
+                    let s = &s[h..];
+                    let n = std::cmp::min(s.len(), this.chunk_size);
+                    let v = s[0..n].to_vec();
+                    this.reader.reader.consume(n);
+                    return Poll::Ready(Some(v));
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+/// [Stream] of owned frames, returned by [frames](Reader::frames).
+pub struct Frames<'a, T, M: Metadata = NoMetadata> {
+    reader: &'a mut Reader<T, M>,
+    n: usize,
+}
+
+impl<T: Clone, M: Metadata> Stream for Frames<'_, T, M> {
+    type Item = Vec<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Vec<T>>> {
+        let this = self.get_mut();
+        loop {
+            match this.reader.reader.slice_parts(this.n, this.n, true) {
+                Some((s, h, _)) if s.len() == h => {
+                    if this.reader.signal.poll_wait(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                }
+                Some((s, h, _)) => {
+                    let v = s[h..h + this.n].to_vec();
+                    this.reader.reader.consume(this.n);
+                    return Poll::Ready(Some(v));
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+/// Items of a [Reader], which are consumed when the guard is dropped.
+///
+/// The guard dereferences to the readable slice, which starts with the
+/// [history](crate::generic::Reader::set_history) of the reader. By default,
+/// all new items are consumed. Use [consume](ReadGuard::consume) to consume
+/// less.
+pub struct ReadGuard<'a, T, M: Metadata = NoMetadata> {
+    reader: &'a mut Reader<T, M>,
+    ptr: *const T,
+    len: usize,
+    history: usize,
+    n: usize,
+}
+
+impl<T, M: Metadata> ReadGuard<'_, T, M> {
+    /// Consume the first `n` new items of the slice, i.e., the items after
+    /// the [history](crate::generic::Reader::set_history).
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the number of new items in the slice.
+    pub fn consume(mut self, n: usize) {
+        assert!(
+            n <= self.len - self.history,
+            "vmcircbuffer: consumed too much!"
+        );
+        self.n = n;
+    }
+}
+
+impl<T, M: Metadata> Deref for ReadGuard<'_, T, M> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T, M: Metadata> Drop for ReadGuard<'_, T, M> {
+    fn drop(&mut self) {
+        self.reader.consume(self.n);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::task::noop_waker;
+
+    #[test]
+    fn delay_registration() {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut delay = Delay::new(Instant::now() + Duration::from_secs(60));
+
+        for _ in 0..10 {
+            assert!(Pin::new(&mut delay).poll(&mut cx).is_pending());
+        }
+        assert_eq!(TIMER.state.lock().unwrap().delays.len(), 1);
+
+        drop(delay);
+        assert!(TIMER.state.lock().unwrap().delays.is_empty());
+
+        // the timer thread exits without registrations
+        let start = Instant::now();
+        while TIMER.state.lock().unwrap().running {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(1));
+        }
     }
 }
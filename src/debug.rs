@@ -0,0 +1,85 @@
+//! Process-global registry of named buffers for inspection at runtime.
+//!
+//! Buffers are added with `register` on the [generic Writer](crate::generic::Writer)
+//! and listed with [buffers], e.g., from an admin thread or a debugger, to find
+//! stalled stages of a pipeline. Buffers are removed from the registry, once
+//! all their readers and writers are dropped.
+
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+/// Snapshot of the state of a registered buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BufferInfo {
+    /// Name given at registration.
+    pub name: String,
+    /// Number of items the buffer can hold.
+    pub capacity: usize,
+    /// Number of items that are not yet consumed by the slowest reader.
+    pub occupancy: usize,
+    /// Number of items that each reader did not consume yet.
+    pub reader_lags: Vec<usize>,
+    /// Absolute number of items produced.
+    pub position: u64,
+}
+
+/// Buffer in the registry.
+pub(crate) trait Inspect: Send + Sync {
+    /// Returns `None`, if the buffer was dropped.
+    fn inspect(&self) -> Option<BufferInfo>;
+    /// Whether the buffer was dropped.
+    fn is_dropped(&self) -> bool;
+}
+
+static REGISTRY: Lazy<Mutex<Vec<Box<dyn Inspect>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+pub(crate) fn register(buffer: Box<dyn Inspect>) {
+    let mut registry = REGISTRY.lock().unwrap();
+    // remove dropped buffers, even if the registry is never listed
+    registry.retain(|b| !b.is_dropped());
+    registry.push(buffer);
+}
+
+/// State of all live, registered buffers in the order of registration.
+pub fn buffers() -> Vec<BufferInfo> {
+    let mut registry = REGISTRY.lock().unwrap();
+    let mut infos = Vec::with_capacity(registry.len());
+    registry.retain(|b| match b.inspect() {
+        Some(info) => {
+            infos.push(info);
+            true
+        }
+        None => false,
+    });
+    infos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    struct Dummy(Arc<AtomicBool>);
+
+    impl Inspect for Dummy {
+        fn inspect(&self) -> Option<BufferInfo> {
+            None
+        }
+        fn is_dropped(&self) -> bool {
+            self.0.load(Ordering::SeqCst)
+        }
+    }
+
+    #[test]
+    fn prune_on_register() {
+        let dropped = Arc::new(AtomicBool::new(false));
+        register(Box::new(Dummy(dropped.clone())));
+        assert_eq!(Arc::strong_count(&dropped), 2);
+
+        // the entry is removed, when the next buffer is registered
+        dropped.store(true, Ordering::SeqCst);
+        register(Box::new(Dummy(Arc::new(AtomicBool::new(true)))));
+        assert_eq!(Arc::strong_count(&dropped), 1);
+    }
+}
@@ -1,9 +1,315 @@
 use std::marker::PhantomData;
 use std::mem;
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, IntoRawFd, OwnedFd, RawFd};
+#[cfg(windows)]
+use std::os::windows::io::{AsRawHandle, RawHandle};
+use std::path::PathBuf;
 use std::slice;
 
 use super::DoubleMappedBufferError;
 use super::DoubleMappedBufferImpl;
+use super::MemoryMapper;
+use super::MirroredFallbackBuffer;
+
+/// Memory object that backs the mapping.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backing {
+    /// Unlinked file in the [temp directory](std::env::temp_dir).
+    #[default]
+    TempFile,
+    /// POSIX shared memory object (`shm_open`), which is unlinked right away.
+    ///
+    /// This is useful, if creating files in the temp directory is not allowed,
+    /// e.g., for sandboxed apps. Only supported on Unix (except Android).
+    SharedMemory,
+    /// Anonymous memory that is remapped with `mach_vm_remap`.
+    ///
+    /// No file or file descriptor is involved, which works in sandboxed and
+    /// App Store contexts. Only supported on macOS and iOS. The buffer has no
+    /// file descriptor, i.e., `as_raw_fd()` returns `-1`.
+    MachVm,
+    /// Anonymous file created with `memfd_create`.
+    ///
+    /// The object can be [sealed](Builder::seal), which makes it well-suited
+    /// for sharing the buffer with other processes. Only supported on Linux and
+    /// Android.
+    Memfd,
+    /// Plain heap allocation, where the copies are kept in sync explicitly.
+    ///
+    /// This does not require any support for memory mappings but writers
+    /// have to [mirror](DoubleMappedBuffer::mirror) the items that they
+    /// produced. The buffer has no file descriptor or handle.
+    Heap,
+}
+
+/// Underlying memory of a [DoubleMappedBuffer].
+enum Storage {
+    Mapped(DoubleMappedBufferImpl),
+    Mirrored(MirroredFallbackBuffer),
+    Custom {
+        mapper: Box<dyn MemoryMapper>,
+        item_size: usize,
+    },
+}
+
+impl Storage {
+    fn addr(&self) -> usize {
+        match self {
+            Storage::Mapped(b) => b.addr(),
+            Storage::Mirrored(b) => b.addr(),
+            Storage::Custom { mapper, .. } => mapper.addr(),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        match self {
+            Storage::Mapped(b) => b.capacity(),
+            Storage::Mirrored(b) => b.capacity(),
+            Storage::Custom { mapper, item_size } => mapper.size_bytes() / item_size,
+        }
+    }
+
+    fn mappings(&self) -> usize {
+        match self {
+            Storage::Mapped(b) => b.mappings(),
+            Storage::Mirrored(b) => b.mappings(),
+            Storage::Custom { mapper, .. } => mapper.mappings(),
+        }
+    }
+
+    #[cfg(unix)]
+    fn fd(&self) -> RawFd {
+        match self {
+            Storage::Mapped(b) => b.fd(),
+            Storage::Mirrored(_) | Storage::Custom { .. } => -1,
+        }
+    }
+
+    #[cfg(windows)]
+    fn handle(&self) -> RawHandle {
+        match self {
+            Storage::Mapped(b) => b.handle(),
+            Storage::Mirrored(_) | Storage::Custom { .. } => std::ptr::null_mut(),
+        }
+    }
+}
+
+/// Builder for a [DoubleMappedBuffer].
+#[derive(Clone, Debug)]
+pub struct Builder {
+    pub(super) min_items: usize,
+    pub(super) backing: Backing,
+    pub(super) tmp_dir: Option<PathBuf>,
+    pub(super) lock: bool,
+    pub(super) prefault: bool,
+    pub(super) seal: bool,
+    pub(super) mappings: usize,
+    pub(super) fallback: bool,
+    pub(super) power_of_two: bool,
+    pub(super) alignment: Option<usize>,
+}
+
+impl Builder {
+    /// Create a builder for a buffer that can hold at least `min_items` items.
+    pub fn new(min_items: usize) -> Self {
+        Builder {
+            min_items,
+            backing: Backing::default(),
+            tmp_dir: None,
+            lock: false,
+            prefault: false,
+            seal: false,
+            mappings: 2,
+            fallback: false,
+            power_of_two: false,
+            alignment: None,
+        }
+    }
+
+    /// Minimum number of items the buffer can hold.
+    pub fn min_items(&self) -> usize {
+        self.min_items
+    }
+
+    /// Change the minimum number of items, keeping all other settings.
+    #[cfg(feature = "generic")]
+    pub(crate) fn with_min_items(mut self, min_items: usize) -> Self {
+        self.min_items = min_items;
+        self
+    }
+
+    /// Set the memory object that backs the mapping.
+    ///
+    /// This only has an effect on Unix-based systems.
+    pub fn backing(mut self, backing: Backing) -> Self {
+        self.backing = backing;
+        self
+    }
+
+    /// Set the directory, in which the [temp file](Backing::TempFile) is created.
+    ///
+    /// Defaults to [std::env::temp_dir]. This only has an effect on Unix-based
+    /// systems.
+    pub fn tmp_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.tmp_dir = Some(path.into());
+        self
+    }
+
+    /// Lock the buffer into memory (`mlock`/`VirtualLock`) after creation.
+    ///
+    /// This guarantees that accessing the buffer does not cause page faults,
+    /// which is important for real-time applications. If the pages cannot be
+    /// locked (e.g., due to `RLIMIT_MEMLOCK`), creation fails with
+    /// [DoubleMappedBufferError::Lock]. This also applies to
+    /// [heap](Backing::Heap) buffers, whose copies are all locked.
+    pub fn lock(mut self, lock: bool) -> Self {
+        self.lock = lock;
+        self
+    }
+
+    /// Map the buffer `mappings` times back-to-back (default: 2).
+    ///
+    /// With `k` mappings, [windows](DoubleMappedBuffer::window) of up to
+    /// `(k - 1) * capacity` items can be accessed from any offset within the
+    /// buffer, which is useful for algorithms with a long look-back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mappings` is smaller than 2.
+    pub fn mappings(mut self, mappings: usize) -> Self {
+        assert!(mappings >= 2, "at least two mappings are required");
+        self.mappings = mappings;
+        self
+    }
+
+    /// Touch all pages of all mappings after creation.
+    ///
+    /// This avoids page-fault latency spikes during the first pass through the
+    /// buffer at the cost of a slower setup.
+    pub fn prefault(mut self, prefault: bool) -> Self {
+        self.prefault = prefault;
+        self
+    }
+
+    /// Seal the size of the [memfd](Backing::Memfd) object.
+    ///
+    /// The seals `F_SEAL_SHRINK`, `F_SEAL_GROW`, and `F_SEAL_SEAL` are applied
+    /// after truncation. Processes that receive the
+    /// [file descriptor](DoubleMappedBuffer::as_raw_fd) can, therefore, rely on
+    /// the object not being resized under them. This only has an effect for the
+    /// memfd backing.
+    pub fn seal(mut self, seal: bool) -> Self {
+        self.seal = seal;
+        self
+    }
+
+    /// Fall back to a [heap](Backing::Heap) buffer, if the mapping fails.
+    ///
+    /// This allows applications to degrade gracefully on platforms, where
+    /// mappings are not permitted (e.g., due to seccomp filters, a `noexec` temp
+    /// directory, or file descriptor limits). If the buffer should be
+    /// [locked](Builder::lock), the heap buffer is locked as well. Other
+    /// errors, e.g., if the buffer cannot be locked or
+    /// [sealed](Builder::seal), are returned, since the heap buffer would not
+    /// provide these guarantees either.
+    pub fn fallback(mut self, fallback: bool) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Round the capacity up to a power of two.
+    ///
+    /// This allows to wrap offsets with a mask instead of a modulo operation,
+    /// which the circular buffers do automatically. The buffer can be
+    /// considerably larger than requested.
+    pub fn power_of_two(mut self, power_of_two: bool) -> Self {
+        self.power_of_two = power_of_two;
+        self
+    }
+
+    /// Guarantee an alignment of `bytes` bytes (e.g., 64 for AVX-512).
+    ///
+    /// The start of the buffer is always page-aligned. With an alignment that
+    /// is larger than the alignment of `T`, the circular buffers produce and
+    /// consume items in multiples of `bytes / size_of::<T>()`, i.e., all
+    /// slices start aligned. The alignment has to be a power of two, a multiple
+    /// of the size of `T`, and at most the [page size](super::pagesize).
+    /// Otherwise, building fails with [DoubleMappedBufferError::Alignment].
+    pub fn alignment(mut self, bytes: usize) -> Self {
+        self.alignment = Some(bytes);
+        self
+    }
+
+    /// Create the buffer.
+    ///
+    /// The memory of the buffer is zeroed for all backings, i.e., it never
+    /// exposes stale contents of the system or of the process.
+    pub fn build<T>(&self) -> Result<DoubleMappedBuffer<T>, DoubleMappedBufferError> {
+        let alignment = match self.alignment {
+            Some(a) if a > mem::align_of::<T>() => {
+                if !a.is_power_of_two()
+                    || !a.is_multiple_of(mem::size_of::<T>())
+                    || a > super::pagesize()
+                {
+                    return Err(DoubleMappedBufferError::Alignment);
+                }
+                a
+            }
+            _ => mem::align_of::<T>(),
+        };
+
+        let min_items = if self.power_of_two {
+            super::power_of_two_items(self.min_items, mem::size_of::<T>())?
+        } else {
+            self.min_items
+        };
+
+        let heap = || {
+            let mut buffer = MirroredFallbackBuffer::new(
+                min_items,
+                mem::size_of::<T>(),
+                mem::align_of::<T>(),
+                self.mappings,
+            )?;
+            if self.lock {
+                buffer.lock()?;
+            }
+            Ok::<_, DoubleMappedBufferError>(Storage::Mirrored(buffer))
+        };
+
+        let buffer = match self.backing {
+            Backing::Heap => heap()?,
+            _ => match DoubleMappedBufferImpl::new(
+                min_items,
+                mem::size_of::<T>(),
+                mem::align_of::<T>(),
+                self,
+            ) {
+                Ok(b) => Storage::Mapped(b),
+                Err(e) if self.fallback && e.is_mapping() => heap()?,
+                Err(e) => return Err(e),
+            },
+        };
+
+        if self.prefault {
+            let addr = buffer.addr() as *mut u8;
+            let len = buffer.mappings() * buffer.capacity() * mem::size_of::<T>();
+            for offset in (0..len).step_by(super::pagesize()) {
+                unsafe {
+                    let p = addr.add(offset);
+                    p.write_volatile(p.read_volatile());
+                }
+            }
+        }
+
+        Ok(DoubleMappedBuffer {
+            buffer,
+            alignment,
+            _p: PhantomData,
+        })
+    }
+}
 
 /// A buffer that is mapped twice, back-to-back in the virtual address space of the process.
 ///
@@ -11,7 +317,8 @@ use super::DoubleMappedBufferImpl;
 /// want to exploit the consequtive mappings to present available buffer space
 /// sequentially, without having to worry about wrapping.
 pub struct DoubleMappedBuffer<T> {
-    buffer: DoubleMappedBufferImpl,
+    buffer: Storage,
+    alignment: usize,
     _p: PhantomData<T>,
 }
 
@@ -20,15 +327,59 @@ impl<T> DoubleMappedBuffer<T> {
     ///
     /// The acutal capacity of the buffer will be the smallest multiple of the
     /// system page size and the item size that can hold at least `min_items`
-    /// items.
+    /// items. The memory of the buffer is zeroed.
     pub fn new(min_items: usize) -> Result<Self, DoubleMappedBufferError> {
-        match DoubleMappedBufferImpl::new(min_items, mem::size_of::<T>(), mem::align_of::<T>()) {
-            Ok(buffer) => Ok(DoubleMappedBuffer {
-                buffer,
-                _p: PhantomData,
-            }),
-            Err(e) => Err(e),
+        Builder::new(min_items).build()
+    }
+
+    /// Create a buffer that can hold at least `bytes` bytes.
+    ///
+    /// Like for [new](DoubleMappedBuffer::new), the size is rounded up to a
+    /// multiple of the system page size and the item size.
+    pub fn with_capacity_bytes(bytes: usize) -> Result<Self, DoubleMappedBufferError> {
+        let item_size = mem::size_of::<T>();
+        if item_size == 0 {
+            return Err(DoubleMappedBufferError::ZeroSized);
+        }
+        Self::new(bytes.div_ceil(item_size))
+    }
+
+    /// Map an existing memory object twice, back-to-back.
+    ///
+    /// The object (e.g., a dma-buf, a shared memory segment, or a file on
+    /// hugetlbfs) has to be at least `size_bytes` large, which has to be a
+    /// multiple of the [page size](super::pagesize) and the size of `T`. The
+    /// buffer takes ownership of the file descriptor and closes it on drop or
+    /// if the mapping fails. In contrast to [new](DoubleMappedBuffer::new), the
+    /// current contents of the object are kept.
+    #[cfg(unix)]
+    pub fn from_fd(fd: OwnedFd, size_bytes: usize) -> Result<Self, DoubleMappedBufferError> {
+        if mem::size_of::<T>() == 0 {
+            return Err(DoubleMappedBufferError::ZeroSized);
         }
+        let buffer = DoubleMappedBufferImpl::from_fd(
+            fd.into_raw_fd(),
+            size_bytes,
+            mem::size_of::<T>(),
+            mem::align_of::<T>(),
+            2,
+        )?;
+        Ok(DoubleMappedBuffer {
+            buffer: Storage::Mapped(buffer),
+            alignment: mem::align_of::<T>(),
+            _p: PhantomData,
+        })
+    }
+
+    /// Create a buffer that can hold at least `min_items` items, which is
+    /// mapped `mappings` times back-to-back.
+    ///
+    /// See [Builder::mappings].
+    pub fn with_mappings(
+        min_items: usize,
+        mappings: usize,
+    ) -> Result<Self, DoubleMappedBufferError> {
+        Builder::new(min_items).mappings(mappings).build()
     }
 
     /// Returns the slice corresponding to the first mapping of the buffer.
@@ -81,10 +432,159 @@ impl<T> DoubleMappedBuffer<T> {
         slice::from_raw_parts_mut((addr as *mut T).add(offset), self.buffer.capacity())
     }
 
+    /// View of `len` items of the mappings, starting at `offset`.
+    ///
+    /// # Safety
+    ///
+    /// Provides raw access to the slice. `offset + len` has to be <=
+    /// [mappings](DoubleMappedBuffer::mappings) * [capacity](DoubleMappedBuffer::capacity).
+    pub unsafe fn window(&self, offset: usize, len: usize) -> &[T] {
+        let addr = self.buffer.addr();
+        debug_assert_eq!(addr % mem::align_of::<T>(), 0);
+        debug_assert!(offset + len <= self.buffer.mappings() * self.buffer.capacity());
+        slice::from_raw_parts((addr as *const T).add(offset), len)
+    }
+
+    /// Mutable view of `len` items of the mappings, starting at `offset`.
+    ///
+    /// # Safety
+    ///
+    /// Provides raw access to the slice. `offset + len` has to be <=
+    /// [mappings](DoubleMappedBuffer::mappings) * [capacity](DoubleMappedBuffer::capacity).
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn window_mut(&self, offset: usize, len: usize) -> &mut [T] {
+        let addr = self.buffer.addr();
+        debug_assert_eq!(addr % mem::align_of::<T>(), 0);
+        debug_assert!(offset + len <= self.buffer.mappings() * self.buffer.capacity());
+        slice::from_raw_parts_mut((addr as *mut T).add(offset), len)
+    }
+
     /// The capacity of the buffer, i.e., how many items it can hold.
     pub fn capacity(&self) -> usize {
         self.buffer.capacity()
     }
+
+    /// The capacity of the buffer in bytes, i.e., the size of one mapping.
+    pub fn capacity_bytes(&self) -> usize {
+        self.buffer.capacity() * mem::size_of::<T>()
+    }
+
+    /// Size of an item in bytes.
+    pub fn item_size(&self) -> usize {
+        mem::size_of::<T>()
+    }
+
+    /// Guaranteed [alignment](Builder::alignment) in bytes.
+    ///
+    /// This is the alignment of `T`, if no larger alignment was requested.
+    pub fn alignment(&self) -> usize {
+        self.alignment
+    }
+
+    /// Number of back-to-back mappings of the buffer.
+    pub fn mappings(&self) -> usize {
+        self.buffer.mappings()
+    }
+
+    /// Whether the buffer is a [heap](Backing::Heap) buffer, whose copies have
+    /// to be [mirrored](DoubleMappedBuffer::mirror) explicitly.
+    pub fn is_mirrored(&self) -> bool {
+        matches!(self.buffer, Storage::Mirrored(_))
+    }
+
+    /// Create a buffer on top of memory that is provided by a [MemoryMapper].
+    ///
+    /// The size of a mapping has to be a non-zero multiple of the size of `T`
+    /// and the address has to be aligned for `T`.
+    /// The memory is used as is, i.e., it is not zeroed.
+    pub fn from_mapper(
+        mapper: impl MemoryMapper + 'static,
+    ) -> Result<Self, DoubleMappedBufferError> {
+        let item_size = mem::size_of::<T>();
+        if item_size == 0 {
+            return Err(DoubleMappedBufferError::ZeroSized);
+        }
+        let size = mapper.size_bytes();
+        if size == 0 || !size.is_multiple_of(item_size) || mapper.mappings() < 2 {
+            return Err(DoubleMappedBufferError::Size);
+        }
+        if !mapper.addr().is_multiple_of(mem::align_of::<T>()) {
+            return Err(DoubleMappedBufferError::Alignment);
+        }
+
+        Ok(DoubleMappedBuffer {
+            buffer: Storage::Custom {
+                mapper: Box::new(mapper),
+                item_size,
+            },
+            alignment: mem::align_of::<T>(),
+            _p: PhantomData,
+        })
+    }
+
+    /// Propagate the items `offset..offset + n` to all copies of the buffer.
+    ///
+    /// This has to be called after writing items. It is a no-op for mapped
+    /// buffers.
+    ///
+    /// # Safety
+    ///
+    /// `n` has to be <= [capacity](DoubleMappedBuffer::capacity) and
+    /// `offset + n` <= [mappings](DoubleMappedBuffer::mappings) * capacity. No
+    /// other thread may access the affected items concurrently.
+    pub unsafe fn mirror(&self, offset: usize, n: usize) {
+        match &self.buffer {
+            Storage::Mapped(_) => {}
+            Storage::Mirrored(b) => b.mirror(offset, n),
+            Storage::Custom { mapper, item_size } => {
+                mapper.mirror(offset * item_size, n * item_size)
+            }
+        }
+    }
+
+    /// Raw parts of the buffer: start address, size of one mapping in bytes,
+    /// and item size.
+    ///
+    /// The second mapping follows directly at `addr + len_bytes`. This can be
+    /// used to register the memory with other APIs, e.g., to pin it for DMA
+    /// with `cudaHostRegister`. Together with the backing file descriptor or
+    /// handle (see `AsRawFd`/`AsRawHandle`), this gives full access to the
+    /// memory. Accessing it through the pointer is subject to the same rules as
+    /// the unsafe slice accessors.
+    pub fn raw_parts(&self) -> (*mut T, usize, usize) {
+        (
+            self.buffer.addr() as *mut T,
+            self.buffer.capacity() * mem::size_of::<T>(),
+            mem::size_of::<T>(),
+        )
+    }
+}
+
+/// The file descriptor of the shared memory object that backs the buffer.
+///
+/// The object has a size of [capacity](DoubleMappedBuffer::capacity) items.
+/// Other processes, which receive the descriptor (e.g., through a Unix domain
+/// socket), can map it with `MAP_SHARED` to access the same memory. The
+/// descriptor is owned by the buffer and closed, when the buffer is dropped.
+#[cfg(unix)]
+impl<T> AsRawFd for DoubleMappedBuffer<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.buffer.fd()
+    }
+}
+
+/// The handle of the file mapping object that backs the buffer.
+///
+/// The object has a size of [capacity](DoubleMappedBuffer::capacity) items.
+/// Other processes, which receive a duplicate of the handle (see
+/// `DuplicateHandle`), can map it with `MapViewOfFile` to access the same
+/// memory. The handle is owned by the buffer and closed, when the buffer is
+/// dropped.
+#[cfg(windows)]
+impl<T> AsRawHandle for DoubleMappedBuffer<T> {
+    fn as_raw_handle(&self) -> RawHandle {
+        self.buffer.handle()
+    }
 }
 
 #[cfg(test)]
@@ -165,6 +665,368 @@ mod test {
         }
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn shared_fd() {
+        let b = DoubleMappedBuffer::<u32>::new(123).expect("failed to create buffer");
+        let size = b.capacity() * mem::size_of::<u32>();
+
+        unsafe {
+            let addr = libc::mmap(
+                std::ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                b.as_raw_fd(),
+                0,
+            );
+            assert_ne!(addr, libc::MAP_FAILED);
+
+            b.slice_mut()[7] = 42;
+            compiler_fence(Ordering::SeqCst);
+            assert_eq!(*(addr as *const u32).add(7), 42);
+            libc::munmap(addr, size);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tmp_dir() {
+        let b = Builder::new(123)
+            .tmp_dir(std::env::temp_dir())
+            .build::<u32>();
+        assert!(b.is_ok());
+
+        let b = Builder::new(123).tmp_dir("/does/not/exist").build::<u32>();
+        match b {
+            Err(DoubleMappedBufferError::Create(e)) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::NotFound)
+            }
+            _ => panic!("expected create error"),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn memfd_sealed() {
+        let b = Builder::new(123)
+            .backing(Backing::Memfd)
+            .seal(true)
+            .build::<u32>()
+            .expect("failed to create buffer");
+
+        unsafe {
+            b.slice_mut()[0] = 123;
+            compiler_fence(Ordering::SeqCst);
+            assert_eq!(b.slice_with_offset(b.capacity())[0], 123);
+
+            let seals = libc::fcntl(b.as_raw_fd(), libc::F_GET_SEALS);
+            assert_eq!(
+                seals,
+                libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_SEAL
+            );
+            assert!(libc::ftruncate(b.as_raw_fd(), 0) < 0);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn from_fd() {
+        use std::os::unix::io::FromRawFd;
+
+        let size = pagesize();
+        let name = std::ffi::CString::new("from_fd").unwrap();
+        let fd = unsafe {
+            let fd = libc::memfd_create(name.as_ptr(), libc::MFD_CLOEXEC);
+            assert!(fd >= 0);
+            assert_eq!(libc::ftruncate(fd, size as libc::off_t), 0);
+            OwnedFd::from_raw_fd(fd)
+        };
+
+        let b = DoubleMappedBuffer::<u32>::from_fd(fd, size).expect("failed to map fd");
+        assert_eq!(b.capacity(), size / 4);
+        unsafe {
+            b.slice_mut()[0] = 123;
+            compiler_fence(Ordering::SeqCst);
+            assert_eq!(b.slice_with_offset(b.capacity())[0], 123);
+        }
+
+        let fd = unsafe { OwnedFd::from_raw_fd(libc::memfd_create(name.as_ptr(), 0)) };
+        assert!(matches!(
+            DoubleMappedBuffer::<u32>::from_fd(fd, size + 1),
+            Err(DoubleMappedBufferError::Size)
+        ));
+    }
+
+    #[test]
+    fn mappings() {
+        let b = DoubleMappedBuffer::<u32>::with_mappings(123, 4).expect("failed to create buffer");
+        assert_eq!(b.mappings(), 4);
+        let cap = b.capacity();
+
+        unsafe {
+            for (i, v) in b.slice_mut().iter_mut().enumerate() {
+                *v = i as u32;
+            }
+            compiler_fence(Ordering::SeqCst);
+            let w = b.window(cap - 1, 3 * cap + 1);
+            for (i, v) in w.iter().enumerate() {
+                assert_eq!(*v as usize, (cap - 1 + i) % cap);
+            }
+        }
+    }
+
+    #[test]
+    fn heap() {
+        let b = Builder::new(123)
+            .backing(Backing::Heap)
+            .mappings(3)
+            .build::<u32>()
+            .expect("failed to create buffer");
+        assert!(b.is_mirrored());
+        let cap = b.capacity();
+
+        unsafe {
+            b.window_mut(cap - 2, 4).copy_from_slice(&[1, 2, 3, 4]);
+            b.mirror(cap - 2, 4);
+            assert_eq!(b.slice()[0..2], [3, 4]);
+            assert_eq!(b.slice()[cap - 2..], [1, 2]);
+            assert_eq!(b.window(2 * cap - 2, 4), [1, 2, 3, 4]);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fallback() {
+        let b = Builder::new(123)
+            .tmp_dir("/does/not/exist")
+            .fallback(true)
+            .build::<u32>()
+            .expect("failed to create buffer");
+        assert!(b.is_mirrored());
+        assert_eq!(b.as_raw_fd(), -1);
+    }
+
+    #[test]
+    fn raw_parts() {
+        let b = DoubleMappedBuffer::<u32>::new(123).expect("failed to create buffer");
+        let (addr, len, item_size) = b.raw_parts();
+        assert_eq!(item_size, 4);
+        assert_eq!(len, b.capacity() * 4);
+        assert_eq!(len % pagesize(), 0);
+
+        unsafe {
+            addr.write(123);
+            compiler_fence(Ordering::SeqCst);
+            assert_eq!(b.slice_with_offset(b.capacity())[0], 123);
+        }
+    }
+
+    #[test]
+    fn prefault() {
+        let b = Builder::new(12345)
+            .prefault(true)
+            .build::<u32>()
+            .expect("failed to create buffer");
+
+        unsafe {
+            assert!(b.slice().iter().all(|x| *x == 0));
+            b.slice_mut()[0] = 123;
+            compiler_fence(Ordering::SeqCst);
+            assert_eq!(b.slice_with_offset(b.capacity())[0], 123);
+        }
+    }
+
+    #[test]
+    fn lock() {
+        match Builder::new(123).lock(true).build::<u32>() {
+            Ok(b) => unsafe {
+                b.slice_mut()[0] = 123;
+                compiler_fence(Ordering::SeqCst);
+                assert_eq!(b.slice_with_offset(b.capacity())[0], 123);
+            },
+            // locking might not be permitted in the test environment
+            Err(e) => assert!(matches!(e, DoubleMappedBufferError::Lock(_))),
+        }
+    }
+
+    /// Number of bytes that are locked into memory by the process.
+    #[cfg(target_os = "linux")]
+    fn locked_bytes() -> usize {
+        let status = std::fs::read_to_string("/proc/self/status").unwrap();
+        let line = status.lines().find(|l| l.starts_with("VmLck:")).unwrap();
+        let kb: usize = line.split_whitespace().nth(1).unwrap().parse().unwrap();
+        kb * 1024
+    }
+
+    #[test]
+    fn lock_heap() {
+        match Builder::new(123)
+            .backing(Backing::Heap)
+            .lock(true)
+            .build::<u32>()
+        {
+            Ok(b) => {
+                assert!(b.is_mirrored());
+                #[cfg(target_os = "linux")]
+                assert!(locked_bytes() >= b.mappings() * b.capacity_bytes());
+            }
+            // locking might not be permitted in the test environment
+            Err(e) => assert!(matches!(e, DoubleMappedBufferError::Lock(_))),
+        }
+    }
+
+    #[test]
+    fn lock_fallback() {
+        // a failed lock is not hidden by the heap fallback
+        match Builder::new(123).lock(true).fallback(true).build::<u32>() {
+            Ok(b) => assert!(!b.is_mirrored()),
+            Err(e) => assert!(matches!(e, DoubleMappedBufferError::Lock(_))),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn lock_fallback_mapping() {
+        // a failed mapping falls back to a heap buffer that is locked as well
+        match Builder::new(123)
+            .tmp_dir("/does/not/exist")
+            .lock(true)
+            .fallback(true)
+            .build::<u32>()
+        {
+            Ok(b) => {
+                assert!(b.is_mirrored());
+                #[cfg(target_os = "linux")]
+                assert!(locked_bytes() >= b.mappings() * b.capacity_bytes());
+            }
+            Err(e) => assert!(matches!(e, DoubleMappedBufferError::Lock(_))),
+        }
+    }
+
+    #[cfg(all(unix, not(target_os = "android")))]
+    #[test]
+    fn shared_memory() {
+        let b = Builder::new(123)
+            .backing(Backing::SharedMemory)
+            .build::<u32>()
+            .expect("failed to create buffer");
+
+        unsafe {
+            b.slice_mut()[0] = 123;
+            compiler_fence(Ordering::SeqCst);
+            assert_eq!(b.slice_with_offset(b.capacity())[0], 123);
+        }
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    #[test]
+    fn mach_vm() {
+        let b = Builder::new(123)
+            .backing(Backing::MachVm)
+            .build::<u32>()
+            .expect("failed to create buffer");
+
+        unsafe {
+            b.slice_mut()[0] = 123;
+            compiler_fence(Ordering::SeqCst);
+            assert_eq!(b.slice_with_offset(b.capacity())[0], 123);
+        }
+    }
+
+    #[test]
+    fn zeroed() {
+        let mut backings = vec![Backing::TempFile, Backing::Heap];
+        #[cfg(all(unix, not(target_os = "android")))]
+        backings.push(Backing::SharedMemory);
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        backings.push(Backing::Memfd);
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        backings.push(Backing::MachVm);
+
+        for backing in backings {
+            // reuse memory of dropped buffers, if the system hands it out again
+            for _ in 0..2 {
+                let b = Builder::new(123)
+                    .backing(backing)
+                    .build::<u64>()
+                    .expect("failed to create buffer");
+                unsafe {
+                    assert!(b.slice_with_offset(0).iter().all(|v| *v == 0));
+                    assert!(b.slice_with_offset(b.capacity()).iter().all(|v| *v == 0));
+                    for v in b.slice_mut() {
+                        *v = u64::MAX;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn item_sizes() {
+        assert!(matches!(
+            DoubleMappedBuffer::<()>::new(123),
+            Err(DoubleMappedBufferError::ZeroSized)
+        ));
+        assert!(matches!(
+            Builder::new(123).backing(Backing::Heap).build::<()>(),
+            Err(DoubleMappedBufferError::ZeroSized)
+        ));
+        assert!(matches!(
+            DoubleMappedBuffer::<u64>::new(usize::MAX / 4),
+            Err(DoubleMappedBufferError::Overflow)
+        ));
+
+        let ps = pagesize();
+        let b = DoubleMappedBuffer::<[u8; 3]>::new(0).expect("failed to create buffer");
+        assert_eq!(b.capacity() * 3, 3 * ps);
+        let b = DoubleMappedBuffer::<[u8; 3]>::new(ps + 1).expect("failed to create buffer");
+        assert_eq!(b.capacity() * 3, 6 * ps);
+        let b = DoubleMappedBuffer::<u32>::new(ps / 4 + 1).expect("failed to create buffer");
+        assert_eq!(b.capacity() * 4, 2 * ps);
+    }
+
+    #[test]
+    fn capacity_bytes() {
+        let ps = pagesize();
+        let b = DoubleMappedBuffer::<u32>::with_capacity_bytes(ps + 1).unwrap();
+        assert_eq!(b.item_size(), 4);
+        assert_eq!(b.capacity_bytes(), 2 * ps);
+        assert_eq!(b.capacity(), 2 * ps / 4);
+
+        let b = DoubleMappedBuffer::<[u8; 3]>::with_capacity_bytes(ps).unwrap();
+        assert_eq!(b.item_size(), 3);
+        assert_eq!(b.capacity_bytes(), 3 * ps);
+
+        assert!(matches!(
+            DoubleMappedBuffer::<()>::with_capacity_bytes(ps),
+            Err(DoubleMappedBufferError::ZeroSized)
+        ));
+    }
+
+    #[test]
+    fn power_of_two() {
+        let ps = pagesize();
+        let b = Builder::new(ps + 1)
+            .power_of_two(true)
+            .build::<u32>()
+            .expect("failed to create buffer");
+        assert_eq!(b.capacity(), (ps + 1).next_power_of_two());
+
+        let b = Builder::new(10)
+            .power_of_two(true)
+            .build::<[u8; 3]>()
+            .expect("failed to create buffer");
+        assert_eq!(b.capacity(), ps);
+
+        let b = Builder::new(10)
+            .power_of_two(true)
+            .backing(Backing::Heap)
+            .build::<u64>()
+            .expect("failed to create buffer");
+        assert_eq!(b.capacity(), ps / 8);
+    }
+
     #[test]
     fn many_buffers() {
         let _b0 = DoubleMappedBuffer::<u32>::new(123).expect("failed to create buffer");
@@ -0,0 +1,141 @@
+use std::alloc::{alloc_zeroed, dealloc, Layout};
+use std::io;
+use std::ptr;
+
+#[cfg(windows)]
+use winapi::shared::minwindef::LPVOID;
+#[cfg(windows)]
+use winapi::um::memoryapi::{VirtualLock, VirtualUnlock};
+
+use super::buffer_size;
+use super::pagesize;
+use super::DoubleMappedBufferError;
+
+/// Heap allocation that emulates the double mapping.
+///
+/// The buffer is allocated `mappings` times back-to-back. Since the copies
+/// are not backed by the same memory, written items have to be
+/// [mirrored](MirroredFallbackBuffer::mirror) explicitly.
+#[derive(Debug)]
+pub struct MirroredFallbackBuffer {
+    addr: usize,
+    layout: Layout,
+    size_bytes: usize,
+    item_size: usize,
+    mappings: usize,
+    locked: bool,
+}
+
+impl MirroredFallbackBuffer {
+    pub fn new(
+        min_items: usize,
+        item_size: usize,
+        alignment: usize,
+        mappings: usize,
+    ) -> Result<Self, DoubleMappedBufferError> {
+        let size = buffer_size(min_items, item_size)?;
+
+        let layout = Layout::from_size_align(mappings * size, alignment.max(pagesize()))
+            .map_err(|_| DoubleMappedBufferError::Alignment)?;
+        let addr = unsafe { alloc_zeroed(layout) };
+        if addr.is_null() {
+            return Err(DoubleMappedBufferError::Placeholder(
+                io::ErrorKind::OutOfMemory.into(),
+            ));
+        }
+
+        Ok(MirroredFallbackBuffer {
+            addr: addr as usize,
+            layout,
+            size_bytes: size,
+            item_size,
+            mappings,
+            locked: false,
+        })
+    }
+
+    /// Lock all copies into memory (`mlock`/`VirtualLock`).
+    ///
+    /// They are unlocked again, when the buffer is dropped.
+    pub fn lock(&mut self) -> Result<(), DoubleMappedBufferError> {
+        #[cfg(unix)]
+        let ok = unsafe { libc::mlock(self.addr as *const libc::c_void, self.layout.size()) } == 0;
+        #[cfg(windows)]
+        let ok = unsafe { VirtualLock(self.addr as LPVOID, self.layout.size()) } != 0;
+        if !ok {
+            return Err(DoubleMappedBufferError::Lock(io::Error::last_os_error()));
+        }
+        self.locked = true;
+        Ok(())
+    }
+
+    pub fn addr(&self) -> usize {
+        self.addr
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.size_bytes / self.item_size
+    }
+
+    pub fn mappings(&self) -> usize {
+        self.mappings
+    }
+
+    /// Propagate the items `offset..offset + n` to all copies of the buffer.
+    ///
+    /// # Safety
+    ///
+    /// `n` has to be <= capacity, `offset + n` has to be <=
+    /// `mappings * capacity`, and no other thread may
+    /// access the affected items in any of the copies.
+    pub unsafe fn mirror(&self, offset: usize, n: usize) {
+        let cap = self.capacity();
+        let base = self.addr as *mut u8;
+        let is = self.item_size;
+        let end = offset + n;
+        debug_assert!(end <= self.mappings * cap);
+
+        // items written past the first copy are moved to the first copy; as
+        // n <= capacity, the items cover at most two ranges of the first copy
+        let mut ranges = [(0, 0); 2];
+        let mut len = 0;
+        if offset < cap {
+            ranges[0] = (offset, end.min(cap));
+            len = 1;
+        }
+        let mut i = offset.max(cap);
+        while i < end {
+            let e = ((i / cap + 1) * cap).min(end);
+            ptr::copy_nonoverlapping(base.add(i * is), base.add((i % cap) * is), (e - i) * is);
+            ranges[len] = (i % cap, i % cap + e - i);
+            len += 1;
+            i = e;
+        }
+
+        // first copy is replicated to the others
+        for &(s, e) in &ranges[..len] {
+            for j in 1..self.mappings {
+                ptr::copy_nonoverlapping(
+                    base.add(s * is),
+                    base.add((j * cap + s) * is),
+                    (e - s) * is,
+                );
+            }
+        }
+    }
+}
+
+impl Drop for MirroredFallbackBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            // the allocator might keep the pages, which would stay locked
+            if self.locked {
+                #[cfg(unix)]
+                libc::munlock(self.addr as *const libc::c_void, self.layout.size());
+                #[cfg(windows)]
+                VirtualUnlock(self.addr as LPVOID, self.layout.size());
+            }
+            dealloc(self.addr as *mut u8, self.layout);
+        }
+    }
+}
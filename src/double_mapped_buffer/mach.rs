@@ -0,0 +1,111 @@
+//! Double mapping with Mach VM primitives, which needs neither a file nor a
+//! file descriptor.
+
+use libc::{
+    boolean_t, kern_return_t, mach_vm_address_t, mach_vm_offset_t, mach_vm_size_t, vm_inherit_t,
+    vm_map_t, vm_prot_t,
+};
+
+use std::io;
+
+use super::DoubleMappedBufferError;
+
+extern "C" {
+    fn mach_vm_allocate(
+        target: vm_map_t,
+        address: *mut mach_vm_address_t,
+        size: mach_vm_size_t,
+        flags: libc::c_int,
+    ) -> kern_return_t;
+
+    fn mach_vm_deallocate(
+        target: vm_map_t,
+        address: mach_vm_address_t,
+        size: mach_vm_size_t,
+    ) -> kern_return_t;
+
+    #[allow(clippy::too_many_arguments)]
+    fn mach_vm_remap(
+        target_task: vm_map_t,
+        target_address: *mut mach_vm_address_t,
+        size: mach_vm_size_t,
+        mask: mach_vm_offset_t,
+        flags: libc::c_int,
+        src_task: vm_map_t,
+        src_address: mach_vm_address_t,
+        copy: boolean_t,
+        cur_protection: *mut vm_prot_t,
+        max_protection: *mut vm_prot_t,
+        inheritance: vm_inherit_t,
+    ) -> kern_return_t;
+}
+
+/// Allocate `mappings * size` bytes and remap the first `size` bytes onto the
+/// following blocks.
+pub fn map(
+    size: usize,
+    alignment: usize,
+    mappings: usize,
+) -> Result<usize, DoubleMappedBufferError> {
+    unsafe {
+        let task = libc::mach_task_self();
+        let mut addr: mach_vm_address_t = 0;
+
+        let ret = mach_vm_allocate(
+            task,
+            &mut addr,
+            (mappings * size) as mach_vm_size_t,
+            libc::VM_FLAGS_ANYWHERE,
+        );
+        if ret != libc::KERN_SUCCESS {
+            return Err(DoubleMappedBufferError::Placeholder(kern_error(ret)));
+        }
+
+        if !(addr as usize).is_multiple_of(alignment) {
+            unmap(addr as usize, size, mappings);
+            return Err(DoubleMappedBufferError::Alignment);
+        }
+
+        for i in 1..mappings {
+            let target = addr + (i * size) as mach_vm_address_t;
+            let mut next = target;
+            let mut cur_prot: vm_prot_t = 0;
+            let mut max_prot: vm_prot_t = 0;
+            let ret = mach_vm_remap(
+                task,
+                &mut next,
+                size as mach_vm_size_t,
+                0,
+                libc::VM_FLAGS_FIXED | libc::VM_FLAGS_OVERWRITE,
+                task,
+                addr,
+                0,
+                &mut cur_prot,
+                &mut max_prot,
+                libc::VM_INHERIT_SHARE as vm_inherit_t,
+            );
+            if ret != libc::KERN_SUCCESS || next != target {
+                unmap(addr as usize, size, mappings);
+                return Err(DoubleMappedBufferError::MapSecond(kern_error(ret)));
+            }
+        }
+
+        Ok(addr as usize)
+    }
+}
+
+/// Mach calls return a `kern_return_t` instead of setting `errno`.
+fn kern_error(ret: kern_return_t) -> io::Error {
+    io::Error::other(format!("kern_return_t {ret}"))
+}
+
+/// Release all blocks of a mapping created with [map].
+pub fn unmap(addr: usize, size: usize, mappings: usize) {
+    unsafe {
+        mach_vm_deallocate(
+            libc::mach_task_self(),
+            addr as mach_vm_address_t,
+            (mappings * size) as mach_vm_size_t,
+        );
+    }
+}
@@ -0,0 +1,38 @@
+/// Custom provider of the memory of a [DoubleMappedBuffer](super::DoubleMappedBuffer).
+///
+/// This allows to reuse the buffer (and the circular buffer implementations on
+/// top of it) with memory that is set up by the application, e.g., on
+/// platforms without support for the built-in mappings or with static memory.
+///
+/// # Safety
+///
+/// The memory at [addr](MemoryMapper::addr) has to be valid for reads and
+/// writes of [mappings](MemoryMapper::mappings) * [size_bytes](MemoryMapper::size_bytes)
+/// bytes for the lifetime of the mapper. Each block of `size_bytes` has to
+/// present the same data, either because it maps the same memory or because
+/// [mirror](MemoryMapper::mirror) copies it explicitly.
+pub unsafe trait MemoryMapper: Send + Sync {
+    /// Start address of the first mapping.
+    fn addr(&self) -> usize;
+
+    /// Size of one mapping in bytes.
+    fn size_bytes(&self) -> usize;
+
+    /// Number of back-to-back mappings.
+    fn mappings(&self) -> usize {
+        2
+    }
+
+    /// Propagate the bytes `offset..offset + len` to all mappings.
+    ///
+    /// This is called after data was written. The default implementation does
+    /// nothing, which is correct for memory that is actually mapped multiple
+    /// times.
+    ///
+    /// # Safety
+    ///
+    /// `len` has to be <= `size_bytes` and `offset + len` <=
+    /// `mappings * size_bytes`. No other thread may access the affected bytes
+    /// concurrently.
+    unsafe fn mirror(&self, _offset: usize, _len: usize) {}
+}
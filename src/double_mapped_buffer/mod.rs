@@ -2,49 +2,142 @@
 
 #[allow(clippy::module_inception)]
 mod double_mapped_buffer;
-pub use double_mapped_buffer::DoubleMappedBuffer;
+pub use double_mapped_buffer::{Backing, Builder, DoubleMappedBuffer};
+mod mapper;
+pub use mapper::MemoryMapper;
+mod pool;
+pub use pool::BufferPool;
+
+mod fallback;
+use fallback::MirroredFallbackBuffer;
 
 #[cfg(windows)]
 mod windows;
 #[cfg(windows)]
 use windows::DoubleMappedBufferImpl;
 
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod mach;
 #[cfg(unix)]
 mod unix;
 #[cfg(unix)]
 use unix::DoubleMappedBufferImpl;
 
+use std::io;
 use thiserror::Error;
 /// Errors that can occur when setting up the double mapping.
+///
+/// Errors of failed system calls carry the corresponding OS error.
 #[derive(Error, Debug)]
 pub enum DoubleMappedBufferError {
     /// Failed to close temp file.
-    #[error("Failed to close temp file.")]
-    Close,
+    #[error("Failed to close temp file: {0}")]
+    Close(#[source] io::Error),
     /// Failed to unmap second half.
-    #[error("Failed to unmap second half.")]
-    UnmapSecond,
+    #[error("Failed to unmap second half: {0}")]
+    UnmapSecond(#[source] io::Error),
     /// Failed to mmap second half.
-    #[error("Failed to mmap second half.")]
-    MapSecond,
+    #[error("Failed to mmap second half: {0}")]
+    MapSecond(#[source] io::Error),
     /// Failed to mmap first half.
-    #[error("Failed to mmap first half.")]
-    MapFirst,
+    #[error("Failed to mmap first half: {0}")]
+    MapFirst(#[source] io::Error),
     /// Failed to mmap placeholder.
-    #[error("Failed to mmap placeholder.")]
-    Placeholder,
+    #[error("Failed to mmap placeholder: {0}")]
+    Placeholder(#[source] io::Error),
     /// Failed to truncate temp file.
-    #[error("Failed to truncate temp file.")]
-    Truncate,
+    #[error("Failed to truncate temp file: {0}")]
+    Truncate(#[source] io::Error),
     /// Failed to unlink temp file.
-    #[error("Failed to unlinkt temp file.")]
-    Unlink,
+    #[error("Failed to unlink temp file: {0}")]
+    Unlink(#[source] io::Error),
     /// Failed to create temp file.
-    #[error("Failed to create temp file.")]
-    Create,
+    #[error("Failed to create temp file: {0}")]
+    Create(#[source] io::Error),
+    /// Failed to lock the buffer into memory.
+    #[error("Failed to lock buffer into memory: {0}")]
+    Lock(#[source] io::Error),
+    /// Failed to seal the memory object.
+    #[error("Failed to seal memory object: {0}")]
+    Seal(#[source] io::Error),
+    /// Size is not a multiple of the page size and the item size.
+    #[error("Size is not a multiple of the page size and the item size.")]
+    Size,
     /// Wrong alignment for data type.
     #[error("Wrong buffer alignment for data type.")]
     Alignment,
+    /// Zero-sized types are not supported.
+    #[error("Zero-sized types are not supported.")]
+    ZeroSized,
+    /// The size of the buffer overflows.
+    #[error("The size of the buffer overflows.")]
+    Overflow,
+}
+
+impl DoubleMappedBufferError {
+    /// Whether setting up the mapping itself failed, i.e., whether a
+    /// [heap](Backing::Heap) buffer can be used instead.
+    fn is_mapping(&self) -> bool {
+        matches!(
+            self,
+            DoubleMappedBufferError::Close(_)
+                | DoubleMappedBufferError::UnmapSecond(_)
+                | DoubleMappedBufferError::MapSecond(_)
+                | DoubleMappedBufferError::MapFirst(_)
+                | DoubleMappedBufferError::Placeholder(_)
+                | DoubleMappedBufferError::Truncate(_)
+                | DoubleMappedBufferError::Unlink(_)
+                | DoubleMappedBufferError::Create(_)
+        )
+    }
+}
+
+/// Size in bytes of a buffer for at least `min_items` items of `item_size`
+/// bytes.
+///
+/// This is the smallest multiple of the least common multiple of the page size
+/// and the item size that can hold the items.
+fn buffer_size(min_items: usize, item_size: usize) -> Result<usize, DoubleMappedBufferError> {
+    if item_size == 0 {
+        return Err(DoubleMappedBufferError::ZeroSized);
+    }
+
+    let ps = pagesize();
+    let lcm = (ps / gcd(ps, item_size))
+        .checked_mul(item_size)
+        .ok_or(DoubleMappedBufferError::Overflow)?;
+    let min_size = min_items
+        .checked_mul(item_size)
+        .ok_or(DoubleMappedBufferError::Overflow)?;
+    min_size
+        .div_ceil(lcm)
+        .max(1)
+        .checked_mul(lcm)
+        .ok_or(DoubleMappedBufferError::Overflow)
+}
+
+/// Smallest power of two that is at least `min_items` and, multiplied with
+/// `item_size`, a multiple of the page size.
+fn power_of_two_items(
+    min_items: usize,
+    item_size: usize,
+) -> Result<usize, DoubleMappedBufferError> {
+    let ps = pagesize();
+    let granule = ps / gcd(ps, item_size);
+    if !granule.is_power_of_two() {
+        return Err(DoubleMappedBufferError::Size);
+    }
+    min_items
+        .checked_next_power_of_two()
+        .map(|n| n.max(granule))
+        .ok_or(DoubleMappedBufferError::Overflow)
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
 }
 
 // =================== PAGESIZE ======================
@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ptr;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use super::Builder;
+use super::DoubleMappedBuffer;
+use super::DoubleMappedBufferError;
+
+/// Pool of [DoubleMappedBuffer]s that are reused instead of being unmapped.
+///
+/// Setting up and tearing down a double mapping requires several system
+/// calls. Applications that frequently create and drop buffers, e.g., dynamic
+/// flowgraphs that are reconfigured at runtime, can take buffers from a pool
+/// instead. The pool keeps a reference to every buffer it handed out. Once all
+/// other references are dropped, the buffer is idle and handed out again for a
+/// request that fits its capacity.
+///
+/// Idle buffers stay mapped until the pool is [trimmed](BufferPool::trim) or
+/// dropped. Reused buffers are zeroed, like newly created ones.
+pub struct BufferPool<T> {
+    builder: Builder,
+    max_idle: usize,
+    buffers: Mutex<BTreeMap<usize, Vec<Arc<DoubleMappedBuffer<T>>>>>,
+}
+
+impl<T> BufferPool<T> {
+    /// Create a pool, whose buffers use the default [Builder] settings.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self::with_builder(&Builder::new(0))
+    }
+
+    /// Create a pool, whose buffers are configured by `builder`.
+    ///
+    /// The minimum number of items of the builder is ignored, it is set for
+    /// each [get](BufferPool::get).
+    pub fn with_builder(builder: &Builder) -> Self {
+        BufferPool {
+            builder: builder.clone(),
+            max_idle: usize::MAX,
+            buffers: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Limit the number of idle buffers that are kept for reuse.
+    ///
+    /// Surplus idle buffers are unmapped on the next [get](BufferPool::get)
+    /// or [trim](BufferPool::trim).
+    pub fn max_idle(mut self, max_idle: usize) -> Self {
+        self.max_idle = max_idle;
+        self
+    }
+
+    /// Get a buffer that can hold at least `min_items` items.
+    ///
+    /// The smallest idle buffer with sufficient capacity is reused. Only if
+    /// there is none, a new buffer is created.
+    pub fn get(
+        &self,
+        min_items: usize,
+    ) -> Result<Arc<DoubleMappedBuffer<T>>, DoubleMappedBufferError> {
+        let mut buffers = self.buffers.lock().unwrap();
+        Self::release_surplus(&mut buffers, self.max_idle);
+
+        for pool in buffers.range(min_items..).map(|(_, v)| v) {
+            if let Some(b) = pool.iter().find(|b| Arc::strong_count(b) == 1) {
+                unsafe {
+                    ptr::write_bytes(b.raw_parts().0, 0, b.capacity());
+                    b.mirror(0, b.capacity());
+                }
+                return Ok(b.clone());
+            }
+        }
+
+        let mut builder = self.builder.clone();
+        builder.min_items = min_items;
+        let b = Arc::new(builder.build()?);
+        buffers.entry(b.capacity()).or_default().push(b.clone());
+        Ok(b)
+    }
+
+    /// Number of buffers that are currently not in use.
+    pub fn idle(&self) -> usize {
+        self.buffers
+            .lock()
+            .unwrap()
+            .values()
+            .flatten()
+            .filter(|b| Arc::strong_count(b) == 1)
+            .count()
+    }
+
+    /// Number of buffers of the pool, including the ones that are in use.
+    pub fn len(&self) -> usize {
+        self.buffers.lock().unwrap().values().map(Vec::len).sum()
+    }
+
+    /// Whether the pool holds no buffers.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Unmap all idle buffers.
+    pub fn trim(&self) {
+        Self::release_surplus(&mut self.buffers.lock().unwrap(), 0);
+    }
+
+    fn release_surplus(
+        buffers: &mut BTreeMap<usize, Vec<Arc<DoubleMappedBuffer<T>>>>,
+        max_idle: usize,
+    ) {
+        let mut idle = 0;
+        for pool in buffers.values_mut() {
+            pool.retain(|b| {
+                if Arc::strong_count(b) > 1 {
+                    return true;
+                }
+                idle += 1;
+                idle <= max_idle
+            });
+        }
+        buffers.retain(|_, v| !v.is_empty());
+    }
+}
+
+impl<T> fmt::Debug for BufferPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("len", &self.len())
+            .field("idle", &self.idle())
+            .field("max_idle", &self.max_idle)
+            .finish()
+    }
+}
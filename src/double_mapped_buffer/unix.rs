@@ -1,15 +1,24 @@
 use std::ffi::CString;
+use std::io;
 use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::RawFd;
 use std::path::PathBuf;
+#[cfg(not(target_os = "android"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+use super::buffer_size;
 use super::pagesize;
+use super::Backing;
+use super::Builder;
 use super::DoubleMappedBufferError;
 
 #[derive(Debug)]
 pub struct DoubleMappedBufferImpl {
     addr: usize,
+    fd: RawFd,
     size_bytes: usize,
     item_size: usize,
+    mappings: usize,
 }
 
 impl DoubleMappedBufferImpl {
@@ -17,120 +26,284 @@ impl DoubleMappedBufferImpl {
         min_items: usize,
         item_size: usize,
         alignment: usize,
+        options: &Builder,
     ) -> Result<Self, DoubleMappedBufferError> {
+        let mut ret = Self::new_try(min_items, item_size, alignment, options);
         for _ in 0..5 {
-            let ret = Self::new_try(min_items, item_size, alignment);
             if ret.is_ok() {
-                return ret;
+                break;
             }
+            ret = Self::new_try(min_items, item_size, alignment, options);
         }
-        Self::new_try(min_items, item_size, alignment)
+
+        let buffer = ret?;
+        if options.lock {
+            let ret = unsafe {
+                libc::mlock(
+                    buffer.addr as *const libc::c_void,
+                    buffer.mappings * buffer.size_bytes,
+                )
+            };
+            if ret < 0 {
+                let e = io::Error::last_os_error();
+                return Err(DoubleMappedBufferError::Lock(e));
+            }
+        }
+        Ok(buffer)
     }
 
     fn new_try(
         min_items: usize,
         item_size: usize,
         alignment: usize,
+        options: &Builder,
+    ) -> Result<Self, DoubleMappedBufferError> {
+        let size = buffer_size(min_items, item_size)?;
+
+        let fd = match options.backing {
+            Backing::TempFile => Self::create_temp_file(size, options)?,
+            Backing::SharedMemory => Self::create_shared_memory(size)?,
+            Backing::Memfd => Self::create_memfd(size, options)?,
+            Backing::Heap => {
+                return Err(DoubleMappedBufferError::Create(
+                    io::ErrorKind::Unsupported.into(),
+                ))
+            }
+            Backing::MachVm => {
+                return Self::with_mach_vm(size, item_size, alignment, options.mappings)
+            }
+        };
+
+        Self::map_fd(fd, size, item_size, alignment, options.mappings)
+    }
+
+    pub fn from_fd(
+        fd: RawFd,
+        size: usize,
+        item_size: usize,
+        alignment: usize,
+        mappings: usize,
     ) -> Result<Self, DoubleMappedBufferError> {
-        let ps = pagesize();
-        let mut size = ps;
-        while size < min_items * item_size || size % item_size != 0 {
-            size += ps;
+        if size == 0 || !size.is_multiple_of(pagesize()) || !size.is_multiple_of(item_size) {
+            unsafe {
+                libc::close(fd);
+            }
+            return Err(DoubleMappedBufferError::Size);
         }
 
-        let tmp = std::env::temp_dir();
+        Self::map_fd(fd, size, item_size, alignment, mappings)
+    }
+
+    fn create_temp_file(size: usize, options: &Builder) -> Result<RawFd, DoubleMappedBufferError> {
+        let tmp = options.tmp_dir.clone().unwrap_or_else(std::env::temp_dir);
         let mut path = PathBuf::new();
         path.push(tmp);
         path.push("buffer-XXXXXX");
         let cstring = CString::new(path.into_os_string().as_bytes()).unwrap();
         let path = cstring.as_bytes_with_nul().as_ptr();
 
-        let fd;
-        let buff;
         unsafe {
-            fd = libc::mkstemp(path as *mut libc::c_char);
+            let fd = libc::mkstemp(path as *mut libc::c_char);
             if fd < 0 {
-                return Err(DoubleMappedBufferError::Create);
+                let e = io::Error::last_os_error();
+                return Err(DoubleMappedBufferError::Create(e));
             }
 
             let ret = libc::unlink(path.cast::<libc::c_char>());
             if ret < 0 {
+                let e = io::Error::last_os_error();
                 libc::close(fd);
-                return Err(DoubleMappedBufferError::Unlink);
+                return Err(DoubleMappedBufferError::Unlink(e));
             }
 
-            let ret = libc::ftruncate(fd, 2 * size as libc::off_t);
+            let ret = libc::ftruncate(fd, size as libc::off_t);
             if ret < 0 {
+                let e = io::Error::last_os_error();
                 libc::close(fd);
-                return Err(DoubleMappedBufferError::Truncate);
+                return Err(DoubleMappedBufferError::Truncate(e));
             }
 
-            buff = libc::mmap(
-                std::ptr::null_mut::<libc::c_void>(),
-                2 * size,
-                libc::PROT_READ | libc::PROT_WRITE,
-                libc::MAP_SHARED,
-                fd,
-                0,
+            Ok(fd)
+        }
+    }
+
+    #[cfg(target_os = "android")]
+    fn create_shared_memory(_size: usize) -> Result<RawFd, DoubleMappedBufferError> {
+        Err(DoubleMappedBufferError::Create(
+            io::ErrorKind::Unsupported.into(),
+        ))
+    }
+
+    #[cfg(not(target_os = "android"))]
+    fn create_shared_memory(size: usize) -> Result<RawFd, DoubleMappedBufferError> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let name = CString::new(format!(
+            "/vmcircbuffer-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ))
+        .unwrap();
+
+        unsafe {
+            let fd = libc::shm_open(
+                name.as_ptr(),
+                libc::O_RDWR | libc::O_CREAT | libc::O_EXCL,
+                0o600 as libc::c_uint,
             );
-            if buff == libc::MAP_FAILED {
+            if fd < 0 {
+                let e = io::Error::last_os_error();
+                return Err(DoubleMappedBufferError::Create(e));
+            }
+
+            let ret = libc::shm_unlink(name.as_ptr());
+            if ret < 0 {
+                let e = io::Error::last_os_error();
                 libc::close(fd);
-                return Err(DoubleMappedBufferError::Placeholder);
+                return Err(DoubleMappedBufferError::Unlink(e));
             }
-            if buff as usize % alignment != 0 {
+
+            // shared memory objects can only be truncated once on some platforms
+            let ret = libc::ftruncate(fd, size as libc::off_t);
+            if ret < 0 {
+                let e = io::Error::last_os_error();
                 libc::close(fd);
-                return Err(DoubleMappedBufferError::Alignment);
+                return Err(DoubleMappedBufferError::Truncate(e));
+            }
+
+            Ok(fd)
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn create_memfd(_size: usize, _options: &Builder) -> Result<RawFd, DoubleMappedBufferError> {
+        Err(DoubleMappedBufferError::Create(
+            io::ErrorKind::Unsupported.into(),
+        ))
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn create_memfd(size: usize, options: &Builder) -> Result<RawFd, DoubleMappedBufferError> {
+        let name = CString::new("vmcircbuffer").unwrap();
+
+        unsafe {
+            let fd = libc::memfd_create(name.as_ptr(), libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING);
+            if fd < 0 {
+                let e = io::Error::last_os_error();
+                return Err(DoubleMappedBufferError::Create(e));
             }
 
-            let ret = libc::munmap(buff.add(size), size);
+            let ret = libc::ftruncate(fd, size as libc::off_t);
             if ret < 0 {
-                libc::munmap(buff, size);
+                let e = io::Error::last_os_error();
                 libc::close(fd);
-                return Err(DoubleMappedBufferError::UnmapSecond);
+                return Err(DoubleMappedBufferError::Truncate(e));
             }
 
-            #[cfg(target_os = "freebsd")]
-            let buff2 = libc::mmap(
-                buff.add(size),
-                size,
-                libc::PROT_READ | libc::PROT_WRITE,
-                libc::MAP_SHARED | libc::MAP_FIXED,
-                fd,
-                0,
-            );
-            #[cfg(not(target_os = "freebsd"))]
-            let buff2 = libc::mmap(
-                buff.add(size),
-                size,
-                libc::PROT_READ | libc::PROT_WRITE,
-                libc::MAP_SHARED,
-                fd,
+            if options.seal {
+                let ret = libc::fcntl(
+                    fd,
+                    libc::F_ADD_SEALS,
+                    libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_SEAL,
+                );
+                if ret < 0 {
+                    let e = io::Error::last_os_error();
+                    libc::close(fd);
+                    return Err(DoubleMappedBufferError::Seal(e));
+                }
+            }
+
+            Ok(fd)
+        }
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    fn with_mach_vm(
+        size: usize,
+        item_size: usize,
+        alignment: usize,
+        mappings: usize,
+    ) -> Result<Self, DoubleMappedBufferError> {
+        let addr = super::mach::map(size, alignment, mappings)?;
+        Ok(DoubleMappedBufferImpl {
+            addr,
+            fd: -1,
+            size_bytes: size,
+            item_size,
+            mappings,
+        })
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "ios")))]
+    fn with_mach_vm(
+        _size: usize,
+        _item_size: usize,
+        _alignment: usize,
+        _mappings: usize,
+    ) -> Result<Self, DoubleMappedBufferError> {
+        Err(DoubleMappedBufferError::Create(
+            io::ErrorKind::Unsupported.into(),
+        ))
+    }
+
+    /// Map the memory object `fd` of `size` bytes `mappings` times, back-to-back.
+    ///
+    /// The file descriptor is closed on failure.
+    fn map_fd(
+        fd: RawFd,
+        size: usize,
+        item_size: usize,
+        alignment: usize,
+        mappings: usize,
+    ) -> Result<Self, DoubleMappedBufferError> {
+        let buff;
+        unsafe {
+            buff = libc::mmap(
+                std::ptr::null_mut::<libc::c_void>(),
+                mappings * size,
+                libc::PROT_NONE,
+                libc::MAP_PRIVATE | libc::MAP_ANON,
+                -1,
                 0,
             );
-            if buff2 != buff.add(size) {
-                libc::munmap(buff, size);
+            if buff == libc::MAP_FAILED {
+                let e = io::Error::last_os_error();
                 libc::close(fd);
-                return Err(DoubleMappedBufferError::MapSecond);
+                return Err(DoubleMappedBufferError::Placeholder(e));
             }
-
-            let ret = libc::ftruncate(fd, size as libc::off_t);
-            if ret < 0 {
-                libc::munmap(buff, size);
-                libc::munmap(buff2, size);
+            if !(buff as usize).is_multiple_of(alignment) {
+                libc::munmap(buff, mappings * size);
                 libc::close(fd);
-                return Err(DoubleMappedBufferError::Truncate);
+                return Err(DoubleMappedBufferError::Alignment);
             }
 
-            let ret = libc::close(fd);
-            if ret < 0 {
-                return Err(DoubleMappedBufferError::Close);
+            for i in 0..mappings {
+                let b = libc::mmap(
+                    buff.add(i * size),
+                    size,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED | libc::MAP_FIXED,
+                    fd,
+                    0,
+                );
+                if b != buff.add(i * size) {
+                    let e = io::Error::last_os_error();
+                    libc::munmap(buff, mappings * size);
+                    libc::close(fd);
+                    return if i == 0 {
+                        Err(DoubleMappedBufferError::MapFirst(e))
+                    } else {
+                        Err(DoubleMappedBufferError::MapSecond(e))
+                    };
+                }
             }
         }
 
         Ok(DoubleMappedBufferImpl {
             addr: buff as usize,
+            fd,
             size_bytes: size,
             item_size,
+            mappings,
         })
     }
 
@@ -141,12 +314,30 @@ impl DoubleMappedBufferImpl {
     pub fn capacity(&self) -> usize {
         self.size_bytes / self.item_size
     }
+
+    pub fn mappings(&self) -> usize {
+        self.mappings
+    }
+
+    pub fn fd(&self) -> RawFd {
+        self.fd
+    }
 }
 
 impl Drop for DoubleMappedBufferImpl {
     fn drop(&mut self) {
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        if self.fd < 0 {
+            super::mach::unmap(self.addr, self.size_bytes, self.mappings);
+            return;
+        }
+
         unsafe {
-            libc::munmap(self.addr as *mut libc::c_void, self.size_bytes * 2);
+            libc::munmap(
+                self.addr as *mut libc::c_void,
+                self.mappings * self.size_bytes,
+            );
+            libc::close(self.fd);
         }
     }
 }
@@ -1,3 +1,6 @@
+use std::io;
+use std::os::windows::io::RawHandle;
+
 use winapi::shared::minwindef::DWORD;
 use winapi::shared::minwindef::LPCVOID;
 use winapi::shared::minwindef::LPVOID;
@@ -6,6 +9,7 @@ use winapi::um::handleapi::INVALID_HANDLE_VALUE;
 use winapi::um::memoryapi::MapViewOfFileEx;
 use winapi::um::memoryapi::VirtualAlloc;
 use winapi::um::memoryapi::VirtualFree;
+use winapi::um::memoryapi::VirtualLock;
 use winapi::um::winnt::HANDLE;
 use winapi::um::winnt::MEM_RELEASE;
 use winapi::um::winnt::MEM_RESERVE;
@@ -16,7 +20,8 @@ use winapi::um::{
     winbase::CreateFileMappingA,
 };
 
-use super::pagesize;
+use super::buffer_size;
+use super::Builder;
 use super::DoubleMappedBufferError;
 
 #[derive(Debug)]
@@ -25,6 +30,7 @@ pub struct DoubleMappedBufferImpl {
     handle: usize,
     size_bytes: usize,
     item_size: usize,
+    mappings: usize,
 }
 
 impl DoubleMappedBufferImpl {
@@ -32,26 +38,35 @@ impl DoubleMappedBufferImpl {
         min_items: usize,
         item_size: usize,
         alignment: usize,
+        options: &Builder,
     ) -> Result<Self, DoubleMappedBufferError> {
+        let mut ret = Self::new_try(min_items, item_size, alignment, options.mappings);
         for _ in 0..5 {
-            let ret = Self::new_try(min_items, item_size, alignment);
             if ret.is_ok() {
-                return ret;
+                break;
+            }
+            ret = Self::new_try(min_items, item_size, alignment, options.mappings);
+        }
+
+        let buffer = ret?;
+        if options.lock {
+            let ret =
+                unsafe { VirtualLock(buffer.addr as LPVOID, buffer.mappings * buffer.size_bytes) };
+            if ret == 0 {
+                let e = io::Error::last_os_error();
+                return Err(DoubleMappedBufferError::Lock(e));
             }
         }
-        Self::new_try(min_items, item_size, alignment)
+        Ok(buffer)
     }
 
     fn new_try(
         min_items: usize,
         item_size: usize,
         alignment: usize,
+        mappings: usize,
     ) -> Result<Self, DoubleMappedBufferError> {
-        let ps = pagesize();
-        let mut size = ps;
-        while size < min_items * item_size || size % item_size != 0 {
-            size += ps;
-        }
+        let size = buffer_size(min_items, item_size)?;
 
         unsafe {
             let handle = CreateFileMappingA(
@@ -64,39 +79,49 @@ impl DoubleMappedBufferImpl {
             );
 
             if handle == INVALID_HANDLE_VALUE || handle == 0 as LPVOID {
-                return Err(DoubleMappedBufferError::Placeholder);
+                let e = io::Error::last_os_error();
+                return Err(DoubleMappedBufferError::Placeholder(e));
             }
 
-            let first_tmp =
-                VirtualAlloc(std::ptr::null_mut(), 2 * size, MEM_RESERVE, PAGE_NOACCESS);
+            let first_tmp = VirtualAlloc(
+                std::ptr::null_mut(),
+                mappings * size,
+                MEM_RESERVE,
+                PAGE_NOACCESS,
+            );
             if first_tmp.is_null() {
+                let e = io::Error::last_os_error();
                 CloseHandle(handle);
-                return Err(DoubleMappedBufferError::MapFirst);
+                return Err(DoubleMappedBufferError::MapFirst(e));
             }
 
             let res = VirtualFree(first_tmp, 0, MEM_RELEASE);
             if res == 0 {
+                let e = io::Error::last_os_error();
                 CloseHandle(handle);
-                return Err(DoubleMappedBufferError::MapSecond);
+                return Err(DoubleMappedBufferError::MapSecond(e));
             }
 
-            let first_cpy = MapViewOfFileEx(handle, FILE_MAP_WRITE, 0, 0, size, first_tmp);
-            if first_tmp != first_cpy {
-                CloseHandle(handle);
-                return Err(DoubleMappedBufferError::MapFirst);
-            }
-
-            if first_tmp as usize % alignment != 0 {
+            if !(first_tmp as usize).is_multiple_of(alignment) {
                 CloseHandle(handle);
                 return Err(DoubleMappedBufferError::Alignment);
             }
 
-            let first_ptr = (first_tmp as *mut u8).add(size) as LPVOID;
-            let second_cpy = MapViewOfFileEx(handle, FILE_MAP_WRITE, 0, 0, size, first_ptr);
-            if second_cpy != first_ptr {
-                UnmapViewOfFile(first_cpy);
-                CloseHandle(handle);
-                return Err(DoubleMappedBufferError::MapSecond);
+            for i in 0..mappings {
+                let ptr = (first_tmp as *mut u8).add(i * size) as LPVOID;
+                let cpy = MapViewOfFileEx(handle, FILE_MAP_WRITE, 0, 0, size, ptr);
+                if cpy != ptr {
+                    let e = io::Error::last_os_error();
+                    for j in 0..i {
+                        UnmapViewOfFile((first_tmp as *mut u8).add(j * size) as LPCVOID);
+                    }
+                    CloseHandle(handle);
+                    return if i == 0 {
+                        Err(DoubleMappedBufferError::MapFirst(e))
+                    } else {
+                        Err(DoubleMappedBufferError::MapSecond(e))
+                    };
+                }
             }
 
             Ok(DoubleMappedBufferImpl {
@@ -104,6 +129,7 @@ impl DoubleMappedBufferImpl {
                 handle: handle as usize,
                 size_bytes: size,
                 item_size,
+                mappings,
             })
         }
     }
@@ -115,13 +141,22 @@ impl DoubleMappedBufferImpl {
     pub fn capacity(&self) -> usize {
         self.size_bytes / self.item_size
     }
+
+    pub fn mappings(&self) -> usize {
+        self.mappings
+    }
+
+    pub fn handle(&self) -> RawHandle {
+        self.handle as RawHandle
+    }
 }
 
 impl Drop for DoubleMappedBufferImpl {
     fn drop(&mut self) {
         unsafe {
-            UnmapViewOfFile(self.addr as LPCVOID);
-            UnmapViewOfFile((self.addr + self.size_bytes) as LPCVOID);
+            for i in 0..self.mappings {
+                UnmapViewOfFile((self.addr + i * self.size_bytes) as LPCVOID);
+            }
             CloseHandle(self.handle as HANDLE);
         }
     }
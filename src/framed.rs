@@ -0,0 +1,133 @@
+//! Blocking Circular Buffer for variable-length frames.
+//!
+//! Frames are stored with a length prefix in a byte buffer. Due to the double
+//! mapping, each frame is available as one contiguous slice, even if it wraps
+//! around the end of the buffer.
+
+use thiserror::Error;
+
+use crate::generic::CircularError;
+use crate::sync;
+
+/// Size of the length prefix of a frame.
+const HEADER: usize = std::mem::size_of::<u32>();
+
+/// Error sending a frame.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// Frame does not fit into the buffer.
+    #[error("Frame of {size} bytes exceeds the maximum frame size of {max} bytes.")]
+    TooLarge {
+        /// Size of the frame.
+        size: usize,
+        /// Maximum frame size of the buffer.
+        max: usize,
+    },
+}
+
+/// Framed Circular Buffer Constructor
+pub struct Circular;
+
+impl Circular {
+    /// Create a buffer for frames.
+    ///
+    /// The capacity is set to the page size.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> Result<FrameWriter, CircularError> {
+        Self::with_capacity(0)
+    }
+
+    /// Create a buffer that can hold frames with at least `max_frame` bytes.
+    pub fn with_capacity(max_frame: usize) -> Result<FrameWriter, CircularError> {
+        let writer = sync::Circular::with_capacity::<u8>(max_frame + HEADER)?;
+        Ok(FrameWriter { writer })
+    }
+}
+
+/// Writer for a framed circular buffer.
+#[derive(Debug)]
+pub struct FrameWriter {
+    writer: sync::Writer<u8>,
+}
+
+impl FrameWriter {
+    /// Add a reader to the buffer.
+    pub fn add_reader(&self) -> FrameReader {
+        FrameReader {
+            reader: self.writer.add_reader(),
+            pending: 0,
+        }
+    }
+
+    /// Maximum size of a frame.
+    ///
+    /// The frame has to fit into the buffer and its size into the length
+    /// prefix.
+    pub fn max_frame(&self) -> usize {
+        std::cmp::min(self.writer.capacity() - HEADER, u32::MAX as usize)
+    }
+
+    /// Blocks until there is space for the frame and writes it to the buffer.
+    pub fn send(&mut self, frame: &[u8]) -> Result<(), FrameError> {
+        self.send_with(frame.len(), |s| {
+            s.copy_from_slice(frame);
+            Ok::<(), FrameError>(())
+        })
+    }
+
+    /// Blocks until there is space for a frame of `size` bytes and lets `f`
+    /// fill it in the buffer. The frame is only produced, if `f` succeeds.
+    pub(crate) fn send_with<F, E>(&mut self, size: usize, f: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut [u8]) -> Result<(), E>,
+        E: From<FrameError>,
+    {
+        if size > self.max_frame() {
+            return Err(FrameError::TooLarge {
+                size,
+                max: self.max_frame(),
+            }
+            .into());
+        }
+
+        let n = HEADER + size;
+        let s = self.writer.wait_for_space(n);
+        s[0..HEADER].copy_from_slice(&(size as u32).to_le_bytes());
+        f(&mut s[HEADER..n])?;
+        self.writer.produce(n);
+        Ok(())
+    }
+}
+
+/// Reader for a framed circular buffer.
+#[derive(Debug)]
+pub struct FrameReader {
+    reader: sync::Reader<u8>,
+    pending: usize,
+}
+
+impl FrameReader {
+    /// Blocks until a frame is available or until the writer is dropped.
+    ///
+    /// The frame of the previous call is consumed. If all frames are read and
+    /// the writer is dropped, `None` is returned.
+    pub fn recv(&mut self) -> Option<&[u8]> {
+        self.reader.consume(self.pending);
+        self.pending = 0;
+
+        let s = self.reader.slice_at_least(HEADER)?;
+        if s.len() < HEADER {
+            return None;
+        }
+        let mut header = [0; HEADER];
+        header.copy_from_slice(&s[0..HEADER]);
+        let n = HEADER + u32::from_le_bytes(header) as usize;
+
+        let s = self.reader.slice_at_least(n)?;
+        if s.len() < n {
+            return None;
+        }
+        self.pending = n;
+        Some(&s[HEADER..n])
+    }
+}
@@ -0,0 +1,103 @@
+//! Tracking of the items that were dropped, for types that need drop.
+//!
+//! Items before [State::dropped] were dropped or handed out to the writer
+//! again. The items from there up to the writer position are dropped, once
+//! the state is dropped.
+
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::double_mapped_buffer::DoubleMappedBuffer;
+
+use super::state::{Ring, State};
+use super::{Metadata, Notifier, Writer};
+
+/// Type-erased drop of the items of a buffer.
+///
+/// It holds a reference to the buffer, since the state might outlive all
+/// readers and writers, e.g., while the [debug registry](crate::debug)
+/// inspects it.
+pub(super) struct DropItems {
+    // `Arc<DoubleMappedBuffer<T>>` of the items from `Arc::into_raw`
+    pub(super) buffer: usize,
+    pub(super) drop: unsafe fn(usize, Ring, Range<u64>),
+    pub(super) release: unsafe fn(usize),
+}
+
+impl DropItems {
+    pub(super) fn new<T>(buffer: &Arc<DoubleMappedBuffer<T>>) -> Self {
+        DropItems {
+            buffer: Arc::into_raw(buffer.clone()) as usize,
+            drop: drop_items::<T>,
+            release: release_buffer::<T>,
+        }
+    }
+}
+
+impl Drop for DropItems {
+    fn drop(&mut self) {
+        unsafe { (self.release)(self.buffer) }
+    }
+}
+
+/// Drop the items at the absolute positions `range` of `buffer`, which points
+/// to a [DoubleMappedBuffer].
+///
+/// # Safety
+///
+/// The items have to be initialized and must not be used afterwards.
+pub(super) unsafe fn drop_items<T>(buffer: usize, ring: Ring, range: Range<u64>) {
+    let addr = (*(buffer as *const DoubleMappedBuffer<T>)).slice().as_ptr() as *mut T;
+    for position in range {
+        let offset = ring.wrap(position);
+        std::ptr::drop_in_place(addr.add(offset));
+    }
+}
+
+/// Release the reference of [DropItems] to the buffer.
+///
+/// # Safety
+///
+/// `buffer` has to come from `Arc::into_raw` and must not be used afterwards.
+pub(super) unsafe fn release_buffer<T>(buffer: usize) {
+    drop(Arc::from_raw(buffer as *const DoubleMappedBuffer<T>));
+}
+
+impl<N, M, W> Drop for State<N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    fn drop(&mut self) {
+        if let Some(ref d) = self.drop_items {
+            unsafe { (d.drop)(d.buffer, self.ring, self.dropped..self.writer_position) }
+        }
+    }
+}
+
+impl<T, N, M, W> Writer<T, N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    /// Positions of the consumed items that are handed out again with an
+    /// uninit slice of `space` items. For types that need drop, they are no
+    /// longer dropped with the buffer.
+    pub(super) fn hand_out(&mut self, space: usize) -> Range<u64> {
+        if !mem::needs_drop::<T>() {
+            return 0..0;
+        }
+        let capacity = self.buffer.capacity() as u64;
+        let mut state = self.state.lock().unwrap();
+        let start = state.dropped;
+        let end = std::cmp::max(
+            start,
+            (state.writer_position + space as u64).saturating_sub(capacity),
+        );
+        state.dropped = end;
+        start..end
+    }
+}
@@ -0,0 +1,333 @@
+//! [Metadata] to annotate items, e.g., with [Tags] or [Timestamps].
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Custom metadata to annotate items.
+///
+/// The metadata of a [produce](super::Writer::produce) call is allocated once and
+/// shared between all readers, i.e., implementations should keep a reference to
+/// the [Arc] rather than copying the items.
+pub trait Metadata {
+    type Item: Clone;
+    /// Metadata that is returned together with a slice, e.g., a `Vec` of
+    /// [items](Self::Item) or a view of the shared items.
+    type View: Default;
+
+    /// Create metadata container.
+    fn new() -> Self;
+    /// Add metadata, applying `offset` shift to items.
+    ///
+    /// This is only called for non-empty metadata.
+    fn add(&mut self, offset: usize, tags: Arc<[Self::Item]>);
+    /// Get metadata.
+    fn get(&self) -> Self::View;
+    /// Get metadata of the items in `range`, relative to the current read position.
+    ///
+    /// The default implementation returns all metadata. Implementations that
+    /// know the items their metadata refers to should override it.
+    fn get_range(&self, range: Range<usize>) -> Self::View {
+        let _ = range;
+        self.get()
+    }
+    /// Prune metadata, i.e., delete consumed [items](Self::Item) and update offsets for the remaining.
+    fn consume(&mut self, items: usize);
+}
+
+/// Void implementation for the [Metadata] trait for buffers that don't use metadata.
+#[derive(Clone)]
+pub struct NoMetadata;
+impl Metadata for NoMetadata {
+    type Item = ();
+    type View = Vec<()>;
+
+    fn new() -> Self {
+        Self
+    }
+    fn add(&mut self, _offset: usize, _tags: Arc<[Self::Item]>) {}
+    fn get(&self) -> Vec<Self::Item> {
+        Vec::new()
+    }
+    fn consume(&mut self, _items: usize) {}
+}
+
+/// A tag, annotating the item at offset `item` with `data`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tag<D> {
+    /// Offset of the tagged item, relative to the start of the reader's slice.
+    pub item: usize,
+    /// User data.
+    pub data: D,
+}
+
+/// [Metadata] implementation for [Tags](Tag) that annotate individual items.
+///
+/// Offsets are shifted when tags are added and consumed, such that they are
+/// always relative to the current read position. Tags of consumed items are
+/// removed.
+///
+/// The tags of a produce call are shared between all readers. Each reader
+/// only keeps the absolute position where they were added. Slices return a
+/// [TagSlice] that refers to the shared tags, i.e., the data is not cloned.
+#[derive(Clone)]
+pub struct Tags<D> {
+    pub(super) consumed: usize,
+    pub(super) chunks: Vec<(usize, Arc<[Tag<D>]>)>,
+}
+
+impl<D: Clone> Metadata for Tags<D> {
+    type Item = Tag<D>;
+    type View = TagSlice<D>;
+
+    fn new() -> Self {
+        Tags {
+            consumed: 0,
+            chunks: Vec::new(),
+        }
+    }
+    fn add(&mut self, offset: usize, tags: Arc<[Self::Item]>) {
+        self.chunks.push((self.consumed + offset, tags));
+    }
+    fn get(&self) -> TagSlice<D> {
+        self.get_range(0..usize::MAX)
+    }
+    fn get_range(&self, range: Range<usize>) -> TagSlice<D> {
+        let start = self.consumed.saturating_add(range.start);
+        let end = self.consumed.saturating_add(range.end);
+        TagSlice {
+            origin: self.consumed,
+            range: start..end,
+            chunks: self
+                .chunks
+                .iter()
+                .filter(|(base, chunk)| {
+                    chunk
+                        .iter()
+                        .any(|t| (start..end).contains(&(base + t.item)))
+                })
+                .cloned()
+                .collect(),
+        }
+    }
+    fn consume(&mut self, items: usize) {
+        self.consumed += items;
+        let consumed = self.consumed;
+        self.chunks
+            .retain(|(base, chunk)| chunk.iter().any(|t| base + t.item >= consumed));
+    }
+}
+
+/// [Tags](Tag) of the items of a slice.
+///
+/// The view shares the tags with the buffer and all readers. The offsets of
+/// the tags are relative to the start of the reader's slice.
+pub struct TagSlice<D> {
+    // absolute position of the start of the slice
+    pub(super) origin: usize,
+    // absolute positions of the tagged items
+    pub(super) range: Range<usize>,
+    pub(super) chunks: Vec<(usize, Arc<[Tag<D>]>)>,
+}
+
+impl<D> TagSlice<D> {
+    /// Iterate over the tags, ordered by produce call.
+    pub fn iter(&self) -> impl Iterator<Item = Tag<&D>> + '_ {
+        self.chunks.iter().flat_map(move |(base, chunk)| {
+            chunk.iter().filter_map(move |t| {
+                let pos = base + t.item;
+                self.range.contains(&pos).then(|| Tag {
+                    item: pos - self.origin,
+                    data: &t.data,
+                })
+            })
+        })
+    }
+
+    /// Get the `i`-th tag.
+    pub fn get(&self, i: usize) -> Option<Tag<&D>> {
+        self.iter().nth(i)
+    }
+
+    /// Number of tags.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Whether there are no tags.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Copy the tags into a `Vec`, cloning their data.
+    pub fn to_vec(&self) -> Vec<Tag<D>>
+    where
+        D: Clone,
+    {
+        self.iter()
+            .map(|t| Tag {
+                item: t.item,
+                data: t.data.clone(),
+            })
+            .collect()
+    }
+}
+
+impl<D> Default for TagSlice<D> {
+    fn default() -> Self {
+        TagSlice {
+            origin: 0,
+            range: 0..0,
+            chunks: Vec::new(),
+        }
+    }
+}
+
+impl<D> Clone for TagSlice<D> {
+    fn clone(&self) -> Self {
+        TagSlice {
+            origin: self.origin,
+            range: self.range.clone(),
+            chunks: self.chunks.clone(),
+        }
+    }
+}
+
+impl<D: fmt::Debug> fmt::Debug for TagSlice<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<D: PartialEq> PartialEq<Vec<Tag<D>>> for TagSlice<D> {
+    fn eq(&self, other: &Vec<Tag<D>>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other)
+                .all(|(a, b)| a.item == b.item && *a.data == b.data)
+    }
+}
+
+/// [Metadata] that records when items were produced to measure their latency.
+///
+/// The writer passes [Timestamps::now] as metadata to [produce](super::Writer::produce).
+/// Readers get a [Tag] with the production time for the first item of their
+/// slice and for each later produce call, i.e., the first tag always refers to
+/// the oldest item in the slice.
+#[derive(Clone, Debug)]
+pub struct Timestamps {
+    pub(super) consumed: usize,
+    pub(super) stamps: VecDeque<(usize, Instant)>,
+}
+
+impl Timestamps {
+    /// Metadata with the current time for a [produce](super::Writer::produce) call.
+    pub fn now() -> Vec<Tag<Instant>> {
+        vec![Tag {
+            item: 0,
+            data: Instant::now(),
+        }]
+    }
+}
+
+impl Metadata for Timestamps {
+    type Item = Tag<Instant>;
+    type View = Vec<Tag<Instant>>;
+
+    fn new() -> Self {
+        Timestamps {
+            consumed: 0,
+            stamps: VecDeque::new(),
+        }
+    }
+    fn add(&mut self, offset: usize, tags: Arc<[Self::Item]>) {
+        for t in tags.iter() {
+            self.stamps
+                .push_back((self.consumed + offset + t.item, t.data));
+        }
+    }
+    fn get(&self) -> Vec<Self::Item> {
+        self.get_range(0..usize::MAX)
+    }
+    fn get_range(&self, range: Range<usize>) -> Vec<Self::Item> {
+        self.stamps
+            .iter()
+            .map(|(pos, t)| Tag {
+                item: pos.saturating_sub(self.consumed),
+                data: *t,
+            })
+            .filter(|t| range.contains(&t.item))
+            .collect()
+    }
+    fn consume(&mut self, items: usize) {
+        self.consumed += items;
+        // keep the timestamp of the chunk that contains the read position
+        while self.stamps.len() > 1 && self.stamps[1].0 <= self.consumed {
+            self.stamps.pop_front();
+        }
+    }
+}
+
+/// Collection of latencies to compute percentiles, e.g., of the age of
+/// [Timestamps].
+#[derive(Clone, Debug, Default)]
+pub struct Latencies {
+    pub(super) samples: Vec<Duration>,
+}
+
+impl Latencies {
+    /// Create an empty collection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a latency.
+    pub fn record(&mut self, latency: Duration) {
+        self.samples.push(latency);
+    }
+
+    /// Add the age of all timestamps.
+    pub fn record_tags(&mut self, tags: &[Tag<Instant>]) {
+        let now = Instant::now();
+        self.samples
+            .extend(tags.iter().map(|t| now.saturating_duration_since(t.data)));
+    }
+
+    /// Number of recorded latencies.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Whether no latencies were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Latency below which `p` percent of the recorded latencies lie.
+    ///
+    /// Returns `None`, if no latencies were recorded.
+    ///
+    /// # Panics
+    ///
+    /// If `p` is not in `0.0..=100.0`.
+    pub fn percentile(&mut self, p: f64) -> Option<Duration> {
+        assert!(
+            (0.0..=100.0).contains(&p),
+            "vmcircbuffer: percentile has to be in 0..=100"
+        );
+        if self.samples.is_empty() {
+            return None;
+        }
+        self.samples.sort_unstable();
+        let i = ((p / 100.0) * (self.samples.len() - 1) as f64).round() as usize;
+        Some(self.samples[i])
+    }
+
+    /// Remove all recorded latencies.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
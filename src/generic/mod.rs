@@ -0,0 +1,418 @@
+//! Circular Buffer with generic [Notifier] to implement custom wait/block behavior.
+
+use slab::Slab;
+#[cfg(any(feature = "sync", feature = "async"))]
+use std::io::{IoSlice, IoSliceMut};
+use std::mem;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+use crate::double_mapped_buffer::{
+    BufferPool, Builder, DoubleMappedBuffer, DoubleMappedBufferError,
+};
+
+mod drops;
+use drops::DropItems;
+mod metadata;
+pub use metadata::{Latencies, Metadata, NoMetadata, Tag, TagSlice, Tags, Timestamps};
+mod multi;
+pub use multi::{MultiReader, MultiWriter};
+mod producers;
+mod reader;
+pub use reader::{Reader, ReaderOptions};
+mod state;
+use state::{Ring, State};
+mod stats;
+pub use stats::{ReaderSnapshot, Snapshot, Stats};
+mod writer;
+pub use writer::Writer;
+
+/// Error setting up the underlying buffer.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum CircularError {
+    /// Failed to allocate double mapped buffer.
+    #[error("Failed to allocate double mapped buffer.")]
+    Allocation(#[from] DoubleMappedBufferError),
+    /// Failed to set up the wait strategy, e.g., to create an eventfd.
+    #[error("Failed to set up the wait strategy.")]
+    WaitStrategy(#[from] std::io::Error),
+}
+
+/// Error producing items.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ProduceError {
+    /// Produced more items than space was available in the last slice.
+    #[error("Produced {requested} items, but only {available} were available.")]
+    TooMany {
+        /// Number of produced items.
+        requested: usize,
+        /// Space that was available.
+        available: usize,
+    },
+    /// Produced a number of items that is not a multiple of the granularity of
+    /// a buffer with an increased [alignment](Builder::alignment).
+    #[error("Produced {requested} items, which is not a multiple of {granularity}.")]
+    Unaligned {
+        /// Number of produced items.
+        requested: usize,
+        /// Granularity of the buffer.
+        granularity: usize,
+    },
+}
+
+/// Error consuming items.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ConsumeError {
+    /// Consumed more items than were available in the last slice.
+    #[error("Consumed {requested} items, but only {available} were available.")]
+    TooMany {
+        /// Number of consumed items.
+        requested: usize,
+        /// Items that were available.
+        available: usize,
+    },
+    /// Consumed a number of items that is not a multiple of the granularity of
+    /// a buffer with an increased [alignment](Builder::alignment).
+    #[error("Consumed {requested} items, which is not a multiple of {granularity}.")]
+    Unaligned {
+        /// Number of consumed items.
+        requested: usize,
+        /// Granularity of the buffer.
+        granularity: usize,
+    },
+}
+
+/// A custom notifier can be used to trigger arbitrary mechanism to signal to a
+/// reader or writer that data or buffer space is available. This could be a
+/// write to an sync/async channel or a condition variable.
+pub trait Notifier {
+    /// Arm the notifier.
+    fn arm(&mut self);
+    /// The implementation must
+    /// - only notify if armed
+    /// - notify
+    /// - unarm
+    fn notify(&mut self);
+    /// Like [notify](Notifier::notify), but with the number of items (for a
+    /// reader) or free slots (for a writer) that are available at the time of
+    /// the notification.
+    ///
+    /// The buffer always notifies through this function. The default
+    /// implementation ignores the count and calls [notify](Notifier::notify).
+    fn notify_available(&mut self, n: usize) {
+        let _ = n;
+        self.notify();
+    }
+}
+
+impl<N: Notifier + ?Sized> Notifier for Box<N> {
+    fn arm(&mut self) {
+        (**self).arm();
+    }
+    fn notify(&mut self) {
+        (**self).notify();
+    }
+    fn notify_available(&mut self, n: usize) {
+        (**self).notify_available(n);
+    }
+}
+
+/// [Notifier] whose type is chosen at runtime, allowing to mix different
+/// notifiers in one buffer.
+pub type DynNotifier = Box<dyn Notifier + Send>;
+
+/// [Writer] with [DynNotifier]s.
+pub type DynWriter<T, M = NoMetadata> = Writer<T, DynNotifier, M>;
+
+/// [Reader] with [DynNotifier]s.
+pub type DynReader<T, M = NoMetadata> = Reader<T, DynNotifier, M>;
+
+/// Item types, for which any bit pattern is a valid value.
+///
+/// The output space of a writer holds items that were already consumed or,
+/// on the first pass through the buffer, zeroed memory. It is, therefore,
+/// only handed out as `&mut [T]` (e.g., with [slice](Writer::slice)) for
+/// these types. Other types are written through
+/// [uninit_slice](Writer::uninit_slice).
+///
+/// ```compile_fail
+/// use vmcircbuffer::sync::Circular;
+///
+/// let mut w = Circular::new::<bool>().unwrap();
+/// w.slice();
+/// ```
+///
+/// # Safety
+///
+/// Any bit pattern, including all zeros, has to be a valid value of the
+/// type. This excludes, e.g., references, `bool`, `char`, `NonZeroU32`, and
+/// types that need drop.
+pub unsafe trait Pod: Copy {}
+
+macro_rules! impl_pod {
+    ($($t:ty),*) => {
+        $(unsafe impl Pod for $t {})*
+    };
+}
+
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// Gerneric Circular Buffer Constructor
+pub struct Circular;
+
+impl Circular {
+    /// Create a buffer that can hold at least `min_items` items of type `T`.
+    ///
+    /// The size is the least common multiple of the page size and the size of `T`.
+    pub fn with_capacity<T, N, M>(min_items: usize) -> Result<Writer<T, N, M>, CircularError>
+    where
+        N: Notifier,
+        M: Metadata,
+    {
+        Self::with_builder(&Builder::new(min_items))
+    }
+
+    /// Create a buffer that can hold at least `min_items` items of type `T`
+    /// with preallocated slots for `max_readers` readers.
+    ///
+    /// See [reserve_readers](Writer::reserve_readers).
+    pub fn with_capacity_and_readers<T, N, M>(
+        min_items: usize,
+        max_readers: usize,
+    ) -> Result<Writer<T, N, M>, CircularError>
+    where
+        N: Notifier,
+        M: Metadata,
+    {
+        let writer = Self::with_capacity(min_items)?;
+        writer.reserve_readers(max_readers);
+        Ok(writer)
+    }
+
+    /// Create a buffer, whose underlying [DoubleMappedBuffer] is configured by
+    /// a [Builder], e.g., to set the [temp directory](Builder::tmp_dir).
+    pub fn with_builder<T, N, M>(builder: &Builder) -> Result<Writer<T, N, M>, CircularError>
+    where
+        N: Notifier,
+        M: Metadata,
+    {
+        let mut writer = Self::with_buffer(builder.build()?);
+        writer.builder = Some(builder.clone());
+        Ok(writer)
+    }
+
+    /// Create a circular buffer on top of an existing [DoubleMappedBuffer],
+    /// e.g., one [backed by a custom mapper](DoubleMappedBuffer::from_mapper).
+    pub fn with_buffer<T, N, M>(buffer: DoubleMappedBuffer<T>) -> Writer<T, N, M>
+    where
+        N: Notifier,
+        M: Metadata,
+    {
+        Self::writer(Arc::new(buffer))
+    }
+
+    /// Create a buffer that can hold at least `min_items` items of type `T`,
+    /// whose [DoubleMappedBuffer] is taken from a [BufferPool].
+    ///
+    /// The buffer returns to the pool, once the writer and all readers are
+    /// dropped.
+    pub fn with_pool<T, N, M>(
+        pool: &BufferPool<T>,
+        min_items: usize,
+    ) -> Result<Writer<T, N, M>, CircularError>
+    where
+        N: Notifier,
+        M: Metadata,
+    {
+        Ok(Self::writer(pool.get(min_items)?))
+    }
+
+    /// Create a buffer, whose readers are signaled through notifiers of type
+    /// `N` and whose writers through notifiers of type `W`.
+    ///
+    /// This allows, e.g., waking an async task when data becomes available,
+    /// while signaling space to the writer through an eventfd.
+    pub fn with_notifiers<T, N, W, M>(
+        builder: &Builder,
+    ) -> Result<Writer<T, N, M, W>, CircularError>
+    where
+        N: Notifier,
+        M: Metadata,
+        W: Notifier,
+    {
+        let mut writer = Self::writer(Arc::new(builder.build()?));
+        writer.builder = Some(builder.clone());
+        Ok(writer)
+    }
+
+    fn writer<T, N, M, W>(buffer: Arc<DoubleMappedBuffer<T>>) -> Writer<T, N, M, W>
+    where
+        N: Notifier,
+        M: Metadata,
+        W: Notifier,
+    {
+        let drop_items = mem::needs_drop::<T>().then(|| DropItems::new(&buffer));
+
+        let state = Arc::new(Mutex::new(State {
+            writer_offset: 0,
+            writer_ab: false,
+            writer_done: false,
+            writer_position: 0,
+            slice_end: 0,
+            readers: Slab::new(),
+            producers: None,
+            holes: Vec::new(),
+            on_produce: None,
+            on_consume: None,
+            dropped: 0,
+            drop_items,
+            ring: Ring::new(buffer.capacity()),
+            generation: 0,
+            stall_timeout: None,
+        }));
+        let current = Arc::new(Mutex::new(buffer.clone()));
+
+        Writer {
+            state,
+            ring: Ring::new(buffer.capacity()),
+            buffer,
+            current,
+            producer: None,
+            lossy: false,
+            last_space: 0,
+            uninit_space: 0,
+            watermark: 0,
+            stats: None,
+            builder: None,
+        }
+    }
+
+    /// Create a buffer that can hold at least `min_items` items of type `T`,
+    /// where the writer overwrites the oldest data, instead of waiting for the
+    /// readers. It only waits for readers that hold a slice.
+    ///
+    /// All readers of the buffer are [lossy](Writer::add_lossy_reader).
+    ///
+    /// The size is the least common multiple of the page size and the size of `T`.
+    pub fn with_capacity_lossy<T, N, M>(min_items: usize) -> Result<Writer<T, N, M>, CircularError>
+    where
+        N: Notifier,
+        M: Metadata,
+    {
+        let mut writer = Self::with_capacity(min_items)?;
+        writer.lossy = true;
+        Ok(writer)
+    }
+
+    /// Create a buffer with `channels` planes that can each hold at least
+    /// `min_items` items of type `T`.
+    ///
+    /// Each channel is backed by its own mapping of the same capacity, since
+    /// every channel has to wrap around contiguously. The channels share the
+    /// state of one buffer, i.e., slices of all channels have the same length
+    /// and the channels advance in lockstep.
+    ///
+    /// # Panics
+    ///
+    /// If `channels` is zero.
+    pub fn new_multi<T, N, M>(
+        channels: usize,
+        min_items: usize,
+    ) -> Result<MultiWriter<T, N, M>, CircularError>
+    where
+        N: Notifier,
+        M: Metadata,
+    {
+        Self::new_multi_with_builder(channels, &Builder::new(min_items))
+    }
+
+    /// Create a buffer with `channels` planes, whose [DoubleMappedBuffer]s
+    /// are configured by a [Builder].
+    ///
+    /// See [new_multi](Circular::new_multi).
+    ///
+    /// # Panics
+    ///
+    /// If `channels` is zero.
+    pub fn new_multi_with_builder<T, N, M>(
+        channels: usize,
+        builder: &Builder,
+    ) -> Result<MultiWriter<T, N, M>, CircularError>
+    where
+        N: Notifier,
+        M: Metadata,
+    {
+        assert!(
+            channels > 0,
+            "vmcircbuffer: at least one channel is required"
+        );
+        let writer: Writer<T, N, M> = Self::with_builder(builder)?;
+        let mut planes = vec![writer.buffer.clone()];
+        for _ in 1..channels {
+            planes.push(Arc::new(builder.build()?));
+        }
+        debug_assert!(planes.iter().all(|p| p.capacity() == writer.capacity()));
+        Ok(MultiWriter {
+            writer,
+            planes: planes.into(),
+            offset: 0,
+        })
+    }
+}
+
+/// Panic with a message that matches the kind of a failed produce.
+#[cold]
+fn produce_failed(e: ProduceError) -> ! {
+    match e {
+        ProduceError::TooMany { .. } => panic!("vmcircbuffer: produced too much"),
+        ProduceError::Unaligned { .. } => {
+            panic!("vmcircbuffer: produced a partial chunk of an aligned buffer")
+        }
+    }
+}
+
+/// Panic with a message that matches the kind of a failed consume.
+#[cold]
+fn consume_failed(e: ConsumeError) -> ! {
+    match e {
+        ConsumeError::TooMany { .. } => panic!("vmcircbuffer: consumed too much!"),
+        ConsumeError::Unaligned { .. } => {
+            panic!("vmcircbuffer: consumed a partial chunk of an aligned buffer")
+        }
+    }
+}
+
+/// Copy the bytes of `bufs` into `dst`, until it is full. Returns the number
+/// of copied bytes.
+#[cfg(any(feature = "sync", feature = "async"))]
+pub(crate) fn gather(dst: &mut [u8], bufs: &[IoSlice<'_>]) -> usize {
+    let mut n = 0;
+    for b in bufs {
+        let k = std::cmp::min(b.len(), dst.len() - n);
+        dst[n..n + k].copy_from_slice(&b[0..k]);
+        n += k;
+        if n == dst.len() {
+            break;
+        }
+    }
+    n
+}
+
+/// Copy the bytes of `src` into `bufs`, until they are full. Returns the
+/// number of copied bytes.
+#[cfg(any(feature = "sync", feature = "async"))]
+pub(crate) fn scatter(src: &[u8], bufs: &mut [IoSliceMut<'_>]) -> usize {
+    let mut n = 0;
+    for b in bufs {
+        let k = std::cmp::min(b.len(), src.len() - n);
+        b[0..k].copy_from_slice(&src[n..n + k]);
+        n += k;
+        if n == src.len() {
+            break;
+        }
+    }
+    n
+}
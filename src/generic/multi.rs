@@ -0,0 +1,204 @@
+//! Writer and reader for buffers with multiple channels.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::double_mapped_buffer::DoubleMappedBuffer;
+
+use super::{produce_failed, ConsumeError, Metadata, Notifier, Pod, ProduceError, Reader, Writer};
+
+/// Writer for a buffer with multiple channels, created with
+/// [new_multi](super::Circular::new_multi).
+pub struct MultiWriter<T, N, M>
+where
+    N: Notifier,
+    M: Metadata,
+{
+    pub(super) writer: Writer<T, N, M>,
+    pub(super) planes: Arc<[Arc<DoubleMappedBuffer<T>>]>,
+    // offset of the last provided slices
+    pub(super) offset: usize,
+}
+
+impl<T, N, M> MultiWriter<T, N, M>
+where
+    N: Notifier,
+    M: Metadata,
+{
+    /// Add a [MultiReader] to the buffer.
+    ///
+    /// See [add_reader](Writer::add_reader).
+    pub fn add_reader(&self, reader_notifier: N, writer_notifier: N) -> MultiReader<T, N, M> {
+        MultiReader {
+            reader: self.writer.add_reader(reader_notifier, writer_notifier),
+            planes: self.planes.clone(),
+        }
+    }
+
+    /// Number of channels.
+    pub fn channels(&self) -> usize {
+        self.planes.len()
+    }
+
+    /// Number of items each channel can hold.
+    pub fn capacity(&self) -> usize {
+        self.writer.capacity()
+    }
+
+    /// Get one slice per channel to write into.
+    ///
+    /// All slices have the same length. See [slice](Writer::slice).
+    pub fn slice(&mut self, arm: bool) -> Vec<&mut [T]>
+    where
+        T: Pod,
+    {
+        self.slice_at_least(0, arm)
+    }
+
+    /// Get one slice per channel with at least `n` items to write into.
+    ///
+    /// See [slice_at_least](Writer::slice_at_least).
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub fn slice_at_least(&mut self, n: usize, arm: bool) -> Vec<&mut [T]>
+    where
+        T: Pod,
+    {
+        let (space, offset) = self.writer.prepare_slice(n, arm);
+        self.offset = offset;
+        self.planes
+            .iter()
+            .map(|p| unsafe { &mut p.slice_with_offset_mut(offset)[0..space] })
+            .collect()
+    }
+
+    /// Indicates that `n` items were written to each channel.
+    ///
+    /// # Panics
+    ///
+    /// If produced more than space was available in the last provided slices.
+    pub fn produce(&mut self, n: usize, meta: Vec<M::Item>) {
+        if let Err(e) = self.try_produce(n, meta) {
+            produce_failed(e);
+        }
+    }
+
+    /// Indicates that `n` items were written to each channel.
+    ///
+    /// See [try_produce](Writer::try_produce).
+    pub fn try_produce(&mut self, n: usize, meta: Vec<M::Item>) -> Result<(), ProduceError> {
+        // the writer mirrors the first plane, the others are mirrored here,
+        // before the items become visible to readers
+        if n > 0 && n <= self.writer.last_space {
+            for p in self.planes.iter().skip(1) {
+                unsafe {
+                    p.mirror(self.offset, n);
+                }
+            }
+        }
+        self.writer.try_produce(n, meta)?;
+        self.offset = self.writer.ring.wrap((self.offset + n) as u64);
+        Ok(())
+    }
+}
+
+impl<T, N, M> fmt::Debug for MultiWriter<T, N, M>
+where
+    N: Notifier,
+    M: Metadata,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiWriter")
+            .field("channels", &self.planes.len())
+            .field("writer", &self.writer)
+            .finish()
+    }
+}
+
+/// Slices of all channels and the metadata of the items.
+type ChannelSlices<'a, T, M> = (Vec<&'a [T]>, <M as Metadata>::View);
+
+/// Reader for a buffer with multiple channels, created with
+/// [MultiWriter::add_reader].
+pub struct MultiReader<T, N, M>
+where
+    N: Notifier,
+    M: Metadata,
+{
+    pub(super) reader: Reader<T, N, M>,
+    pub(super) planes: Arc<[Arc<DoubleMappedBuffer<T>>]>,
+}
+
+impl<T, N, M> MultiReader<T, N, M>
+where
+    N: Notifier,
+    M: Metadata,
+{
+    /// Number of channels.
+    pub fn channels(&self) -> usize {
+        self.planes.len()
+    }
+
+    /// Number of items each channel can hold.
+    pub fn capacity(&self) -> usize {
+        self.reader.capacity()
+    }
+
+    /// Get one slice per channel with the items available to read, together
+    /// with the metadata of these items.
+    ///
+    /// All slices have the same length. Returns `None` if the writer was
+    /// dropped and all data was read.
+    pub fn slice(&mut self, arm: bool) -> Option<ChannelSlices<'_, T, M>> {
+        self.slice_at_least(0, arm)
+    }
+
+    /// Get one slice per channel with at least `n` items to read, together
+    /// with the metadata of these items.
+    ///
+    /// See [slice_at_least](Reader::slice_at_least).
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub fn slice_at_least(&mut self, n: usize, arm: bool) -> Option<ChannelSlices<'_, T, M>> {
+        let (offset, history, space, tags) = self.reader.prepare_slice(n, 1, arm)?;
+        let slices = self
+            .planes
+            .iter()
+            .map(|p| unsafe { &p.slice_with_offset(offset)[0..history + space] })
+            .collect();
+        Some((slices, tags))
+    }
+
+    /// Indicates that `n` items were read from each channel.
+    ///
+    /// # Panics
+    ///
+    /// If consumed more than space was available in the last provided slices.
+    pub fn consume(&mut self, n: usize) {
+        self.reader.consume(n);
+    }
+
+    /// Indicates that `n` items were read from each channel.
+    ///
+    /// See [try_consume](Reader::try_consume).
+    pub fn try_consume(&mut self, n: usize) -> Result<(), ConsumeError> {
+        self.reader.try_consume(n)
+    }
+}
+
+impl<T, N, M> fmt::Debug for MultiReader<T, N, M>
+where
+    N: Notifier,
+    M: Metadata,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiReader")
+            .field("channels", &self.planes.len())
+            .field("reader", &self.reader)
+            .finish()
+    }
+}
@@ -0,0 +1,195 @@
+//! Reservations of multiple writers that produce into the same buffer.
+//!
+//! Writers reserve space in order. Items only become visible to the readers,
+//! once all reservations before them are produced or released.
+
+use slab::Slab;
+use std::mem;
+
+use super::state::{granularity, State};
+use super::{Metadata, Notifier, Writer};
+
+/// Write reservations of multiple writers.
+pub(super) struct Producers<N> {
+    pub(super) reserved: u64,
+    pub(super) slots: Slab<Producer<N>>,
+}
+
+impl<N> Producers<N> {
+    /// Position up to which all items were produced.
+    pub(super) fn position(&self) -> u64 {
+        self.slots
+            .iter()
+            .filter_map(|(_, p)| p.reservation.map(|(start, _)| start))
+            .min()
+            .unwrap_or(self.reserved)
+    }
+}
+
+pub(super) struct Producer<N> {
+    // the writer that created the buffer uses the writer notifiers of the readers
+    pub(super) notifier: Option<N>,
+    pub(super) reservation: Option<(u64, usize)>,
+    // number of items of space that have to be available to fire the armed notifier
+    pub(super) want: usize,
+}
+
+impl<N, M, W> State<N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    /// Notify the writers, for which the space they wait for became available.
+    pub(super) fn notify_producers(&mut self, capacity: usize) {
+        let State {
+            readers,
+            producers,
+            writer_position,
+            ..
+        } = self;
+        if let Some(ref mut producers) = producers {
+            let base = readers
+                .iter()
+                .filter(|(_, r)| r.blocks_writer())
+                .map(|(_, r)| r.position)
+                .min()
+                .unwrap_or(*writer_position);
+            let reserved = producers.reserved;
+            let free = capacity - (reserved - base) as usize;
+            for (_, p) in producers.slots.iter_mut() {
+                if let Some(ref mut n) = p.notifier {
+                    let space = match p.reservation {
+                        Some((start, len)) if start + len as u64 != reserved => len,
+                        Some((_, len)) => len + free,
+                        None => free,
+                    };
+                    if space >= p.want {
+                        n.notify_available(space);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T, N, M, W> Writer<T, N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    /// Add a writer that produces into the same buffer.
+    ///
+    /// Each writer reserves the space it gets with a slice. Readers only see
+    /// items once all items before them are produced, i.e., the items appear in
+    /// the order in which the space was reserved. The `writer_notifier` is used
+    /// to signal the new writer that space became available. The writer
+    /// notifiers of the readers still signal the writer that created the buffer.
+    ///
+    /// The buffer is considered finished, once all writers are dropped. If a
+    /// writer is dropped while it holds a reservation, which is followed by
+    /// reservations of other writers, readers skip the reserved items.
+    pub fn add_writer(&mut self, writer_notifier: W) -> Writer<T, N, M, W> {
+        assert!(
+            !mem::needs_drop::<T>(),
+            "vmcircbuffer: multiple writers do not support types that need drop"
+        );
+        assert!(
+            granularity(&self.buffer) == 1,
+            "vmcircbuffer: multiple writers do not support an increased alignment"
+        );
+        let mut state = self.state.lock().unwrap();
+
+        if self.producer.is_none() {
+            let position = state.writer_position;
+            let mut slots = Slab::new();
+            let id = slots.insert(Producer {
+                notifier: None,
+                reservation: (self.last_space > 0).then_some((position, self.last_space)),
+                want: 0,
+            });
+            state.producers = Some(Producers {
+                reserved: position + self.last_space as u64,
+                slots,
+            });
+            self.producer = Some(id);
+        }
+
+        let id = state.producers.as_mut().unwrap().slots.insert(Producer {
+            notifier: Some(writer_notifier),
+            reservation: None,
+            want: 0,
+        });
+
+        Writer {
+            producer: Some(id),
+            lossy: self.lossy,
+            last_space: 0,
+            uninit_space: 0,
+            watermark: 0,
+            stats: None,
+            buffer: self.buffer.clone(),
+            ring: self.ring,
+            current: self.current.clone(),
+            state: self.state.clone(),
+            builder: self.builder.clone(),
+        }
+    }
+
+    /// Reserve space for a writer, if there are multiple writers.
+    ///
+    /// The reservation of a writer can only be extended, if no other writer
+    /// reserved space after it.
+    pub(super) fn reserve(&self, id: usize, min_items: usize, arm: bool) -> (usize, usize) {
+        let mut state = self.state.lock().unwrap();
+        let capacity = self.buffer.capacity();
+
+        let State {
+            readers,
+            producers,
+            writer_position,
+            ..
+        } = &mut *state;
+        let producers = producers.as_mut().unwrap();
+
+        let base = readers
+            .iter()
+            .filter(|(_, r)| r.blocks_writer())
+            .map(|(_, r)| r.position)
+            .min()
+            .unwrap_or(*writer_position);
+        let reserved = producers.reserved;
+        let free = capacity - (reserved - base) as usize;
+
+        let p = &mut producers.slots[id];
+        let (start, len) = p.reservation.unwrap_or((reserved, 0));
+        let mut space = len;
+        if start + len as u64 == reserved && len + free >= min_items {
+            space = len + free;
+            p.reservation = (space > 0).then_some((start, space));
+        }
+
+        if space < std::cmp::max(min_items, 1) && arm {
+            let want = std::cmp::max(min_items, self.watermark);
+            match p.notifier {
+                Some(ref mut n) => {
+                    p.want = want;
+                    n.arm();
+                }
+                None => {
+                    for (_, r) in readers.iter_mut().filter(|(_, r)| r.blocks_writer()) {
+                        r.writer_want = want;
+                        r.writer_notifier.arm();
+                    }
+                }
+            }
+        }
+        if space != len {
+            producers.reserved = start + space as u64;
+        }
+
+        let space = if space < min_items { 0 } else { space };
+        (space, self.ring.wrap(start))
+    }
+}
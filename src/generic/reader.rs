@@ -0,0 +1,1118 @@
+//! [Reader] of a generic circular buffer.
+
+use std::fmt;
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+use crate::double_mapped_buffer::DoubleMappedBuffer;
+
+#[cfg(feature = "bytes")]
+use super::state::Ring;
+use super::state::{granularity, refresh, BufferSlot, CachePadded, ReaderState, State, Worker};
+use super::{consume_failed, ConsumeError, Metadata, Notifier, Snapshot, Stats};
+
+/// Options of a reader, which is added with
+/// [add_reader_with_opts](super::Writer::add_reader_with_opts).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReaderOptions {
+    pub(super) non_blocking: bool,
+    pub(super) max_chunk: usize,
+    pub(super) watermark: usize,
+    pub(super) history: usize,
+}
+
+impl ReaderOptions {
+    /// Options of a normal reader, i.e., one that blocks the writer.
+    pub fn new() -> Self {
+        ReaderOptions {
+            non_blocking: false,
+            max_chunk: usize::MAX,
+            watermark: 0,
+            history: 0,
+        }
+    }
+
+    /// Whether the reader never blocks the writer, i.e., it is
+    /// [lossy](super::Writer::add_lossy_reader).
+    ///
+    /// This allows, e.g., attaching a debug tap to a buffer, without affecting
+    /// the backpressure of the other readers.
+    pub fn non_blocking(mut self, non_blocking: bool) -> Self {
+        self.non_blocking = non_blocking;
+        self
+    }
+
+    /// Set the [maximum chunk size](Reader::set_max_chunk).
+    pub fn max_chunk(mut self, n: usize) -> Self {
+        self.max_chunk = n;
+        self
+    }
+
+    /// Set the [watermark](Reader::set_watermark).
+    pub fn watermark(mut self, n: usize) -> Self {
+        self.watermark = n;
+        self
+    }
+
+    /// Set the [history](Reader::set_history).
+    pub fn history(mut self, n: usize) -> Self {
+        self.history = n;
+        self
+    }
+}
+
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reader for a generic circular buffer with items of type `T` and [Notifier] of type `N`.
+///
+/// Readers are signaled through notifiers of type `N`, writers through
+/// notifiers of type `W`, which defaults to `N`.
+pub struct Reader<T, N, M, W = N>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    pub(super) id: usize,
+    pub(super) worker: Option<usize>,
+    pub(super) last_space: usize,
+    pub(super) max_chunk: usize,
+    pub(super) watermark: usize,
+    pub(super) stats: Option<Arc<Stats>>,
+    pub(super) state: Arc<Mutex<State<N, M, W>>>,
+    pub(super) buffer: Arc<DoubleMappedBuffer<T>>,
+    // generation of the state that corresponds to the buffer
+    pub(super) generation: u64,
+    pub(super) current: BufferSlot<T>,
+}
+
+/// Slice, number of history items at its start, and metadata.
+#[cfg(any(feature = "sync", feature = "async", feature = "nonblocking"))]
+type SliceParts<'a, T, M> = (&'a [T], usize, <M as Metadata>::View);
+
+impl<T, N, M, W> Reader<T, N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    /// Returns the number of new items, the offset of the slice, the number of
+    /// history items at the start of the slice, whether the writer is done, and
+    /// the metadata of the slice.
+    fn space_and_offset_and_meta(
+        &mut self,
+        min_items: usize,
+        chunk: usize,
+        arm: bool,
+    ) -> (usize, usize, usize, bool, M::View) {
+        if let Some(worker) = self.worker {
+            return self.claim(worker, min_items, arm);
+        }
+
+        let mut state = self.state.lock().unwrap();
+        refresh(
+            &mut self.buffer,
+            &mut self.generation,
+            &self.current,
+            state.generation,
+        );
+
+        let ring = state.ring;
+        let capacity = ring.capacity;
+        let done = state.writer_done;
+        let w_off = state.writer_offset;
+        let w_ab = state.writer_ab;
+        let w_pos = state.writer_position;
+
+        let output_end = state.output_end();
+        let my = unsafe { state.readers.get_unchecked_mut(self.id) };
+        if my.detached {
+            let offset = my.offset;
+            if my.holding {
+                my.unhold(capacity);
+                state.notify_producers(capacity);
+            }
+            return (0, offset, 0, true, M::View::default());
+        }
+        if my.lossy {
+            // items in the output space of a writer are lost
+            let start = output_end.saturating_sub(capacity as u64);
+            if my.position < start {
+                let lost = (start - my.position) as usize;
+                my.lose(lost, ring);
+            }
+        }
+        let position = my.position;
+        let read = position + my.retained as u64;
+        let (next, limit) = state.skip_holes(read);
+
+        let my = unsafe { state.readers.get_unchecked_mut(self.id) };
+        if next != read {
+            // the history does not extend across holes
+            let n = (next - position) as usize;
+            my.meta.consume(n);
+            my.advance(n, ring);
+            my.retained = 0;
+            my.notify_writer(w_pos, capacity);
+        }
+        let r_off = my.offset;
+        let r_ab = my.ab;
+        let history = my.retained;
+        my.skipped = 0;
+
+        let space = if r_off > w_off {
+            w_off + capacity - r_off
+        } else if r_off < w_off {
+            w_off - r_off
+        } else if r_ab == w_ab {
+            0
+        } else {
+            capacity
+        };
+        let space = std::cmp::min((space - history) as u64, limit - next) as usize;
+
+        if space < std::cmp::max(min_items, 1) && arm {
+            my.reader_want = std::cmp::max(min_items, self.watermark);
+            my.reader_notifier.arm();
+        }
+
+        let space = std::cmp::min(space, self.max_chunk);
+        let space = space - space % chunk;
+        let space = if space < min_items && !done { 0 } else { space };
+        let meta = my.meta.get_range(0..history + space);
+        my.holding = history + space > 0;
+
+        if next != read {
+            state.notify_producers(self.buffer.capacity());
+        }
+        (space, r_off, history, done, meta)
+    }
+
+    /// Claim items for a shared reader.
+    ///
+    /// The claim of a worker can only be extended, if no other worker claimed
+    /// items after it.
+    fn claim(
+        &mut self,
+        worker: usize,
+        min_items: usize,
+        arm: bool,
+    ) -> (usize, usize, usize, bool, M::View) {
+        let mut state = self.state.lock().unwrap();
+        refresh(
+            &mut self.buffer,
+            &mut self.generation,
+            &self.current,
+            state.generation,
+        );
+
+        let ring = state.ring;
+        let capacity = ring.capacity;
+        let done = state.writer_done;
+        let w_pos = state.writer_position;
+
+        let claimed = unsafe { state.readers.get_unchecked(self.id) }
+            .shared
+            .as_ref()
+            .unwrap()
+            .claimed;
+        let (next, limit) = state.skip_holes(claimed);
+
+        let my = unsafe { state.readers.get_unchecked_mut(self.id) };
+        if next != claimed {
+            let shared = my.shared.as_mut().unwrap();
+            shared.claimed = next;
+            let delta = (shared.position() - my.position) as usize;
+            my.advance(delta, ring);
+            my.notify_writer(w_pos, capacity);
+        }
+        let ReaderState {
+            shared,
+            reader_notifier,
+            reader_want,
+            ..
+        } = &mut **my;
+        let shared = shared.as_mut().unwrap();
+        let claimed = shared.claimed;
+
+        // items released by dropped workers are handed out first
+        if shared.workers[worker].claim.is_none() {
+            if let Some(&(start, len)) = shared.released.first() {
+                if len >= min_items || done {
+                    let space = std::cmp::min(len, self.max_chunk);
+                    if space == len {
+                        shared.released.remove(0);
+                    } else {
+                        shared.released[0] = (start + space as u64, len - space);
+                    }
+                    shared.workers[worker].claim = Some((start, space));
+                }
+            }
+        }
+        let w = &mut shared.workers[worker];
+        w.skipped = 0;
+
+        let (start, len) = w.claim.unwrap_or((claimed, 0));
+        let mut space = len;
+        if start + len as u64 == claimed {
+            let potential = std::cmp::max(
+                len,
+                std::cmp::min(
+                    len + (std::cmp::min(w_pos, limit) - claimed) as usize,
+                    self.max_chunk,
+                ),
+            );
+            if potential >= min_items || done {
+                space = potential;
+                shared.claimed = start + space as u64;
+                w.claim = if space > 0 {
+                    Some((start, space))
+                } else {
+                    None
+                };
+            }
+        }
+
+        if space < std::cmp::max(min_items, 1) && arm {
+            // competing workers are notified regardless of the watermark
+            *reader_want = 0;
+            match w.notifier {
+                Some(ref mut n) => n.arm(),
+                None => reader_notifier.arm(),
+            }
+        }
+
+        if next != claimed {
+            state.notify_producers(self.buffer.capacity());
+        }
+
+        let space = if space < min_items && !done { 0 } else { space };
+        (space, ring.wrap(start), 0, done, M::View::default())
+    }
+
+    /// Add a reader that shares the consumption cursor with this reader.
+    ///
+    /// See [add_shared_reader](super::Writer::add_shared_reader).
+    ///
+    /// # Panics
+    ///
+    /// If this reader is not a shared reader.
+    pub fn share(&self, reader_notifier: N) -> Reader<T, N, M, W> {
+        let mut state = self.state.lock().unwrap();
+        let shared = state.readers[self.id]
+            .shared
+            .as_mut()
+            .expect("vmcircbuffer: only shared readers can be shared");
+        let worker = shared.workers.insert(Worker {
+            notifier: Some(reader_notifier),
+            claim: None,
+            skipped: 0,
+        });
+
+        Reader {
+            id: self.id,
+            worker: Some(worker),
+            last_space: 0,
+            max_chunk: usize::MAX,
+            watermark: 0,
+            stats: None,
+            buffer: self.current.lock().unwrap().clone(),
+            generation: state.generation,
+            current: self.current.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Add a reader that starts at the current position of this reader.
+    ///
+    /// In contrast to [add_reader](super::Writer::add_reader), the new reader sees
+    /// all items (and their metadata) that are not yet consumed by this reader.
+    /// It is independent of this reader, i.e., it has to consume items on its
+    /// own. Forking a lossy reader returns a lossy reader. Forking a shared
+    /// reader returns a normal reader that starts at the position up to which
+    /// all shared readers have consumed.
+    pub fn fork(&self, reader_notifier: N) -> Reader<T, N, M, W>
+    where
+        W: Clone,
+        M: Clone,
+    {
+        let mut state = self.state.lock().unwrap();
+        let my = &state.readers[self.id];
+        let reader_state = ReaderState {
+            ab: my.ab,
+            offset: my.offset,
+            position: my.position,
+            lossy: my.lossy,
+            overrun: 0,
+            skipped: 0,
+            shared: None,
+            reader_notifier,
+            writer_notifier: my.writer_notifier.clone(),
+            reader_want: 0,
+            writer_want: 0,
+            history: my.history,
+            retained: my.retained,
+            stalled_since: None,
+            detached: my.detached,
+            holding: false,
+            paused: my.paused,
+            paused_skipped: 0,
+            meta: my.meta.clone(),
+        };
+        let id = state.readers.insert(CachePadded(reader_state));
+
+        Reader {
+            id,
+            worker: None,
+            last_space: 0,
+            max_chunk: usize::MAX,
+            watermark: 0,
+            stats: None,
+            buffer: self.current.lock().unwrap().clone(),
+            generation: state.generation,
+            current: self.current.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Number of items the buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.current.lock().unwrap().capacity()
+    }
+
+    /// Snapshot of the writer and all readers of the buffer.
+    ///
+    /// See [Writer::state_snapshot](super::Writer::state_snapshot).
+    pub fn state_snapshot(&self) -> Snapshot {
+        self.state.lock().unwrap().snapshot()
+    }
+
+    /// Whether all writers of the buffer are dropped.
+    ///
+    /// Items that were produced before can still be read.
+    pub fn writer_dropped(&self) -> bool {
+        self.state.lock().unwrap().writer_done
+    }
+
+    /// Whether the writer detached the reader, since it
+    /// [stalled](super::Writer::set_stall_timeout).
+    ///
+    /// A detached reader no longer receives items and its slices are `None`.
+    pub fn is_detached(&self) -> bool {
+        self.state.lock().unwrap().readers[self.id].detached
+    }
+
+    /// Number of items that can be read, without getting a slice.
+    ///
+    /// For shared readers, this includes the items claimed by this reader and
+    /// the items that are not claimed by any reader.
+    pub fn available(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        let my = &state.readers[self.id];
+        match (self.worker, &my.shared) {
+            (Some(worker), Some(shared)) => {
+                let claimed = shared.workers[worker].claim.map_or(0, |(_, len)| len);
+                let released: usize = shared.released.iter().map(|&(_, len)| len).sum();
+                claimed + released + (state.writer_position - shared.claimed) as usize
+            }
+            _ => {
+                let (position, limit) = state.skip_holes(my.position + my.retained as u64);
+                (std::cmp::min(state.writer_position, limit) - position) as usize
+            }
+        }
+    }
+
+    /// Identifier of the reader that is passed to the
+    /// [consume hook](super::Writer::set_on_consume).
+    ///
+    /// It is unique among the readers of the buffer, except for
+    /// [shared readers](super::Writer::add_shared_reader), which share the identifier.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Absolute position of the reader in the stream, i.e., the number of
+    /// items produced before the first item of the next [slice](Reader::slice).
+    ///
+    /// In contrast to the offsets in the circular buffer, the position does not
+    /// wrap and can be used to correlate items across buffers.
+    pub fn position(&self) -> u64 {
+        let state = self.state.lock().unwrap();
+        let my = &state.readers[self.id];
+        match (self.worker, &my.shared) {
+            (Some(worker), Some(shared)) => shared.workers[worker]
+                .claim
+                .or_else(|| shared.released.first().copied())
+                .map(|(start, _)| start)
+                .unwrap_or(shared.claimed),
+            _ => my.position + my.retained as u64,
+        }
+    }
+
+    /// Number of items that were dropped since the last call, because the
+    /// reader fell behind.
+    ///
+    /// This is always zero for readers that are not
+    /// [lossy](super::Writer::add_lossy_reader).
+    pub fn take_overrun(&mut self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        std::mem::take(&mut state.readers[self.id].overrun)
+    }
+
+    /// Limit the number of items returned by [slice](Reader::slice).
+    ///
+    /// Even if more data is available, the slice will contain at most `n`
+    /// items. Use `usize::MAX` to remove the limit. For buffers with an
+    /// increased
+    /// [alignment](crate::double_mapped_buffer::Builder::alignment), `n` is
+    /// rounded down to the granularity of the buffer.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero or smaller than the granularity of the buffer.
+    pub fn set_max_chunk(&mut self, n: usize) {
+        let granularity = granularity(&self.buffer);
+        assert!(
+            n >= granularity,
+            "vmcircbuffer: max chunk has to be at least the granularity of the buffer"
+        );
+        self.max_chunk = n - n % granularity;
+    }
+
+    /// Only notify the reader, once at least `n` items are available.
+    ///
+    /// This applies when a [slice](Reader::slice) arms the notifier. It
+    /// batches wakeups, if the writer produces in small chunks. The reader is
+    /// still notified when the writer is dropped. Use `0` to be notified
+    /// whenever items become available. Competing
+    /// [shared readers](super::Writer::add_shared_reader) ignore the watermark.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub fn set_watermark(&mut self, n: usize) {
+        assert!(
+            n <= self.buffer.capacity(),
+            "vmcircbuffer: watermark exceeds the capacity of the buffer"
+        );
+        self.watermark = n;
+    }
+
+    /// Keep the last `n` consumed items at the start of each
+    /// [slice](Reader::slice).
+    ///
+    /// The history items precede the new items in the slice and are not
+    /// counted when consuming, i.e., [consume](Reader::consume) still refers to
+    /// the new items. The writer does not overwrite the history. Until `n`
+    /// items are consumed, the history contains only the consumed items. Lossy
+    /// readers lose the history first, when they fall behind.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is not smaller than the capacity of the buffer, if it is not a
+    /// multiple of the granularity of a buffer with an increased
+    /// [alignment](crate::double_mapped_buffer::Builder::alignment), or if
+    /// this is a [shared reader](super::Writer::add_shared_reader).
+    pub fn set_history(&mut self, n: usize) {
+        assert!(
+            n < self.capacity(),
+            "vmcircbuffer: history has to be smaller than the capacity of the buffer"
+        );
+        assert!(
+            n.is_multiple_of(granularity(&self.buffer)),
+            "vmcircbuffer: history has to be a multiple of the granularity of the buffer"
+        );
+        assert!(
+            self.worker.is_none(),
+            "vmcircbuffer: shared readers do not support history"
+        );
+        let mut state = self.state.lock().unwrap();
+        refresh(
+            &mut self.buffer,
+            &mut self.generation,
+            &self.current,
+            state.generation,
+        );
+        let capacity = self.buffer.capacity();
+        let writer_position = state.writer_position;
+        let ring = state.ring;
+        let my = &mut state.readers[self.id];
+        my.history = n;
+        if my.retained > n {
+            my.release(0, ring);
+            my.notify_writer(writer_position, capacity);
+            state.notify_producers(capacity);
+        }
+    }
+
+    /// Enable [Stats] for this reader and return them.
+    ///
+    /// The returned counters can be handed to a monitoring thread.
+    pub fn enable_stats(&mut self) -> Arc<Stats> {
+        let capacity = self.buffer.capacity();
+        self.stats
+            .get_or_insert_with(|| Arc::new(Stats::new(capacity)))
+            .clone()
+    }
+
+    /// [Stats] of this reader, if they were [enabled](Reader::enable_stats).
+    pub fn stats(&self) -> Option<Arc<Stats>> {
+        self.stats.clone()
+    }
+
+    /// Get a slice with the items available to read, together with the
+    /// metadata of these items.
+    ///
+    /// Returns `None` if the writer was dropped and all data was read. A reader
+    /// that the writer [detached](Reader::is_detached), since it stalled, also
+    /// gets `None`, even if the writer is still running and data is left. Use
+    /// [is_detached](Reader::is_detached) to tell both cases apart.
+    pub fn slice(&mut self, arm: bool) -> Option<(&[T], M::View)> {
+        self.slice_at_least(0, arm)
+    }
+
+    /// Get a slice with at least `n` items to read, together with the metadata
+    /// of these items.
+    ///
+    /// If less than `n` items are available, the slice is empty and, if `arm`
+    /// is set, the notifier is armed. Once the writer is dropped, also less
+    /// than `n` items are returned. Returns `None` if the writer was dropped
+    /// and all data was read or if the reader was
+    /// [detached](Reader::is_detached).
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer or the
+    /// [maximum chunk size](Reader::set_max_chunk).
+    pub fn slice_at_least(&mut self, n: usize, arm: bool) -> Option<(&[T], M::View)> {
+        let (offset, history, space, tags) = self.prepare_slice(n, 1, arm)?;
+        unsafe {
+            Some((
+                &self.buffer.slice_with_offset(offset)[0..history + space],
+                tags,
+            ))
+        }
+    }
+
+    /// Get a slice with a multiple of `n` items to read, together with the
+    /// metadata of these items.
+    ///
+    /// Like [slice_at_least](Reader::slice_at_least), but the remainder that
+    /// does not form a complete chunk is held back. Once the writer is
+    /// dropped, an incomplete last chunk is never returned, i.e., `None` is
+    /// returned, if less than `n` items are left. These items can still be
+    /// read with [slice](Reader::slice). The [history](Reader::set_history) is
+    /// not required to be a multiple of `n`.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero, if it exceeds the capacity of the buffer or the
+    /// [maximum chunk size](Reader::set_max_chunk), or if `n` is larger than
+    /// one and this is a [shared reader](super::Writer::add_shared_reader).
+    pub fn slice_chunks(&mut self, n: usize, arm: bool) -> Option<(&[T], M::View)> {
+        let (offset, history, space, tags) = self.prepare_slice(n, n, arm)?;
+        unsafe {
+            Some((
+                &self.buffer.slice_with_offset(offset)[0..history + space],
+                tags,
+            ))
+        }
+    }
+
+    /// Like [slice_at_least](Reader::slice_at_least) with items in multiples
+    /// of `chunk`, but also returns the number of
+    /// [history](Reader::set_history) items at the start of the slice.
+    ///
+    /// The wrappers use it to wait for new items and to skip the history, when
+    /// they copy items out of the buffer.
+    #[cfg(any(feature = "sync", feature = "async", feature = "nonblocking"))]
+    pub(crate) fn slice_parts(
+        &mut self,
+        n: usize,
+        chunk: usize,
+        arm: bool,
+    ) -> Option<SliceParts<'_, T, M>> {
+        let (offset, history, space, tags) = self.prepare_slice(n, chunk, arm)?;
+        unsafe {
+            Some((
+                &self.buffer.slice_with_offset(offset)[0..history + space],
+                history,
+                tags,
+            ))
+        }
+    }
+
+    /// New items of the last slice that were not consumed yet.
+    ///
+    /// In contrast to [slice](Reader::slice), this does not check for new
+    /// items, which allows to get them through a shared reference.
+    #[cfg(feature = "bytes")]
+    pub(crate) fn pending(&self) -> &[T] {
+        let state = self.state.lock().unwrap();
+        let my = &state.readers[self.id];
+        let position = match (self.worker, &my.shared) {
+            (Some(worker), Some(shared)) => {
+                let w = &shared.workers[worker];
+                match w.claim {
+                    Some((start, _)) => start - w.skipped as u64,
+                    None => return &[],
+                }
+            }
+            // discarded items are released, but still part of the slice
+            _ => my.position + my.retained as u64 - my.skipped as u64,
+        };
+        // the buffer might be older than the state, if the writer grew it
+        let offset = Ring::new(self.buffer.capacity()).wrap(position);
+        unsafe { &self.buffer.slice_with_offset(offset)[0..self.last_space] }
+    }
+
+    /// Offset, number of history items, number of new items, and metadata of
+    /// the next slice.
+    pub(super) fn prepare_slice(
+        &mut self,
+        n: usize,
+        chunk: usize,
+        arm: bool,
+    ) -> Option<(usize, usize, usize, M::View)> {
+        assert!(
+            chunk > 0,
+            "vmcircbuffer: chunks have to hold at least one item"
+        );
+        assert!(
+            chunk == 1 || self.worker.is_none(),
+            "vmcircbuffer: shared readers do not support chunks"
+        );
+        assert!(
+            n <= self.buffer.capacity() && n <= self.max_chunk,
+            "vmcircbuffer: requested more items than can be returned"
+        );
+        let (space, offset, history, done, tags) = self.space_and_offset_and_meta(n, chunk, arm);
+        self.last_space = space;
+        if let Some(ref stats) = self.stats {
+            stats.record_slice(space, space);
+        }
+        if space == 0 && done {
+            None
+        } else {
+            Some((offset, history, space, tags))
+        }
+    }
+
+    /// Indicates that `n` items were read.
+    ///
+    /// # Panics
+    ///
+    /// If consumed more than space was available in the last provided slice or,
+    /// for a buffer with an increased
+    /// [alignment](crate::double_mapped_buffer::Builder::alignment), a number
+    /// of items that is not a multiple of its granularity.
+    pub fn consume(&mut self, n: usize) {
+        if let Err(e) = self.try_consume(n) {
+            consume_failed(e);
+        }
+    }
+
+    /// Consume all items of the last provided slice.
+    ///
+    /// Returns the number of consumed items. Items of the
+    /// [history](Reader::set_history) are not counted.
+    pub fn consume_all(&mut self) -> usize {
+        let n = self.last_space;
+        self.consume(n);
+        n
+    }
+
+    /// Indicates that `n` items were read.
+    ///
+    /// In contrast to [consume](Reader::consume), an error is returned if
+    /// consumed more than space was available in the last provided slice.
+    pub fn try_consume(&mut self, n: usize) -> Result<(), ConsumeError> {
+        if n == 0 {
+            return Ok(());
+        }
+
+        if n > self.last_space {
+            return Err(ConsumeError::TooMany {
+                requested: n,
+                available: self.last_space,
+            });
+        }
+
+        let granularity = granularity(&self.buffer);
+        if !n.is_multiple_of(granularity) {
+            return Err(ConsumeError::Unaligned {
+                requested: n,
+                granularity,
+            });
+        }
+
+        debug_assert!({
+            let (lossy, skipped) = {
+                let state = self.state.lock().unwrap();
+                let my = &state.readers[self.id];
+                (my.lossy, my.skipped)
+            };
+            lossy || self.worker.is_some() || self.available() + skipped >= n
+        });
+
+        self.last_space -= n;
+        self.release_items(n);
+        Ok(())
+    }
+
+    /// Release `n` items that are available to the reader.
+    fn release_items(&mut self, n: usize) {
+        if let Some(ref stats) = self.stats {
+            stats.record_items(n, self.last_space);
+        }
+
+        let mut state = self.state.lock().unwrap();
+        refresh(
+            &mut self.buffer,
+            &mut self.generation,
+            &self.current,
+            state.generation,
+        );
+        let writer_position = state.writer_position;
+        let ring = state.ring;
+        let my = unsafe { state.readers.get_unchecked_mut(self.id) };
+        my.stalled_since = None;
+
+        if let Some(worker) = self.worker {
+            let shared = my.shared.as_mut().unwrap();
+            let w = &mut shared.workers[worker];
+            // items that were already discarded since the last slice
+            let skipped = std::cmp::min(w.skipped, n);
+            w.skipped -= skipped;
+            if n > skipped {
+                let (start, len) = w.claim.unwrap();
+                let k = n - skipped;
+                w.claim = if len > k {
+                    Some((start + k as u64, len - k))
+                } else {
+                    None
+                };
+            }
+            let delta = (shared.position() - my.position) as usize;
+            my.advance(delta, ring);
+            my.notify_writer(writer_position, self.buffer.capacity());
+            state.notify_producers(self.buffer.capacity());
+            if let Some(hook) = state.on_consume.clone() {
+                drop(state);
+                hook(self.id, n);
+            }
+            return;
+        }
+
+        // items that were already lost or discarded since the last slice
+        let skipped = std::cmp::min(my.skipped, n);
+        my.skipped -= skipped;
+        let n = n - skipped;
+
+        my.release(n, ring);
+        my.unhold(self.buffer.capacity());
+
+        my.notify_writer(writer_position, self.buffer.capacity());
+        state.notify_producers(self.buffer.capacity());
+        if let Some(hook) = state.on_consume.clone() {
+            drop(state);
+            hook(self.id, n);
+        }
+    }
+
+    /// Discard up to `n` items without getting a slice first.
+    ///
+    /// Returns the number of discarded items, which is less than `n`, if less
+    /// items are available. Discarded items of a previously returned slice
+    /// count as consumed, i.e., consuming the slice skips them.
+    pub fn discard(&mut self, n: usize) -> usize {
+        if let Some(worker) = self.worker {
+            return self.discard_claimed(worker, n);
+        }
+
+        let granularity = granularity(&self.buffer);
+        let mut state = self.state.lock().unwrap();
+        refresh(
+            &mut self.buffer,
+            &mut self.generation,
+            &self.current,
+            state.generation,
+        );
+
+        let ring = state.ring;
+        let capacity = ring.capacity;
+        let w_pos = state.writer_position;
+        let output_end = state.output_end();
+
+        let my = unsafe { state.readers.get_unchecked_mut(self.id) };
+        if my.detached {
+            return 0;
+        }
+        if my.lossy {
+            // items in the output space of a writer are lost
+            let start = output_end.saturating_sub(capacity as u64);
+            if my.position < start {
+                let lost = (start - my.position) as usize;
+                my.lose(lost, ring);
+            }
+        }
+        my.stalled_since = None;
+
+        let mut discarded = 0;
+        let mut skipped_holes = false;
+        while discarded < n {
+            let my = unsafe { state.readers.get_unchecked(self.id) };
+            let read = my.position + my.retained as u64;
+            let (next, limit) = state.skip_holes(read);
+
+            let my = unsafe { state.readers.get_unchecked_mut(self.id) };
+            if next != read {
+                // the history does not extend across holes
+                let k = (next - my.position) as usize;
+                my.meta.consume(k);
+                my.advance(k, ring);
+                my.retained = 0;
+                skipped_holes = true;
+            }
+
+            let k = std::cmp::min((std::cmp::min(w_pos, limit) - next) as usize, n - discarded);
+            let k = k - k % granularity;
+            if k == 0 {
+                break;
+            }
+            my.release(k, ring);
+            discarded += k;
+        }
+
+        let my = unsafe { state.readers.get_unchecked_mut(self.id) };
+        // the discarded items were the first ones of the last slice
+        my.skipped = std::cmp::min(my.skipped + discarded, self.last_space);
+        if discarded == 0 && !skipped_holes {
+            return 0;
+        }
+        my.notify_writer(w_pos, capacity);
+        state.notify_producers(capacity);
+
+        if let Some(ref stats) = self.stats {
+            stats.record_items(discarded, self.last_space);
+        }
+        if let Some(hook) = state.on_consume.clone() {
+            drop(state);
+            hook(self.id, discarded);
+        }
+        discarded
+    }
+
+    /// Discard up to `n` items of a shared reader, starting with its claim.
+    fn discard_claimed(&mut self, worker: usize, n: usize) -> usize {
+        let granularity = granularity(&self.buffer);
+        let mut discarded = 0;
+
+        // a claim resets the skipped items of the last slice
+        let skipped = self.state.lock().unwrap().readers[self.id]
+            .shared
+            .as_ref()
+            .unwrap()
+            .workers[worker]
+            .skipped;
+        while discarded < n {
+            let (space, _, _, _, _) = self.claim(worker, 0, false);
+            let k = std::cmp::min(space, n - discarded);
+            let k = k - k % granularity;
+            if k == 0 {
+                break;
+            }
+            self.release_items(k);
+            discarded += k;
+        }
+
+        // the discarded items were the first ones of the last slice
+        let mut state = self.state.lock().unwrap();
+        let shared = state.readers[self.id].shared.as_mut().unwrap();
+        shared.workers[worker].skipped = std::cmp::min(skipped + discarded, self.last_space);
+        discarded
+    }
+
+    /// Pause the reader.
+    ///
+    /// While paused, the reader does not block the writer. All available items
+    /// and items that are produced while the reader is paused are skipped,
+    /// i.e., slices are empty. The history is dropped. This allows, e.g., a
+    /// GUI to stop consuming temporarily, without removing the reader.
+    ///
+    /// # Panics
+    ///
+    /// If the reader is [shared](super::Writer::add_shared_reader).
+    pub fn pause(&mut self) {
+        assert!(
+            self.worker.is_none(),
+            "vmcircbuffer: shared readers cannot be paused"
+        );
+        self.last_space = 0;
+
+        let mut state = self.state.lock().unwrap();
+        refresh(
+            &mut self.buffer,
+            &mut self.generation,
+            &self.current,
+            state.generation,
+        );
+        let capacity = self.buffer.capacity();
+        let writer_position = state.writer_position;
+        let ring = state.ring;
+        let my = unsafe { state.readers.get_unchecked_mut(self.id) };
+        if my.paused {
+            return;
+        }
+
+        let total = (writer_position - my.position) as usize;
+        let n = total - my.retained;
+        my.paused = true;
+        my.paused_skipped = n;
+        my.skipped = 0;
+        my.unhold(capacity);
+        my.retained = 0;
+        my.meta.consume(total);
+        my.advance(total, ring);
+
+        my.notify_writer(writer_position, capacity);
+        state.notify_producers(capacity);
+        if let Some(hook) = state.on_consume.clone() {
+            drop(state);
+            hook(self.id, n);
+        }
+    }
+
+    /// Resume a [paused](Reader::pause) reader.
+    ///
+    /// The reader continues with the items that are produced from now on.
+    /// Returns the number of items that were skipped while it was paused.
+    pub fn resume(&mut self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let my = &mut state.readers[self.id];
+        my.paused = false;
+        mem::take(&mut my.paused_skipped)
+    }
+
+    /// Whether the reader is [paused](Reader::pause).
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().readers[self.id].paused
+    }
+
+    /// Discard all available items, i.e., jump to the current position of the
+    /// writer.
+    ///
+    /// Items of a previously returned slice are discarded as well. Returns the
+    /// number of discarded items.
+    pub fn skip_to_latest(&mut self) -> usize {
+        self.last_space = 0;
+
+        let mut state = self.state.lock().unwrap();
+        refresh(
+            &mut self.buffer,
+            &mut self.generation,
+            &self.current,
+            state.generation,
+        );
+        let writer_position = state.writer_position;
+        let ring = state.ring;
+        let my = unsafe { state.readers.get_unchecked_mut(self.id) };
+
+        let n = if let Some(worker) = self.worker {
+            let shared = my.shared.as_mut().unwrap();
+            let claimed = shared.workers[worker]
+                .claim
+                .take()
+                .map(|(_, len)| len)
+                .unwrap_or(0);
+            let released: usize = shared.released.drain(..).map(|(_, len)| len).sum();
+            let n = claimed + released + (writer_position - shared.claimed) as usize;
+            shared.claimed = writer_position;
+            let delta = (shared.position() - my.position) as usize;
+            my.advance(delta, ring);
+            n
+        } else {
+            let n = (writer_position - my.position) as usize - my.retained;
+            my.skipped = 0;
+            my.unhold(self.buffer.capacity());
+            my.release(n, ring);
+            n
+        };
+
+        my.notify_writer(writer_position, self.buffer.capacity());
+        state.notify_producers(self.buffer.capacity());
+        if let Some(hook) = state.on_consume.clone() {
+            drop(state);
+            hook(self.id, n);
+        }
+        n
+    }
+}
+
+impl<T, N, M, W> fmt::Debug for Reader<T, N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reader")
+            .field("id", &self.id)
+            .field("capacity", &self.buffer.capacity())
+            .field("worker", &self.worker)
+            .field("last_space", &self.last_space)
+            .field("state", &*self.state.lock().unwrap())
+            .finish()
+    }
+}
+
+impl<T, N, M, W> Drop for Reader<T, N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        refresh(
+            &mut self.buffer,
+            &mut self.generation,
+            &self.current,
+            state.generation,
+        );
+        let writer_position = state.writer_position;
+        let ring = state.ring;
+
+        if let Some(worker) = self.worker {
+            let my = &mut state.readers[self.id];
+            let shared = my.shared.as_mut().unwrap();
+            // items that were claimed but not consumed go to the other workers
+            if let Some((start, len)) = shared.workers.remove(worker).claim {
+                shared.release(start, len);
+            }
+            if !shared.workers.is_empty() {
+                let delta = (shared.position() - my.position) as usize;
+                my.advance(delta, ring);
+                my.notify_writer(writer_position, self.buffer.capacity());
+                state.notify_producers(self.buffer.capacity());
+                return;
+            }
+        }
+
+        let mut s = state.readers.remove(self.id);
+        let base = state
+            .readers
+            .iter()
+            .filter(|(_, r)| r.blocks_writer())
+            .map(|(_, r)| r.position)
+            .min()
+            .unwrap_or(writer_position);
+        s.writer_notifier
+            .notify_available(self.buffer.capacity() - (writer_position - base) as usize);
+        state.notify_producers(self.buffer.capacity());
+    }
+}
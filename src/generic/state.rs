@@ -0,0 +1,534 @@
+//! State of a buffer that is shared between its writers and readers.
+//!
+//! The [State] is only accessed with its lock held. It tracks the positions
+//! of the writer and of all readers, which never lag behind the writer by
+//! more than the capacity of the buffer.
+
+use slab::Slab;
+use std::fmt;
+use std::mem;
+use std::ops::{Deref, DerefMut, Range};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::double_mapped_buffer::DoubleMappedBuffer;
+
+use super::drops::DropItems;
+use super::producers::Producers;
+use super::{Metadata, Notifier, ReaderSnapshot, Snapshot};
+
+pub(super) struct State<N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    pub(super) writer_offset: usize,
+    pub(super) writer_ab: bool,
+    pub(super) writer_done: bool,
+    pub(super) writer_position: u64,
+    // end of the output space that is handed out to the writer, if there is
+    // only one writer
+    pub(super) slice_end: u64,
+    pub(super) readers: Slab<CachePadded<ReaderState<N, M, W>>>,
+    pub(super) producers: Option<Producers<W>>,
+    // reserved items of dropped writers that were never produced
+    pub(super) holes: Vec<Range<u64>>,
+    pub(super) on_produce: Option<ProduceHook>,
+    pub(super) on_consume: Option<ConsumeHook>,
+    // items before this position were dropped (or never initialized)
+    pub(super) dropped: u64,
+    // drops the remaining items of types that need drop
+    pub(super) drop_items: Option<DropItems>,
+    // capacity of the current buffer, which changes when the writer grows it
+    pub(super) ring: Ring,
+    // incremented, whenever the writer grows the buffer
+    pub(super) generation: u64,
+    // readers that block the writer for longer are detached
+    pub(super) stall_timeout: Option<Duration>,
+}
+
+pub(super) type ProduceHook = Arc<dyn Fn(usize) + Send + Sync>;
+pub(super) type ConsumeHook = Arc<dyn Fn(usize, usize) + Send + Sync>;
+
+/// Current buffer of a circular buffer that [grows](super::Writer::grow).
+pub(super) type BufferSlot<T> = Arc<Mutex<Arc<DoubleMappedBuffer<T>>>>;
+
+/// Switch a reader to the current buffer, if the writer
+/// [grew](super::Writer::grow) it.
+///
+/// This has to be called with the lock of the state held.
+pub(super) fn refresh<T>(
+    buffer: &mut Arc<DoubleMappedBuffer<T>>,
+    generation: &mut u64,
+    current: &Mutex<Arc<DoubleMappedBuffer<T>>>,
+    state_generation: u64,
+) {
+    if *generation != state_generation {
+        *buffer = current.lock().unwrap().clone();
+        *generation = state_generation;
+    }
+}
+
+/// Number of items, in multiples of which a buffer with an increased
+/// [alignment](crate::double_mapped_buffer::Builder::alignment) is produced
+/// and consumed.
+pub(super) fn granularity<T>(buffer: &DoubleMappedBuffer<T>) -> usize {
+    std::cmp::max(1, buffer.alignment() / mem::size_of::<T>())
+}
+
+/// Capacity of a buffer to wrap positions to offsets.
+///
+/// Buffers with a
+/// [power-of-two](crate::double_mapped_buffer::Builder::power_of_two)
+/// capacity use a mask instead of the modulo operation. The mask is computed
+/// once, when the buffer is created or [grows](super::Writer::grow).
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Ring {
+    pub(super) capacity: usize,
+    pub(super) mask: Option<u64>,
+}
+
+impl Ring {
+    pub(super) fn new(capacity: usize) -> Self {
+        Ring {
+            capacity,
+            mask: capacity.is_power_of_two().then(|| capacity as u64 - 1),
+        }
+    }
+
+    /// Wrap a position to an offset in the buffer.
+    #[inline]
+    pub(super) fn wrap(self, position: u64) -> usize {
+        match self.mask {
+            Some(mask) => (position & mask) as usize,
+            None => (position % self.capacity as u64) as usize,
+        }
+    }
+}
+
+impl<N, M, W> State<N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    /// Consistent view of the writer and the readers.
+    pub(super) fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            capacity: self.ring.capacity,
+            writer_offset: self.writer_offset,
+            writer_position: self.writer_position,
+            done: self.writer_done,
+            readers: self
+                .readers
+                .iter()
+                .map(|(id, r)| {
+                    let position = r.position + r.retained as u64;
+                    ReaderSnapshot {
+                        id,
+                        offset: self.ring.wrap(position),
+                        position,
+                        lag: r.lag(self.writer_position),
+                        lossy: r.lossy,
+                        shared: r.shared.is_some(),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Make `n` more items visible to the readers and annotate the items,
+    /// starting at absolute position `start`, with `meta`.
+    pub(super) fn commit(&mut self, start: u64, n: usize, meta: Option<Arc<[M::Item]>>) {
+        let ring = self.ring;
+        let capacity = ring.capacity;
+        for (_, r) in self.readers.iter_mut() {
+            let r = &mut **r;
+            if let (Some(ref meta), None) = (&meta, &r.shared) {
+                r.meta.add((start - r.position) as usize, meta.clone());
+            }
+
+            if n == 0 {
+                continue;
+            }
+
+            let space = (self.writer_position - r.position) as usize;
+            if r.paused {
+                // follow the writer, without blocking it
+                r.advance(space + n, ring);
+                r.meta.consume(space + n);
+                r.paused_skipped += space + n;
+                continue;
+            }
+            if r.lossy && space + n > capacity {
+                r.lose(space + n - capacity, ring);
+            }
+
+            r.notify_reader(std::cmp::min(space + n, capacity) - r.retained);
+        }
+
+        if self.writer_offset + n >= capacity {
+            self.writer_ab = !self.writer_ab;
+        }
+        self.writer_offset = ring.wrap((self.writer_offset + n) as u64);
+        self.writer_position += n as u64;
+        // producing ends the slice of the writer
+        self.slice_end = self.writer_position;
+
+        if !self.holes.is_empty() {
+            let position = self
+                .readers
+                .iter()
+                .map(|(_, r)| r.position)
+                .min()
+                .unwrap_or(self.writer_position);
+            self.holes.retain(|h| h.end > position);
+        }
+    }
+
+    /// End of the output space that is handed out to writers, i.e., items
+    /// before `output_end() - capacity` can no longer be read.
+    pub(super) fn output_end(&self) -> u64 {
+        match self.producers {
+            Some(ref producers) => std::cmp::max(producers.reserved, self.writer_position),
+            None => self.slice_end,
+        }
+    }
+
+    /// Skip holes at `position`.
+    ///
+    /// Returns the new position and the start of the next hole, i.e., the
+    /// position up to which items can be read.
+    pub(super) fn skip_holes(&self, mut position: u64) -> (u64, u64) {
+        for h in self.holes.iter() {
+            if h.end <= position {
+                continue;
+            }
+            if h.start > position {
+                return (position, h.start);
+            }
+            position = h.end;
+        }
+        (position, u64::MAX)
+    }
+}
+
+/// Entry of a buffer in the debug registry.
+#[cfg(feature = "debug")]
+pub(super) struct Registered<N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    pub(super) name: String,
+    pub(super) state: std::sync::Weak<Mutex<State<N, M, W>>>,
+}
+
+#[cfg(feature = "debug")]
+impl<N, M, W> crate::debug::Inspect for Registered<N, M, W>
+where
+    N: Notifier + Send,
+    M: Metadata + Send,
+    W: Notifier + Send,
+{
+    fn inspect(&self) -> Option<crate::debug::BufferInfo> {
+        let state = self.state.upgrade()?;
+        let state = state.lock().unwrap();
+        let reader_lags: Vec<usize> = state
+            .readers
+            .iter()
+            .map(|(_, r)| r.lag(state.writer_position))
+            .collect();
+        Some(crate::debug::BufferInfo {
+            name: self.name.clone(),
+            capacity: state.ring.capacity,
+            occupancy: reader_lags.iter().copied().max().unwrap_or(0),
+            reader_lags,
+            position: state.writer_position,
+        })
+    }
+
+    fn is_dropped(&self) -> bool {
+        self.state.strong_count() == 0
+    }
+}
+
+impl<N, M, W> fmt::Debug for State<N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("State")
+            .field("writer_offset", &self.writer_offset)
+            .field("writer_ab", &self.writer_ab)
+            .field("writer_done", &self.writer_done)
+            .field("writer_position", &self.writer_position)
+            .field("capacity", &self.ring.capacity)
+            .field("readers", &DebugReaders(self))
+            .field(
+                "writers",
+                &self.producers.as_ref().map_or(1, |p| p.slots.len()),
+            )
+            .field("holes", &self.holes)
+            .finish()
+    }
+}
+
+/// Map from the id of a reader to its state and lag.
+struct DebugReaders<'a, N, M, W>(&'a State<N, M, W>)
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier;
+
+impl<N, M, W> fmt::Debug for DebugReaders<'_, N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.readers.iter().map(|(id, r)| {
+                (
+                    id,
+                    DebugReader {
+                        lag: r.lag(self.0.writer_position),
+                        state: r,
+                    },
+                )
+            }))
+            .finish()
+    }
+}
+
+struct DebugReader<'a, N, M, W> {
+    lag: usize,
+    state: &'a ReaderState<N, M, W>,
+}
+
+impl<N, M, W> fmt::Debug for DebugReader<'_, N, M, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReaderState")
+            .field("lag", &self.lag)
+            .field("ab", &self.state.ab)
+            .field("offset", &self.state.offset)
+            .field("position", &self.state.position)
+            .field("history", &self.state.retained)
+            .field("lossy", &self.state.lossy)
+            .field("shared", &self.state.shared.is_some())
+            .finish()
+    }
+}
+
+/// Aligns a value to a cache line to avoid false sharing.
+///
+/// 128 bytes cover the adjacent-line prefetcher of x86 and the cache lines of
+/// recent ARM cores.
+#[repr(align(128))]
+pub(super) struct CachePadded<T>(pub(super) T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CachePadded<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+pub(super) struct ReaderState<N, M, W> {
+    pub(super) ab: bool,
+    pub(super) offset: usize,
+    pub(super) position: u64,
+    pub(super) lossy: bool,
+    pub(super) overrun: usize,
+    // items of the last slice that were lost or discarded, i.e., that a
+    // consume of the slice skips
+    pub(super) skipped: usize,
+    pub(super) shared: Option<Shared<N>>,
+    pub(super) reader_notifier: N,
+    pub(super) writer_notifier: W,
+    // number of items (free slots) that have to be available to fire the armed
+    // reader (writer) notifier
+    pub(super) reader_want: usize,
+    pub(super) writer_want: usize,
+    // maximum number of consumed items that are kept before the read position
+    pub(super) history: usize,
+    // number of consumed items that are kept, i.e., the read position is
+    // `position + retained`
+    pub(super) retained: usize,
+    // since when the reader blocks the writer, if a stall timeout is set
+    pub(super) stalled_since: Option<Instant>,
+    // the reader was detached by the writer, since it stalled
+    pub(super) detached: bool,
+    // the reader holds a slice, which the writer must not overwrite, even if
+    // the reader is lossy
+    pub(super) holding: bool,
+    // a paused reader follows the writer and counts the skipped items
+    pub(super) paused: bool,
+    pub(super) paused_skipped: usize,
+    pub(super) meta: M,
+}
+
+impl<N, M, W> ReaderState<N, M, W> {
+    pub(super) fn advance(&mut self, n: usize, ring: Ring) {
+        if self.offset + n >= ring.capacity {
+            self.ab = !self.ab;
+        }
+        self.offset = ring.wrap((self.offset + n) as u64);
+        self.position += n as u64;
+    }
+
+    /// Number of produced items that the reader did not consume yet, not
+    /// counting the retained history.
+    pub(super) fn lag(&self, writer_position: u64) -> usize {
+        (writer_position - self.position) as usize - self.retained
+    }
+
+    /// Whether the writer has to wait for the reader. Lossy readers block the
+    /// writer only while they hold a slice.
+    pub(super) fn blocks_writer(&self) -> bool {
+        !self.lossy || self.holding
+    }
+}
+
+impl<N, M: Metadata, W> ReaderState<N, M, W> {
+    /// Drop `n` items of a lossy reader that are overwritten by the writer.
+    pub(super) fn lose(&mut self, n: usize, ring: Ring) {
+        self.advance(n, ring);
+        self.meta.consume(n);
+        // the history is overwritten first
+        let kept = std::cmp::min(n, self.retained);
+        self.retained -= kept;
+        self.overrun += n - kept;
+        self.skipped += n - kept;
+    }
+
+    /// Mark `n` items after the read position as consumed, keeping up to
+    /// `history` consumed items.
+    pub(super) fn release(&mut self, n: usize, ring: Ring) {
+        let total = self.retained + n;
+        self.retained = std::cmp::min(total, self.history);
+        let k = total - self.retained;
+        self.meta.consume(k);
+        self.advance(k, ring);
+    }
+}
+
+impl<N: Notifier, M, W: Notifier> ReaderState<N, M, W> {
+    /// Notify the reader, if `available` items reach its watermark.
+    pub(super) fn notify_reader(&mut self, available: usize) {
+        if available >= self.reader_want {
+            self.notify(available);
+        }
+    }
+
+    /// Notify the writer, if the free slots reach its watermark.
+    pub(super) fn notify_writer(&mut self, writer_position: u64, capacity: usize) {
+        let free = capacity - (writer_position - self.position) as usize;
+        if free >= self.writer_want {
+            self.writer_notifier.notify_available(free);
+        }
+    }
+
+    /// Release the slice of a lossy reader, which no longer blocks the
+    /// writer then.
+    pub(super) fn unhold(&mut self, capacity: usize) {
+        if mem::take(&mut self.holding) && self.lossy {
+            self.writer_notifier.notify_available(capacity);
+        }
+    }
+
+    /// Track, if the reader keeps the writer from getting `want` items, while
+    /// only `space` are free. Returns `true`, if the reader stalled for
+    /// `timeout` and got detached.
+    pub(super) fn detach_stalled(&mut self, space: usize, want: usize, timeout: Duration) -> bool {
+        if self.shared.is_some() || self.lossy {
+            return false;
+        }
+        if space >= want {
+            self.stalled_since = None;
+            return false;
+        }
+        if self
+            .stalled_since
+            .get_or_insert_with(Instant::now)
+            .elapsed()
+            < timeout
+        {
+            return false;
+        }
+        // from now on, the reader is treated like a lossy reader, but a slice
+        // it still holds is not overwritten
+        self.detached = true;
+        self.lossy = true;
+        self.stalled_since = None;
+        // wake up the reader, which returns `None` from now on
+        self.notify(0);
+        true
+    }
+
+    pub(super) fn notify(&mut self, available: usize) {
+        self.reader_notifier.notify_available(available);
+        if let Some(ref mut shared) = self.shared {
+            for (_, w) in shared.workers.iter_mut() {
+                if let Some(ref mut n) = w.notifier {
+                    n.notify_available(available);
+                }
+            }
+        }
+    }
+}
+
+/// Consumption cursor that is shared between competing readers.
+pub(super) struct Shared<N> {
+    pub(super) claimed: u64,
+    pub(super) workers: Slab<Worker<N>>,
+    // claims of dropped workers that were not consumed, sorted by position
+    pub(super) released: Vec<(u64, usize)>,
+}
+
+impl<N> Shared<N> {
+    /// Position of the oldest item that was not yet consumed by any worker.
+    pub(super) fn position(&self) -> u64 {
+        self.workers
+            .iter()
+            .filter_map(|(_, w)| w.claim.map(|(start, _)| start))
+            .chain(self.released.first().map(|&(start, _)| start))
+            .min()
+            .unwrap_or(self.claimed)
+    }
+
+    /// Return the claim of a dropped worker, such that other workers get the
+    /// items.
+    pub(super) fn release(&mut self, start: u64, len: usize) {
+        let i = self.released.partition_point(|&(s, _)| s < start);
+        self.released.insert(i, (start, len));
+        // claims at the end are claimed again by extending the cursor
+        while let Some(&(start, len)) = self.released.last() {
+            if start + len as u64 != self.claimed {
+                break;
+            }
+            self.claimed = start;
+            self.released.pop();
+        }
+    }
+}
+
+pub(super) struct Worker<N> {
+    // the first worker uses the reader notifier of the reader state
+    pub(super) notifier: Option<N>,
+    pub(super) claim: Option<(u64, usize)>,
+    // items of the last slice that were discarded
+    pub(super) skipped: usize,
+}
@@ -0,0 +1,132 @@
+//! [Stats] and [Snapshot]s to observe a buffer at runtime.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Consistent view of the state of a buffer, taken with
+/// [state_snapshot](super::Writer::state_snapshot).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Capacity of the buffer in items.
+    pub capacity: usize,
+    /// Offset of the writer in the buffer.
+    pub writer_offset: usize,
+    /// Absolute position of the writer, i.e., the number of produced items.
+    pub writer_position: u64,
+    /// Whether all writers are dropped.
+    pub done: bool,
+    /// State of the readers.
+    pub readers: Vec<ReaderSnapshot>,
+}
+
+/// State of one reader in a [Snapshot].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReaderSnapshot {
+    /// [Identifier](super::Reader::id) of the reader.
+    pub id: usize,
+    /// Offset of the read position in the buffer.
+    pub offset: usize,
+    /// Absolute read position, i.e., the number of consumed items.
+    pub position: u64,
+    /// Number of produced items that the reader did not consume yet.
+    pub lag: usize,
+    /// Whether the reader is [lossy](super::Writer::add_lossy_reader).
+    pub lossy: bool,
+    /// Whether the reader is [shared](super::Writer::add_shared_reader).
+    pub shared: bool,
+}
+
+/// Counters of a [Writer](super::Writer) or [Reader](super::Reader), which are
+/// enabled with `enable_stats`.
+///
+/// The counters are updated with relaxed atomics and can be read from another
+/// thread, e.g., to find the bottleneck of a pipeline at runtime.
+#[derive(Debug)]
+pub struct Stats {
+    pub(super) capacity: usize,
+    pub(super) items: AtomicU64,
+    pub(super) wakeups: AtomicU64,
+    pub(super) blocked: AtomicU64,
+    pub(super) occupancy: AtomicUsize,
+    pub(super) max_occupancy: AtomicUsize,
+    pub(super) empty_slices: AtomicU64,
+}
+
+impl Stats {
+    pub(super) fn new(capacity: usize) -> Self {
+        Stats {
+            capacity,
+            items: AtomicU64::new(0),
+            wakeups: AtomicU64::new(0),
+            blocked: AtomicU64::new(0),
+            occupancy: AtomicUsize::new(0),
+            max_occupancy: AtomicUsize::new(0),
+            empty_slices: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of items produced by the writer or consumed by the reader.
+    pub fn items(&self) -> u64 {
+        self.items.load(Ordering::Relaxed)
+    }
+
+    /// Number of times a blocking call waited for a notification.
+    pub fn wakeups(&self) -> u64 {
+        self.wakeups.load(Ordering::Relaxed)
+    }
+
+    /// Total time spent waiting in blocking calls.
+    pub fn blocked(&self) -> Duration {
+        Duration::from_nanos(self.blocked.load(Ordering::Relaxed))
+    }
+
+    /// Number of items in the buffer, as seen by the last call of the writer
+    /// or reader.
+    pub fn occupancy(&self) -> usize {
+        self.occupancy.load(Ordering::Relaxed)
+    }
+
+    /// Maximum [occupancy](Stats::occupancy) since the stats were enabled.
+    ///
+    /// If this stays well below the capacity, the buffer can be smaller.
+    pub fn max_occupancy(&self) -> usize {
+        self.max_occupancy.load(Ordering::Relaxed)
+    }
+
+    /// Minimum free space in the buffer since the stats were enabled, i.e.,
+    /// the capacity minus the [maximum occupancy](Stats::max_occupancy).
+    pub fn min_free(&self) -> usize {
+        self.capacity - self.max_occupancy()
+    }
+
+    /// Number of slices that were empty, i.e., how often a reader found no
+    /// items to read or a writer found the buffer full.
+    pub fn empty_slices(&self) -> u64 {
+        self.empty_slices.load(Ordering::Relaxed)
+    }
+
+    /// Record that a blocking call waited for `duration`.
+    ///
+    /// This is called by the blocking implementations and can be used by
+    /// custom implementations on top of the generic buffer.
+    pub fn record_wait(&self, duration: Duration) {
+        self.wakeups.fetch_add(1, Ordering::Relaxed);
+        self.blocked.fetch_add(
+            duration.as_nanos().min(u64::MAX as u128) as u64,
+            Ordering::Relaxed,
+        );
+    }
+
+    pub(super) fn record_items(&self, n: usize, occupancy: usize) {
+        self.items.fetch_add(n as u64, Ordering::Relaxed);
+        self.occupancy.store(occupancy, Ordering::Relaxed);
+        self.max_occupancy.fetch_max(occupancy, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_slice(&self, space: usize, occupancy: usize) {
+        if space == 0 {
+            self.empty_slices.fetch_add(1, Ordering::Relaxed);
+        }
+        self.record_items(0, occupancy);
+    }
+}
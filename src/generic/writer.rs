@@ -0,0 +1,813 @@
+//! [Writer] of a generic circular buffer.
+
+use slab::Slab;
+use std::fmt;
+use std::mem::{self, MaybeUninit};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::double_mapped_buffer::{Builder, DoubleMappedBuffer};
+
+use super::drops::{drop_items, DropItems};
+#[cfg(feature = "debug")]
+use super::state::Registered;
+use super::state::{
+    granularity, BufferSlot, CachePadded, ReaderState, Ring, Shared, State, Worker,
+};
+use super::{
+    produce_failed, CircularError, Metadata, Notifier, Pod, ProduceError, Reader, ReaderOptions,
+    Snapshot, Stats,
+};
+
+/// Writer for a generic circular buffer with items of type `T` and [Notifier] of type `N`.
+///
+/// Readers are signaled through notifiers of type `N`, writers through
+/// notifiers of type `W`, which defaults to `N`.
+pub struct Writer<T, N, M, W = N>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    pub(super) producer: Option<usize>,
+    pub(super) lossy: bool,
+    pub(super) last_space: usize,
+    // space of the last uninit slice
+    pub(super) uninit_space: usize,
+    pub(super) watermark: usize,
+    pub(super) stats: Option<Arc<Stats>>,
+    pub(super) state: Arc<Mutex<State<N, M, W>>>,
+    pub(super) buffer: Arc<DoubleMappedBuffer<T>>,
+    pub(super) ring: Ring,
+    pub(super) current: BufferSlot<T>,
+    // configuration of the buffer, which is kept when it grows
+    pub(super) builder: Option<Builder>,
+}
+
+impl<T, N, M, W> Writer<T, N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    /// Add a [Reader] to the buffer.
+    ///
+    /// If the buffer was created with
+    /// [with_capacity_lossy](super::Circular::with_capacity_lossy), the reader is
+    /// [lossy](Writer::add_lossy_reader).
+    pub fn add_reader(&self, reader_notifier: N, writer_notifier: W) -> Reader<T, N, M, W> {
+        self.add_reader_state(reader_notifier, writer_notifier, self.lossy)
+    }
+
+    /// Add a [Reader] to the buffer that is configured by [ReaderOptions].
+    ///
+    /// # Panics
+    ///
+    /// If an option is invalid for the buffer, see the corresponding setter
+    /// of the [Reader].
+    pub fn add_reader_with_opts(
+        &self,
+        reader_notifier: N,
+        writer_notifier: W,
+        opts: &ReaderOptions,
+    ) -> Reader<T, N, M, W> {
+        let mut reader = self.add_reader_state(
+            reader_notifier,
+            writer_notifier,
+            self.lossy || opts.non_blocking,
+        );
+        if opts.max_chunk != usize::MAX {
+            reader.set_max_chunk(opts.max_chunk);
+        }
+        reader.set_watermark(opts.watermark);
+        if opts.history > 0 {
+            reader.set_history(opts.history);
+        }
+        reader
+    }
+
+    /// Add a lossy [Reader] to the buffer.
+    ///
+    /// A lossy reader does not hold back the writer. If it falls behind, the
+    /// oldest items are dropped and the reader continues with the most recent
+    /// data that fits into the buffer. The number of dropped items can be
+    /// queried with [take_overrun](Reader::take_overrun).
+    ///
+    /// The writer does not overwrite a slice, while the reader holds it, i.e.,
+    /// between [slice](Reader::slice) and [consume](Reader::consume). Only
+    /// then, the writer waits for a lossy reader.
+    pub fn add_lossy_reader(&self, reader_notifier: N, writer_notifier: W) -> Reader<T, N, M, W> {
+        self.add_reader_state(reader_notifier, writer_notifier, true)
+    }
+
+    /// Add a shared [Reader] to the buffer.
+    ///
+    /// In contrast to normal readers, which all see every item, shared readers
+    /// compete for the items. Further readers that share the consumption
+    /// cursor are created with [share](Reader::share). Each item is delivered
+    /// to exactly one of them, i.e., they form a work queue.
+    ///
+    /// A slice claims the returned items for the reader until they are
+    /// consumed. If a reader is dropped, the items it claimed but did not
+    /// consume are handed out to the other readers. Shared readers are never
+    /// lossy and do not support metadata.
+    pub fn add_shared_reader(&self, reader_notifier: N, writer_notifier: W) -> Reader<T, N, M, W> {
+        let mut state = self.state.lock().unwrap();
+        let mut workers = Slab::new();
+        let worker = workers.insert(Worker {
+            notifier: None,
+            claim: None,
+            skipped: 0,
+        });
+        let reader_state = ReaderState {
+            ab: state.writer_ab,
+            offset: state.writer_offset,
+            position: state.writer_position,
+            lossy: false,
+            overrun: 0,
+            skipped: 0,
+            shared: Some(Shared {
+                claimed: state.writer_position,
+                workers,
+                released: Vec::new(),
+            }),
+            reader_notifier,
+            writer_notifier,
+            reader_want: 0,
+            writer_want: 0,
+            history: 0,
+            retained: 0,
+            stalled_since: None,
+            detached: false,
+            holding: false,
+            paused: false,
+            paused_skipped: 0,
+            meta: M::new(),
+        };
+        let id = state.readers.insert(CachePadded(reader_state));
+
+        Reader {
+            id,
+            worker: Some(worker),
+            last_space: 0,
+            max_chunk: usize::MAX,
+            watermark: 0,
+            stats: None,
+            buffer: self.buffer.clone(),
+            generation: state.generation,
+            current: self.current.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    fn add_reader_state(
+        &self,
+        reader_notifier: N,
+        writer_notifier: W,
+        lossy: bool,
+    ) -> Reader<T, N, M, W> {
+        assert!(
+            !(lossy && mem::needs_drop::<T>()),
+            "vmcircbuffer: lossy readers do not support types that need drop"
+        );
+        let mut state = self.state.lock().unwrap();
+        let reader_state = ReaderState {
+            ab: state.writer_ab,
+            offset: state.writer_offset,
+            position: state.writer_position,
+            lossy,
+            overrun: 0,
+            skipped: 0,
+            shared: None,
+            reader_notifier,
+            writer_notifier,
+            reader_want: 0,
+            writer_want: 0,
+            history: 0,
+            retained: 0,
+            stalled_since: None,
+            detached: false,
+            holding: false,
+            paused: false,
+            paused_skipped: 0,
+            meta: M::new(),
+        };
+        let id = state.readers.insert(CachePadded(reader_state));
+
+        Reader {
+            id,
+            worker: None,
+            last_space: 0,
+            max_chunk: usize::MAX,
+            watermark: 0,
+            stats: None,
+            buffer: self.buffer.clone(),
+            generation: state.generation,
+            current: self.current.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Reserve slots for at least `additional` more readers.
+    ///
+    /// Adding up to `additional` readers does not reallocate the table of
+    /// readers. With notifiers and metadata that do not allocate, e.g.,
+    /// [NoMetadata](super::NoMetadata), [add_reader](Writer::add_reader) does not allocate at all,
+    /// which is required in real-time contexts.
+    pub fn reserve_readers(&self, additional: usize) {
+        self.state.lock().unwrap().readers.reserve(additional);
+    }
+
+    /// Detach readers that block the writer for longer than `timeout`.
+    ///
+    /// A reader stalls, if the writer requests space that the reader does not
+    /// free, since it does not consume. Once the reader stalls for `timeout`,
+    /// it no longer blocks the writer. Its next [slice](Reader::slice) returns
+    /// `None` and [is_detached](Reader::is_detached) is set. This keeps one
+    /// hung reader from blocking the whole buffer. Like for lossy readers, a
+    /// slice that the reader still holds is not overwritten, i.e., its space
+    /// is only freed, once the reader consumes or requests a new slice or is
+    /// dropped.
+    ///
+    /// The stall is only detected, when the writer requests a slice, i.e., a
+    /// blocking writer has to wake up periodically.
+    /// [Shared readers](Writer::add_shared_reader) are never detached. `None`
+    /// disables the policy, which is the default.
+    ///
+    /// # Panics
+    ///
+    /// If `timeout` is set for a type that needs drop, since detached readers
+    /// are lossy.
+    pub fn set_stall_timeout(&self, timeout: Option<Duration>) {
+        assert!(
+            !(timeout.is_some() && mem::needs_drop::<T>()),
+            "vmcircbuffer: stall timeouts do not support types that need drop"
+        );
+        self.state.lock().unwrap().stall_timeout = timeout;
+    }
+
+    /// Timeout, after which stalled readers are
+    /// [detached](Writer::set_stall_timeout).
+    pub fn stall_timeout(&self) -> Option<Duration> {
+        self.state.lock().unwrap().stall_timeout
+    }
+
+    /// Set a callback that is called with the number of items, whenever a
+    /// writer of the buffer produces.
+    ///
+    /// The callback is called without holding the lock of the buffer state.
+    pub fn set_on_produce(&self, hook: impl Fn(usize) + Send + Sync + 'static) {
+        self.state.lock().unwrap().on_produce = Some(Arc::new(hook));
+    }
+
+    /// Set a callback that is called with the [id](Reader::id) of the reader
+    /// and the number of items, whenever a reader of the buffer consumes.
+    ///
+    /// The callback is called without holding the lock of the buffer state.
+    pub fn set_on_consume(&self, hook: impl Fn(usize, usize) + Send + Sync + 'static) {
+        self.state.lock().unwrap().on_consume = Some(Arc::new(hook));
+    }
+
+    /// Absolute number of items produced since the buffer was created.
+    ///
+    /// In contrast to the offsets in the circular buffer, the position does not
+    /// wrap and can be used to correlate items across buffers.
+    pub fn position(&self) -> u64 {
+        self.state.lock().unwrap().writer_position
+    }
+
+    /// Add the buffer to the [debug registry](crate::debug) under `name`.
+    ///
+    /// The buffer is removed from the registry, once all its readers and
+    /// writers are dropped. Remaining items might be dropped by the thread
+    /// that inspects the registry, which is why they have to be [Send].
+    #[cfg(feature = "debug")]
+    pub fn register(&self, name: impl Into<String>)
+    where
+        T: Send,
+        N: Send + 'static,
+        M: Send + 'static,
+        W: Send + 'static,
+    {
+        crate::debug::register(Box::new(Registered {
+            name: name.into(),
+            state: Arc::downgrade(&self.state),
+        }));
+    }
+
+    /// Number of items the buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Only notify the writer, once at least `n` items of space are free.
+    ///
+    /// This applies when a [slice](Writer::slice) arms the notifiers. It
+    /// batches wakeups, if readers consume in small chunks. Use `0` to be
+    /// notified whenever space becomes available.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub fn set_watermark(&mut self, n: usize) {
+        assert!(
+            n <= self.buffer.capacity(),
+            "vmcircbuffer: watermark exceeds the capacity of the buffer"
+        );
+        self.watermark = n;
+    }
+
+    /// Enable [Stats] for this writer and return them.
+    ///
+    /// The returned counters can be handed to a monitoring thread.
+    pub fn enable_stats(&mut self) -> Arc<Stats> {
+        let capacity = self.buffer.capacity();
+        self.stats
+            .get_or_insert_with(|| Arc::new(Stats::new(capacity)))
+            .clone()
+    }
+
+    /// [Stats] of this writer, if they were [enabled](Writer::enable_stats).
+    pub fn stats(&self) -> Option<Arc<Stats>> {
+        self.stats.clone()
+    }
+
+    /// Number of readers of the buffer.
+    ///
+    /// [Shared readers](Writer::add_shared_reader) count as one reader.
+    pub fn reader_count(&self) -> usize {
+        self.state.lock().unwrap().readers.len()
+    }
+
+    /// Whether any reader is attached to the buffer.
+    pub fn has_readers(&self) -> bool {
+        !self.state.lock().unwrap().readers.is_empty()
+    }
+
+    /// Number of produced items that each reader did not consume yet.
+    ///
+    /// [Shared readers](Writer::add_shared_reader) count as one reader.
+    pub fn reader_lags(&self) -> Vec<usize> {
+        let state = self.state.lock().unwrap();
+        state
+            .readers
+            .iter()
+            .map(|(_, r)| r.lag(state.writer_position))
+            .collect()
+    }
+
+    /// Snapshot of the writer and all readers.
+    ///
+    /// In contrast to querying [position](Writer::position) and
+    /// [reader_lags](Writer::reader_lags) separately, the values are taken
+    /// with a single lock of the state and, therefore, consistent.
+    pub fn state_snapshot(&self) -> Snapshot {
+        self.state.lock().unwrap().snapshot()
+    }
+
+    /// [Identifier](Reader::id) and lag of the reader that lags the most and,
+    /// therefore, throttles the writer.
+    ///
+    /// Lossy readers are not considered, since they never block the writer.
+    /// Returns `None`, if there is no such reader.
+    pub fn slowest_reader(&self) -> Option<(usize, usize)> {
+        let state = self.state.lock().unwrap();
+        state
+            .readers
+            .iter()
+            .filter(|(_, r)| r.blocks_writer())
+            .min_by_key(|(_, r)| r.position)
+            .map(|(id, r)| (id, r.lag(state.writer_position)))
+    }
+
+    /// Number of items that can be written, without getting a slice.
+    pub fn free_items(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        let capacity = self.buffer.capacity();
+        let base = state
+            .readers
+            .iter()
+            .filter(|(_, r)| r.blocks_writer())
+            .map(|(_, r)| r.position)
+            .min()
+            .unwrap_or(state.writer_position);
+
+        match (self.producer, &state.producers) {
+            (Some(id), Some(producers)) => {
+                let free = capacity - (producers.reserved - base) as usize;
+                match producers.slots[id].reservation {
+                    Some((start, len)) if start + len as u64 == producers.reserved => len + free,
+                    Some((_, len)) => len,
+                    None => free,
+                }
+            }
+            _ => capacity - (state.writer_position - base) as usize,
+        }
+    }
+
+    /// Grow the buffer, such that it can hold at least `min_items` items.
+    ///
+    /// This allocates a new buffer with the configuration of the current one
+    /// (e.g., its [Builder] settings), copies the items that are not yet consumed, and switches all readers to
+    /// the new buffer. Slices that readers obtained before remain valid. The
+    /// metadata is kept. Nothing happens, if the buffer can already hold
+    /// `min_items` items.
+    ///
+    /// # Panics
+    ///
+    /// If the buffer has [multiple writers](Writer::add_writer).
+    pub fn grow(&mut self, min_items: usize) -> Result<(), CircularError> {
+        assert!(
+            self.producer.is_none(),
+            "vmcircbuffer: buffers with multiple writers cannot grow"
+        );
+        if min_items <= self.buffer.capacity() {
+            return Ok(());
+        }
+
+        let builder = match self.builder {
+            Some(ref b) => b.clone().with_min_items(min_items),
+            None => Builder::new(min_items)
+                .alignment(self.buffer.alignment())
+                .mappings(self.buffer.mappings()),
+        };
+        let buffer: Arc<DoubleMappedBuffer<T>> = Arc::new(builder.build()?);
+        let ring = Ring::new(buffer.capacity());
+        let capacity = ring.capacity;
+
+        let mut state = self.state.lock().unwrap();
+        let writer_position = state.writer_position;
+        // lossy and detached readers do not hold the writer back, i.e., their
+        // items might already be handed out to the writer or dropped
+        let base = state
+            .readers
+            .iter()
+            .map(|(_, r)| r.position)
+            .min()
+            .unwrap_or(writer_position)
+            .max(state.dropped)
+            .max(state.output_end().saturating_sub(self.ring.capacity as u64));
+        for (_, r) in state.readers.iter_mut() {
+            if r.position < base {
+                let lost = (base - r.position) as usize;
+                r.lose(lost, self.ring);
+            }
+        }
+
+        // consumed items that were not yet dropped stay in the old buffer
+        let dropped = state.dropped;
+        if let Some(ref mut d) = state.drop_items {
+            unsafe { (d.drop)(d.buffer, self.ring, dropped..base) };
+            *d = DropItems::new(&buffer);
+        }
+        state.dropped = base;
+
+        let n = (writer_position - base) as usize;
+        unsafe {
+            let src = self.buffer.slice_with_offset(self.ring.wrap(base));
+            let dst = buffer.slice_with_offset_mut(ring.wrap(base));
+            std::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), n);
+            buffer.mirror(ring.wrap(base), n);
+        }
+
+        let ab = |position: u64| (position / capacity as u64) % 2 == 1;
+        state.writer_offset = ring.wrap(writer_position);
+        state.writer_ab = ab(writer_position);
+        for (_, r) in state.readers.iter_mut() {
+            r.offset = ring.wrap(r.position);
+            r.ab = ab(r.position);
+        }
+        state.ring = ring;
+        state.generation += 1;
+        *self.current.lock().unwrap() = buffer.clone();
+
+        self.buffer = buffer;
+        self.ring = ring;
+        self.last_space = 0;
+        self.uninit_space = 0;
+        Ok(())
+    }
+
+    fn space_and_offset(&self, min_items: usize, arm: bool) -> (usize, usize) {
+        if let Some(id) = self.producer {
+            return self.reserve(id, min_items, arm);
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let capacity = self.buffer.capacity();
+        let w_off = state.writer_offset;
+        let w_ab = state.writer_ab;
+
+        let stall_timeout = state.stall_timeout;
+
+        let mut space = capacity;
+
+        for (_, reader) in state.readers.iter_mut().filter(|(_, r)| r.blocks_writer()) {
+            let r_off = reader.offset;
+            let r_ab = reader.ab;
+
+            let s = if w_off > r_off {
+                r_off + capacity - w_off
+            } else if w_off < r_off {
+                r_off - w_off
+            } else if r_ab == w_ab {
+                capacity
+            } else {
+                0
+            };
+
+            if let Some(timeout) = stall_timeout {
+                if reader.detach_stalled(s, std::cmp::max(min_items, 1), timeout) && !reader.holding
+                {
+                    continue;
+                }
+            }
+
+            space = std::cmp::min(space, s);
+
+            if s < std::cmp::max(min_items, 1) && arm {
+                reader.writer_want = std::cmp::max(min_items, self.watermark);
+                reader.writer_notifier.arm();
+            }
+            if s == 0 {
+                break;
+            }
+        }
+
+        let space = if space < min_items { 0 } else { space };
+        state.slice_end = state.writer_position + space as u64;
+        (space, w_off)
+    }
+
+    /// Get a slice for the output buffer space. Might be empty.
+    ///
+    /// The slice contains items that were already consumed or, on the first
+    /// pass through the buffer, zeroed memory, which is why `T` has to be
+    /// [Pod]. Use [uninit_slice](Writer::uninit_slice) for other types.
+    pub fn slice(&mut self, arm: bool) -> &mut [T]
+    where
+        T: Pod,
+    {
+        self.slice_at_least(0, arm)
+    }
+
+    /// Get a slice for the output buffer space with at least `n` items.
+    ///
+    /// If less than `n` items of space are available, the slice is empty and,
+    /// if `arm` is set, the notifiers of the readers that block the writer are
+    /// armed.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub fn slice_at_least(&mut self, n: usize, arm: bool) -> &mut [T]
+    where
+        T: Pod,
+    {
+        let (space, offset) = self.prepare_slice(n, arm);
+        unsafe { &mut self.buffer.slice_with_offset_mut(offset)[0..space] }
+    }
+
+    /// Get a slice for the output buffer space with a multiple of `n` items.
+    ///
+    /// Like [slice_at_least](Writer::slice_at_least), but space that does not
+    /// fit a complete chunk is held back.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero or if it exceeds the capacity of the buffer.
+    pub fn slice_chunks(&mut self, n: usize, arm: bool) -> &mut [T]
+    where
+        T: Pod,
+    {
+        assert!(n > 0, "vmcircbuffer: chunks have to hold at least one item");
+        let (space, offset) = self.prepare_slice(n, arm);
+        let space = space - space % n;
+        self.last_space = space;
+        unsafe { &mut self.buffer.slice_with_offset_mut(offset)[0..space] }
+    }
+
+    /// Get a slice of uninitialized output buffer space. Might be empty.
+    ///
+    /// Items have to be initialized before they are produced with
+    /// [assume_init_produce](Writer::assume_init_produce). This also supports
+    /// types that need drop. Their items are dropped, once all readers
+    /// consumed them and the space is handed out again or the buffer is
+    /// dropped.
+    pub fn uninit_slice(&mut self, arm: bool) -> &mut [MaybeUninit<T>] {
+        self.uninit_slice_at_least(0, arm)
+    }
+
+    /// Get a slice of uninitialized output buffer space with at least `n`
+    /// items.
+    ///
+    /// See [slice_at_least](Writer::slice_at_least) and
+    /// [uninit_slice](Writer::uninit_slice).
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub fn uninit_slice_at_least(&mut self, n: usize, arm: bool) -> &mut [MaybeUninit<T>] {
+        let (space, offset) = self.prepare_slice(n, arm);
+        self.uninit_space = mem::take(&mut self.last_space);
+
+        // drop the consumed items that are overwritten by this slice
+        let overwritten = self.hand_out(space);
+        unsafe {
+            drop_items::<T>(Arc::as_ptr(&self.buffer) as usize, self.ring, overwritten);
+        }
+
+        unsafe {
+            let s = &mut self.buffer.slice_with_offset_mut(offset)[0..space];
+            &mut *(s as *mut [T] as *mut [MaybeUninit<T>])
+        }
+    }
+
+    /// Free space for a slice with at least `n` items, without handing it
+    /// out, i.e., nothing can be produced afterwards.
+    ///
+    /// The wrappers use it to wait for space for all item types.
+    #[cfg(all(
+        feature = "sync",
+        feature = "tokio",
+        any(target_os = "linux", target_os = "android")
+    ))]
+    pub(crate) fn space(&self, n: usize, arm: bool) -> usize {
+        self.space_and_offset(n, arm).0
+    }
+
+    /// Space and offset of the next slice.
+    pub(super) fn prepare_slice(&mut self, n: usize, arm: bool) -> (usize, usize) {
+        assert!(
+            n <= self.buffer.capacity(),
+            "vmcircbuffer: requested more space than the buffer can hold"
+        );
+        let (space, offset) = self.space_and_offset(n, arm);
+        self.last_space = space;
+        self.uninit_space = 0;
+        if let Some(ref stats) = self.stats {
+            stats.record_slice(space, self.buffer.capacity() - space);
+        }
+        (space, offset)
+    }
+
+    /// Indicates that `n` items were written to the output buffer.
+    ///
+    /// It is ok if `n` is zero.
+    ///
+    /// # Panics
+    ///
+    /// If produced more than space was available in the last provided slice or,
+    /// for a buffer with an increased [alignment](Builder::alignment), a
+    /// number of items that is not a multiple of its granularity.
+    pub fn produce(&mut self, n: usize, meta: Vec<M::Item>) {
+        if let Err(e) = self.try_produce(n, meta) {
+            produce_failed(e);
+        }
+    }
+
+    /// Indicates that the first `n` items of the last
+    /// [uninit slice](Writer::uninit_slice) were initialized.
+    ///
+    /// # Safety
+    ///
+    /// The `n` items have to be initialized.
+    ///
+    /// # Panics
+    ///
+    /// If produced more than space was available in the last provided slice or,
+    /// for a buffer with an increased [alignment](Builder::alignment), a
+    /// number of items that is not a multiple of its granularity.
+    pub unsafe fn assume_init_produce(&mut self, n: usize, meta: Vec<M::Item>) {
+        self.last_space = mem::take(&mut self.uninit_space);
+        let ret = self.try_produce(n, meta);
+        self.uninit_space = mem::take(&mut self.last_space);
+        if let Err(e) = ret {
+            produce_failed(e);
+        }
+    }
+
+    /// Indicates that `n` items were written to the output buffer.
+    ///
+    /// In contrast to [produce](Writer::produce), an error is returned if
+    /// produced more than space was available in the last provided slice.
+    pub fn try_produce(&mut self, n: usize, meta: Vec<M::Item>) -> Result<(), ProduceError> {
+        if n == 0 {
+            return Ok(());
+        }
+
+        if n > self.last_space {
+            return Err(ProduceError::TooMany {
+                requested: n,
+                available: self.last_space,
+            });
+        }
+
+        let granularity = granularity(&self.buffer);
+        if !n.is_multiple_of(granularity) {
+            return Err(ProduceError::Unaligned {
+                requested: n,
+                granularity,
+            });
+        }
+
+        debug_assert!(self.producer.is_some() || self.free_items() >= n);
+
+        self.last_space -= n;
+        if let Some(ref stats) = self.stats {
+            stats.record_items(n, self.buffer.capacity() - self.last_space);
+        }
+
+        let meta: Option<Arc<[M::Item]>> = if meta.is_empty() {
+            None
+        } else {
+            Some(meta.into())
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let position = state.writer_position;
+
+        let (start, committed) = match (self.producer, state.producers.as_mut()) {
+            (Some(id), Some(producers)) => {
+                let p = &mut producers.slots[id];
+                let (start, len) = p.reservation.unwrap();
+                p.reservation = (len > n).then_some((start + n as u64, len - n));
+                (start, producers.position())
+            }
+            _ => (position, position + n as u64),
+        };
+
+        unsafe {
+            self.buffer.mirror(self.ring.wrap(start), n);
+        }
+
+        state.commit(start, (committed - position) as usize, meta);
+
+        if let Some(hook) = state.on_produce.clone() {
+            drop(state);
+            hook(n);
+        }
+        Ok(())
+    }
+}
+
+impl<T, N, M, W> fmt::Debug for Writer<T, N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Writer")
+            .field("capacity", &self.buffer.capacity())
+            .field("producer", &self.producer)
+            .field("last_space", &self.last_space)
+            .field("state", &*self.state.lock().unwrap())
+            .finish()
+    }
+}
+
+impl<T, N, M, W> Drop for Writer<T, N, M, W>
+where
+    N: Notifier,
+    M: Metadata,
+    W: Notifier,
+{
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(id) = self.producer {
+            let producers = state.producers.as_mut().unwrap();
+            // release the reservation, if no later space was reserved
+            let mut hole = None;
+            if let Some((start, len)) = producers.slots.remove(id).reservation {
+                if start + len as u64 == producers.reserved {
+                    producers.reserved = start;
+                } else {
+                    hole = Some(start..start + len as u64);
+                }
+            }
+            let committed = producers.position();
+            let active = !producers.slots.is_empty();
+
+            if let Some(hole) = hole {
+                let i = state.holes.partition_point(|h| h.start < hole.start);
+                state.holes.insert(i, hole);
+            }
+
+            let position = state.writer_position;
+            state.commit(position, (committed - position) as usize, None);
+            state.notify_producers(self.buffer.capacity());
+            if active {
+                return;
+            }
+        }
+
+        state.writer_done = true;
+        let writer_position = state.writer_position;
+        for (_, r) in state.readers.iter_mut() {
+            let available = (writer_position - r.position) as usize;
+            r.notify(available);
+        }
+    }
+}
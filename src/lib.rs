@@ -6,6 +6,8 @@
 //! - Provides access to all items (not n-1).
 //! - Supports Linux, macOS, Windows, and Android.
 //! - [Sync](sync), [async](asynchronous), and [non-blocking](nonblocking) implementations.
+//! - [Framed](framed) variant for variable-length frames (opt-in).
+//! - [Typed](typed) variant for serialized messages (opt-in).
 //! - [Generic](crate::generic) variant that allows specifying custom [Notifiers](crate::generic::Notifier) to ease integration.
 //! - Underlying data structure (i.e., [DoubleMappedBuffer](double_mapped_buffer::DoubleMappedBuffer)) is exported to allow custom implementations.
 //!
@@ -62,21 +64,56 @@
 //! On Unix-based systems, the mapping is setup with a temporary file. This file
 //! is created in the folder, determined through [std::env::temp_dir], which
 //! considers environment variables. This can be used, if the standard paths are
-//! not present of not writable on the platform.
+//! not present of not writable on the platform. Alternatively, the directory can
+//! be set explicitly with [Builder::tmp_dir](double_mapped_buffer::Builder::tmp_dir)
+//! and passed to the `with_builder` constructors.
+//!
+//! # Real-Time Use
+//!
+//! Once a buffer and its readers are set up, `slice`, `try_slice`, `produce`,
+//! and `consume` of the [generic] and [non-blocking](nonblocking)
+//! implementations do not allocate and do not perform system calls, as long as
+//! no metadata is passed and the notifiers do neither. The [sync] implementation
+//! only performs system calls to wake a thread that actually waits. Reader slots
+//! can be [reserved](crate::generic::Writer::reserve_readers) up front, such
+//! that adding readers does not allocate either.
 //!
 //! # Features
 //!
-//! The `async`, `nonblocking`, and `sync` feature flags, allow to disable the
-//! corresponding implementations. By default, all are enabled. In addition, the
+//! The `async`, `nonblocking`, and `sync` feature flags, allow to
+//! disable the corresponding implementations. By default, all are enabled. In addition, the
 //! `generic` flag allows to disable the generic implementation, leaving only
-//! the [DoubleMappedBuffer](double_mapped_buffer::DoubleMappedBuffer).
+//! the [DoubleMappedBuffer](double_mapped_buffer::DoubleMappedBuffer). The
+//! opt-in `framed` flag enables the [framed] buffer. The
+//! opt-in `debug` flag enables a global registry of named buffers (see
+//! [debug]). The opt-in `tokio` flag implements the `tokio` io traits for the
+//! byte [Reader](asynchronous::Reader) and [Writer](asynchronous::Writer) of
+//! the async implementation and allows the [sync] readers and writers of the
+//! [EventFd](sync::WaitStrategy::EventFd) strategy to wait in the `tokio`
+//! reactor. The opt-in `bytes` flag implements
+//! [Buf](bytes::Buf) and [BufMut](bytes::BufMut) for the byte
+//! [Reader](nonblocking::Reader) and [Writer](nonblocking::Writer) of the
+//! non-blocking implementation. The opt-in `crossbeam` flag adds a
+//! [Notifier](generic::Notifier) that sends on a `crossbeam-channel` (see
+//! [ChannelNotifier](notifiers::ChannelNotifier)). The opt-in `bytemuck` flag
+//! allows viewing the bytes of the [sync] implementation as
+//! [Pod](bytemuck::Pod) types (see [view_as](sync::Reader::view_as)). The
+//! opt-in `serde` flag enables the [typed] buffer for serialized messages.
 
 #[cfg(feature = "async")]
 pub mod asynchronous;
+#[cfg(feature = "debug")]
+pub mod debug;
 pub mod double_mapped_buffer;
+#[cfg(feature = "framed")]
+pub mod framed;
 #[cfg(feature = "generic")]
 pub mod generic;
 #[cfg(feature = "nonblocking")]
 pub mod nonblocking;
+#[cfg(feature = "generic")]
+pub mod notifiers;
 #[cfg(feature = "sync")]
 pub mod sync;
+#[cfg(feature = "serde")]
+pub mod typed;
@@ -1,10 +1,25 @@
 //! Non-blocking Circular Buffer that can only check if data is available right now.
 
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::slice;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::double_mapped_buffer::BufferPool;
+use crate::double_mapped_buffer::Builder;
 use crate::generic;
 use crate::generic::CircularError;
+use crate::generic::ConsumeError;
 use crate::generic::NoMetadata;
 use crate::generic::Notifier;
+use crate::generic::Pod;
+use crate::generic::ProduceError;
+use crate::generic::ReaderOptions;
+use crate::generic::Stats;
 
+#[derive(Clone)]
 struct NullNotifier;
 
 impl Notifier for NullNotifier {
@@ -28,7 +43,54 @@ impl Circular {
     ///
     /// The size is the least common multiple of the page size and the size of `T`.
     pub fn with_capacity<T>(min_items: usize) -> Result<Writer<T>, CircularError> {
-        let writer = generic::Circular::with_capacity(min_items)?;
+        Self::with_builder(&Builder::new(min_items))
+    }
+
+    /// Create a buffer that can hold at least `min_items` items of type `T`
+    /// with preallocated slots for `max_readers` readers.
+    ///
+    /// Adding up to `max_readers` readers does not allocate.
+    pub fn with_capacity_and_readers<T>(
+        min_items: usize,
+        max_readers: usize,
+    ) -> Result<Writer<T>, CircularError> {
+        let writer = generic::Circular::with_capacity_and_readers(min_items, max_readers)?;
+
+        Ok(Writer { writer })
+    }
+
+    /// Create a buffer, whose underlying [DoubleMappedBuffer](crate::double_mapped_buffer::DoubleMappedBuffer)
+    /// is configured by a [Builder], e.g., to set the [temp directory](Builder::tmp_dir).
+    pub fn with_builder<T>(builder: &Builder) -> Result<Writer<T>, CircularError> {
+        let writer = generic::Circular::with_builder(builder)?;
+
+        Ok(Writer { writer })
+    }
+
+    /// Create a buffer that can hold at least `min_items` items of type `T`,
+    /// whose [DoubleMappedBuffer](crate::double_mapped_buffer::DoubleMappedBuffer)
+    /// is taken from a [BufferPool].
+    ///
+    /// The buffer returns to the pool, once the writer and all readers are
+    /// dropped.
+    pub fn with_pool<T>(
+        pool: &BufferPool<T>,
+        min_items: usize,
+    ) -> Result<Writer<T>, CircularError> {
+        let writer = generic::Circular::with_pool(pool, min_items)?;
+
+        Ok(Writer { writer })
+    }
+
+    /// Create a buffer that can hold at least `min_items` items of type `T`,
+    /// where the writer overwrites the oldest data, instead of waiting for the
+    /// readers. It only waits for readers that hold a slice.
+    ///
+    /// All readers of the buffer are [lossy](crate::nonblocking::Writer::add_lossy_reader).
+    ///
+    /// The size is the least common multiple of the page size and the size of `T`.
+    pub fn with_capacity_lossy<T>(min_items: usize) -> Result<Writer<T>, CircularError> {
+        let writer = generic::Circular::with_capacity_lossy(min_items)?;
 
         Ok(Writer { writer })
     }
@@ -39,6 +101,14 @@ pub struct Writer<T> {
     writer: generic::Writer<T, NullNotifier, NoMetadata>,
 }
 
+impl<T> fmt::Debug for Writer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Writer")
+            .field("writer", &self.writer)
+            .finish()
+    }
+}
+
 impl<T> Writer<T> {
     /// Add a reader to the buffer.
     ///
@@ -50,14 +120,250 @@ impl<T> Writer<T> {
         Reader { reader }
     }
 
+    /// Reserve slots for at least `additional` more readers, such that adding
+    /// them does not allocate.
+    #[inline]
+    pub fn reserve_readers(&self, additional: usize) {
+        self.writer.reserve_readers(additional)
+    }
+
+    /// Add a reader to the buffer that is configured by [ReaderOptions],
+    /// e.g., one that never blocks the writer.
+    ///
+    /// See [generic::Writer::add_reader_with_opts].
+    pub fn add_reader_with_opts(&self, opts: &ReaderOptions) -> Reader<T> {
+        let reader = self
+            .writer
+            .add_reader_with_opts(NullNotifier, NullNotifier, opts);
+        Reader { reader }
+    }
+
+    /// Add a lossy reader to the buffer.
+    ///
+    /// A lossy reader does not hold back the writer. If it falls behind, the
+    /// oldest items are dropped and the reader continues with the most recent
+    /// data that fits into the buffer. The number of dropped items can be
+    /// queried with [take_overrun](crate::nonblocking::Reader::take_overrun). The
+    /// writer only waits for the reader while it holds a slice.
+    pub fn add_lossy_reader(&self) -> Reader<T> {
+        let reader = self.writer.add_lossy_reader(NullNotifier, NullNotifier);
+        Reader { reader }
+    }
+
+    /// Add a shared reader to the buffer.
+    ///
+    /// Shared readers compete for the items, i.e., each item is delivered to
+    /// exactly one of them. Further readers that share the consumption cursor
+    /// are created with [share](crate::nonblocking::Reader::share). A slice
+    /// claims the returned items for the reader until they are consumed.
+    pub fn add_shared_reader(&self) -> Reader<T> {
+        let reader = self.writer.add_shared_reader(NullNotifier, NullNotifier);
+        Reader { reader }
+    }
+
+    /// Add a writer that produces into the same buffer.
+    ///
+    /// Each slice reserves the returned space for the writer until it is
+    /// produced. Readers see the items in the order in which the space was
+    /// reserved. The buffer is finished, once all writers are dropped.
+    pub fn add_writer(&mut self) -> Writer<T> {
+        Writer {
+            writer: self.writer.add_writer(NullNotifier),
+        }
+    }
+
     /// Get a slice to the free slots, available for writing.
     ///
     /// This function return immediately. The slice might be [empty](slice::is_empty).
     #[inline]
-    pub fn try_slice(&mut self) -> &mut [T] {
+    pub fn try_slice(&mut self) -> &mut [T]
+    where
+        T: Pod,
+    {
         self.writer.slice(false)
     }
 
+    /// Get a slice to at least `n` free slots, if they are available right
+    /// now.
+    ///
+    /// Returns `None`, if less than `n` slots or no slots at all are free. In
+    /// contrast to [try_slice](Writer::try_slice), the writer never requests a
+    /// notification. This allows producers of fixed-size frames to poll,
+    /// without writing partial frames.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    #[inline]
+    pub fn try_slice_min(&mut self, n: usize) -> Option<&mut [T]>
+    where
+        T: Pod,
+    {
+        let s = self.writer.slice_at_least(n, false);
+        if s.len() < n.max(1) {
+            None
+        } else {
+            Some(s)
+        }
+    }
+
+    /// Get a slice to a multiple of `n` free slots.
+    ///
+    /// Space that does not fit a complete chunk is held back, i.e., the slice
+    /// might be [empty](slice::is_empty).
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero or exceeds the capacity of the buffer.
+    #[inline]
+    pub fn try_slice_chunks(&mut self, n: usize) -> &mut [T]
+    where
+        T: Pod,
+    {
+        self.writer.slice_chunks(n, false)
+    }
+
+    /// Reserve `n` items of output space.
+    ///
+    /// Returns `None` if less than `n` items of output space are available. The
+    /// returned guard produces the items when it is dropped.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub fn try_reserve(&mut self, n: usize) -> Option<WriteGuard<'_, T>>
+    where
+        T: Pod,
+    {
+        let s = self.writer.slice_at_least(n, false);
+        if s.is_empty() {
+            return None;
+        }
+        let ptr = s.as_mut_ptr();
+        Some(WriteGuard {
+            writer: self,
+            ptr,
+            len: n,
+            n,
+        })
+    }
+
+    /// Copy as many `items` as fit into the free slots and produce them.
+    ///
+    /// This function returns immediately with the number of produced items.
+    pub fn produce_from_slice(&mut self, items: &[T]) -> usize
+    where
+        T: Pod,
+    {
+        let s = self.writer.slice(false);
+        let n = std::cmp::min(s.len(), items.len());
+        s[0..n].copy_from_slice(&items[0..n]);
+        self.writer.produce(n, Vec::new());
+        n
+    }
+
+    /// Passes the free slots to `f` and produces the number of items that `f`
+    /// returns. The slice might be [empty](slice::is_empty).
+    ///
+    /// Returns the number of produced items.
+    ///
+    /// # Panics
+    ///
+    /// If `f` returns more than the length of the slice.
+    pub fn write_with<F>(&mut self, f: F) -> usize
+    where
+        T: Pod,
+        F: FnOnce(&mut [T]) -> usize,
+    {
+        let n = f(self.try_slice());
+        self.produce(n);
+        n
+    }
+
+    /// Absolute number of items produced since the buffer was created.
+    ///
+    /// In contrast to the offsets in the circular buffer, the position does not
+    /// wrap and can be used to correlate items across buffers.
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.writer.position()
+    }
+
+    /// Number of items the buffer can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.writer.capacity()
+    }
+
+    /// Grow the buffer, such that it can hold at least `min_items` items.
+    ///
+    /// See [generic::Writer::grow].
+    #[inline]
+    pub fn grow(&mut self, min_items: usize) -> Result<(), CircularError> {
+        self.writer.grow(min_items)
+    }
+
+    /// Enable [Stats] for this writer and return them.
+    #[inline]
+    pub fn enable_stats(&mut self) -> Arc<Stats> {
+        self.writer.enable_stats()
+    }
+
+    /// [Stats] of this writer, if they were enabled.
+    #[inline]
+    pub fn stats(&self) -> Option<Arc<Stats>> {
+        self.writer.stats()
+    }
+
+    /// Detach readers that block the writer for longer than `timeout`.
+    ///
+    /// See [generic::Writer::set_stall_timeout].
+    ///
+    /// # Panics
+    ///
+    /// If `timeout` is set for a type that needs drop.
+    #[inline]
+    pub fn set_stall_timeout(&self, timeout: Option<Duration>) {
+        self.writer.set_stall_timeout(timeout)
+    }
+
+    /// Number of readers of the buffer.
+    ///
+    /// Shared readers count as one reader.
+    #[inline]
+    pub fn reader_count(&self) -> usize {
+        self.writer.reader_count()
+    }
+
+    /// Whether any reader is attached to the buffer.
+    #[inline]
+    pub fn has_readers(&self) -> bool {
+        self.writer.has_readers()
+    }
+
+    /// Number of produced items that each reader did not consume yet.
+    ///
+    /// Shared readers count as one reader.
+    #[inline]
+    pub fn reader_lags(&self) -> Vec<usize> {
+        self.writer.reader_lags()
+    }
+
+    /// [Identifier](crate::nonblocking::Reader::id) and lag of the reader that lags
+    /// the most and, therefore, throttles the writer.
+    ///
+    /// Lossy readers are not considered, since they never block the writer.
+    #[inline]
+    pub fn slowest_reader(&self) -> Option<(usize, usize)> {
+        self.writer.slowest_reader()
+    }
+
+    /// Number of items that can be written, without getting a slice.
+    #[inline]
+    pub fn free_items(&self) -> usize {
+        self.writer.free_items()
+    }
+
     /// Indicates that `n` items were written to the output buffer.
     ///
     /// It is ok if `n` is zero.
@@ -69,6 +375,81 @@ impl<T> Writer<T> {
     pub fn produce(&mut self, n: usize) {
         self.writer.produce(n, Vec::new());
     }
+
+    /// Indicates that `n` items were written to the output buffer.
+    ///
+    /// In contrast to [produce](Writer::produce), an error is returned if
+    /// produced more than space was available in the last provided slice.
+    #[inline]
+    pub fn try_produce(&mut self, n: usize) -> Result<(), ProduceError> {
+        self.writer.try_produce(n, Vec::new())
+    }
+}
+
+// the output space starts at the write position, until bytes are produced, and
+// the buffer only holds initialized bytes
+#[cfg(feature = "bytes")]
+unsafe impl bytes::BufMut for Writer<u8> {
+    /// Free space of the buffer.
+    fn remaining_mut(&self) -> usize {
+        self.free_items()
+    }
+
+    /// Produce `cnt` bytes of the last chunk.
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        self.produce(cnt);
+    }
+
+    /// Output buffer space, as returned by [try_slice](Writer::try_slice).
+    fn chunk_mut(&mut self) -> &mut bytes::buf::UninitSlice {
+        bytes::buf::UninitSlice::new(self.try_slice())
+    }
+}
+
+/// Output space of a [Writer], which is produced when the guard is dropped.
+///
+/// The guard dereferences to the reserved slice. By default, all reserved
+/// items are produced. Use [commit](WriteGuard::commit) to produce less. If
+/// the guard is dropped during a panic, no items are produced.
+pub struct WriteGuard<'a, T> {
+    writer: &'a mut Writer<T>,
+    ptr: *mut T,
+    len: usize,
+    n: usize,
+}
+
+impl<T> WriteGuard<'_, T> {
+    /// Produce the first `n` items of the reserved slice.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the number of reserved items.
+    pub fn commit(mut self, n: usize) {
+        assert!(n <= self.len, "vmcircbuffer: produced too much");
+        self.n = n;
+    }
+}
+
+impl<T> Deref for WriteGuard<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T> DerefMut for WriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl<T> Drop for WriteGuard<'_, T> {
+    fn drop(&mut self) {
+        // do not publish a partially written slice
+        let n = if thread::panicking() { 0 } else { self.n };
+        self.writer.produce(n);
+    }
 }
 
 /// ReaderState for a non-blocking circular buffer with items of type `T`.
@@ -76,17 +457,228 @@ pub struct Reader<T> {
     reader: generic::Reader<T, NullNotifier, NoMetadata>,
 }
 
+impl<T> fmt::Debug for Reader<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reader")
+            .field("reader", &self.reader)
+            .finish()
+    }
+}
+
 impl<T> Reader<T> {
     /// Checks if there is data to read.
     ///
     /// If all data is read and the writer is dropped, all following calls will
-    /// return `None`. If there is no data to read, `Some` is returned with an
-    /// empty slice.
+    /// return `None`. A reader that the writer [detached](Reader::is_detached)
+    /// also gets `None`, while the writer is still running. If there is no data
+    /// to read, `Some` is returned with an empty slice, or with only the
+    /// [history](generic::Reader::set_history).
     #[inline]
     pub fn try_slice(&mut self) -> Option<&[T]> {
         self.reader.slice(false).map(|x| x.0)
     }
 
+    /// Checks if there is a multiple of `n` items to read.
+    ///
+    /// The remainder that does not form a complete chunk is held back, i.e.,
+    /// the slice might be empty. Returns `None`, if the writer is dropped and
+    /// less than `n` items are left. See [generic::Reader::slice_chunks].
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero or exceeds the capacity of the buffer or the
+    /// [maximum chunk size](Reader::set_max_chunk).
+    #[inline]
+    pub fn try_slice_chunks(&mut self, n: usize) -> Option<&[T]> {
+        self.reader.slice_chunks(n, false).map(|x| x.0)
+    }
+
+    /// Checks if there is data to read and returns a guard that consumes the
+    /// items when it is dropped.
+    ///
+    /// Returns `None` if all data is read and the writer is dropped. If there
+    /// is no data to read, the guard holds an empty slice.
+    pub fn try_read(&mut self) -> Option<ReadGuard<'_, T>> {
+        let (ptr, len, history) = self
+            .reader
+            .slice_parts(0, 1, false)
+            .map(|(s, h, _)| (s.as_ptr(), s.len(), h))?;
+        Some(ReadGuard {
+            reader: self,
+            ptr,
+            len,
+            history,
+            n: len - history,
+        })
+    }
+
+    /// Copy available items into `dst` and consume them.
+    ///
+    /// This function returns immediately with the number of copied items. The
+    /// [history](generic::Reader::set_history) is not copied.
+    pub fn read_into(&mut self, dst: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let n = match self.reader.slice_parts(0, 1, false) {
+            Some((s, h, _)) => {
+                let s = &s[h..];
+                let n = std::cmp::min(s.len(), dst.len());
+                dst[0..n].copy_from_slice(&s[0..n]);
+                n
+            }
+            None => 0,
+        };
+        self.reader.consume(n);
+        n
+    }
+
+    /// Passes the available data to `f` and consumes the number of items that
+    /// `f` returns. The slice might be [empty](slice::is_empty).
+    ///
+    /// Returns the number of consumed items or `None`, if all data is read and
+    /// the writer is dropped.
+    ///
+    /// The slice starts with the [history](generic::Reader::set_history), like
+    /// the one returned by [try_slice](Reader::try_slice).
+    ///
+    /// # Panics
+    ///
+    /// If `f` returns more than the number of new items of the slice.
+    pub fn read_with<F>(&mut self, f: F) -> Option<usize>
+    where
+        F: FnOnce(&[T]) -> usize,
+    {
+        let n = f(self.try_slice()?);
+        self.consume(n);
+        Some(n)
+    }
+
+    /// Absolute position of the reader in the stream, i.e., the number of
+    /// items produced before the first item that is read next.
+    ///
+    /// In contrast to the offsets in the circular buffer, the position does not
+    /// wrap and can be used to correlate items across buffers.
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.reader.position()
+    }
+
+    /// Number of items the buffer can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.reader.capacity()
+    }
+
+    /// Identifier of the reader.
+    ///
+    /// See [generic::Reader::id].
+    #[inline]
+    pub fn id(&self) -> usize {
+        self.reader.id()
+    }
+
+    /// Enable [Stats] for this reader and return them.
+    #[inline]
+    pub fn enable_stats(&mut self) -> Arc<Stats> {
+        self.reader.enable_stats()
+    }
+
+    /// [Stats] of this reader, if they were enabled.
+    #[inline]
+    pub fn stats(&self) -> Option<Arc<Stats>> {
+        self.reader.stats()
+    }
+
+    /// Whether all writers of the buffer are dropped.
+    ///
+    /// Items that were produced before can still be read.
+    #[inline]
+    pub fn writer_dropped(&self) -> bool {
+        self.reader.writer_dropped()
+    }
+
+    /// Whether the writer detached the reader, since it stalled.
+    ///
+    /// See [generic::Reader::is_detached].
+    #[inline]
+    pub fn is_detached(&self) -> bool {
+        self.reader.is_detached()
+    }
+
+    /// Pause the reader, such that it does not block the writer and skips
+    /// all items until it is resumed.
+    ///
+    /// See [generic::Reader::pause].
+    #[inline]
+    pub fn pause(&mut self) {
+        self.reader.pause()
+    }
+
+    /// Resume a paused reader. Returns the number of skipped items.
+    #[inline]
+    pub fn resume(&mut self) -> usize {
+        self.reader.resume()
+    }
+
+    /// Whether the reader is paused.
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.reader.is_paused()
+    }
+
+    /// Number of items that can be read, without getting a slice.
+    #[inline]
+    pub fn available(&self) -> usize {
+        self.reader.available()
+    }
+
+    /// Add a reader that shares the consumption cursor with this reader.
+    ///
+    /// See [add_shared_reader](crate::nonblocking::Writer::add_shared_reader).
+    ///
+    /// # Panics
+    ///
+    /// If this reader is not a shared reader.
+    pub fn share(&self) -> Reader<T> {
+        Reader {
+            reader: self.reader.share(NullNotifier),
+        }
+    }
+
+    /// Add a reader that starts at the current position of this reader.
+    ///
+    /// In contrast to [add_reader](crate::nonblocking::Writer::add_reader), the new
+    /// reader sees all items that are not yet consumed by this reader.
+    pub fn fork(&self) -> Reader<T> {
+        Reader {
+            reader: self.reader.fork(NullNotifier),
+        }
+    }
+
+    /// Number of items that were dropped since the last call, because the
+    /// reader fell behind.
+    ///
+    /// This is always zero for readers that are not
+    /// [lossy](crate::nonblocking::Writer::add_lossy_reader).
+    #[inline]
+    pub fn take_overrun(&mut self) -> usize {
+        self.reader.take_overrun()
+    }
+
+    /// Limit the number of items returned by a single slice call.
+    ///
+    /// Even if more data is available, slices will contain at most `n` items.
+    /// Use `usize::MAX` to remove the limit.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero.
+    #[inline]
+    pub fn set_max_chunk(&mut self, n: usize) {
+        self.reader.set_max_chunk(n);
+    }
+
     /// Indicates that `n` items were read.
     ///
     /// # Panics
@@ -96,4 +688,120 @@ impl<T> Reader<T> {
     pub fn consume(&mut self, n: usize) {
         self.reader.consume(n);
     }
+
+    /// Consume all items of the last provided slice.
+    ///
+    /// This saves storing the length of the slice, before calling
+    /// [consume](Reader::consume). Returns the number of consumed items.
+    #[inline]
+    pub fn consume_all(&mut self) -> usize {
+        self.reader.consume_all()
+    }
+
+    /// Copy all items that are available right now and consume them.
+    ///
+    /// Does not block. Returns an empty vector, if no items are available. The
+    /// [history](generic::Reader::set_history) is not copied.
+    pub fn drain(&mut self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let v = self
+            .reader
+            .slice_parts(0, 1, false)
+            .map(|(s, h, _)| s[h..].to_vec())
+            .unwrap_or_default();
+        self.consume_all();
+        v
+    }
+
+    /// Indicates that `n` items were read.
+    ///
+    /// In contrast to [consume](Reader::consume), an error is returned if
+    /// consumed more than space was available in the last provided slice.
+    #[inline]
+    pub fn try_consume(&mut self, n: usize) -> Result<(), ConsumeError> {
+        self.reader.try_consume(n)
+    }
+
+    /// Discard up to `n` items without getting a slice first.
+    ///
+    /// Returns the number of discarded items, which is less than `n`, if less
+    /// items are available.
+    #[inline]
+    pub fn discard(&mut self, n: usize) -> usize {
+        self.reader.discard(n)
+    }
+
+    /// Discard all available items, i.e., jump to the current position of the
+    /// writer.
+    ///
+    /// Returns the number of discarded items.
+    #[inline]
+    pub fn skip_to_latest(&mut self) -> usize {
+        self.reader.skip_to_latest()
+    }
+}
+
+/// The [Buf](bytes::Buf) covers the new bytes of the last
+/// [slice](Reader::try_slice). Advancing it consumes the bytes and gets a new
+/// slice, i.e., it continues with the bytes that were produced in the meantime.
+#[cfg(feature = "bytes")]
+impl bytes::Buf for Reader<u8> {
+    fn remaining(&self) -> usize {
+        self.reader.pending().len()
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.reader.pending()
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.consume(cnt);
+        self.try_slice();
+    }
+}
+
+/// Items of a [Reader], which are consumed when the guard is dropped.
+///
+/// The guard dereferences to the readable slice, which starts with the
+/// [history](crate::generic::Reader::set_history) of the reader. By default,
+/// all new items are consumed. Use [consume](ReadGuard::consume) to consume
+/// less.
+pub struct ReadGuard<'a, T> {
+    reader: &'a mut Reader<T>,
+    ptr: *const T,
+    len: usize,
+    history: usize,
+    n: usize,
+}
+
+impl<T> ReadGuard<'_, T> {
+    /// Consume the first `n` new items of the slice, i.e., the items after
+    /// the [history](crate::generic::Reader::set_history).
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the number of new items in the slice.
+    pub fn consume(mut self, n: usize) {
+        assert!(
+            n <= self.len - self.history,
+            "vmcircbuffer: consumed too much!"
+        );
+        self.n = n;
+    }
+}
+
+impl<T> Deref for ReadGuard<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T> Drop for ReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.reader.consume(self.n);
+    }
 }
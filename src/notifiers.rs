@@ -0,0 +1,262 @@
+//! Reusable [Notifier] implementations for the [generic](crate::generic) circular buffer.
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::io;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::sync::Arc;
+use std::thread::{self, Thread};
+#[cfg(all(feature = "sync", any(target_os = "linux", target_os = "android")))]
+use std::time::Duration;
+
+#[cfg(feature = "crossbeam")]
+use crossbeam_channel::{Receiver, Sender};
+
+use crate::generic::Notifier;
+
+/// [Notifier] that unparks a thread.
+///
+/// The thread waits for the notification with [thread::park]. This avoids
+/// channels and condition variables and, therefore, has the lowest latency of
+/// the blocking notifiers.
+#[derive(Clone, Debug)]
+pub struct ParkNotifier {
+    thread: Thread,
+    armed: bool,
+}
+
+impl ParkNotifier {
+    /// Create a notifier that unparks `thread`.
+    pub fn new(thread: Thread) -> Self {
+        ParkNotifier {
+            thread,
+            armed: false,
+        }
+    }
+
+    /// Create a notifier that unparks the current thread.
+    pub fn current() -> Self {
+        Self::new(thread::current())
+    }
+}
+
+impl Notifier for ParkNotifier {
+    fn arm(&mut self) {
+        self.armed = true;
+    }
+    fn notify(&mut self) {
+        if self.armed {
+            self.thread.unpark();
+            self.armed = false;
+        }
+    }
+}
+
+/// [Notifier] that sends on a `crossbeam-channel`.
+///
+/// The channel holds at most one pending notification, i.e., notifications
+/// that fire before the receiver picks up the last one are merged. This
+/// allows waiting for several buffers in one `select!`, next to other
+/// channels of a pipeline. Arm the notifiers by checking the buffers, before
+/// waiting:
+///
+/// ```
+/// use crossbeam_channel::select;
+/// use vmcircbuffer::generic::{Circular, NoMetadata};
+/// use vmcircbuffer::notifiers::ChannelNotifier;
+///
+/// let (na, rx_a) = ChannelNotifier::new();
+/// let (nb, rx_b) = ChannelNotifier::new();
+/// let mut wa = Circular::with_capacity::<u32, ChannelNotifier, NoMetadata>(0).unwrap();
+/// let mut wb = Circular::with_capacity::<u32, ChannelNotifier, NoMetadata>(0).unwrap();
+/// let mut ra = wa.add_reader(na, ChannelNotifier::new().0);
+/// let mut rb = wb.add_reader(nb, ChannelNotifier::new().0);
+///
+/// std::thread::spawn(move || {
+///     wb.slice(false)[0] = 23;
+///     wb.produce(1, Vec::new());
+/// });
+///
+/// loop {
+///     // getting an empty slice arms the notifier
+///     let na = ra.slice(true).unwrap().0.len();
+///     let nb = rb.slice(true).unwrap().0.len();
+///     if na + nb > 0 {
+///         assert_eq!(rb.slice(false).unwrap().0, &[23]);
+///         break;
+///     }
+///     select! {
+///         recv(rx_a) -> _ => {}
+///         recv(rx_b) -> _ => {}
+///     }
+/// }
+/// ```
+#[cfg(feature = "crossbeam")]
+#[derive(Clone, Debug)]
+pub struct ChannelNotifier {
+    sender: Sender<()>,
+    armed: bool,
+}
+
+#[cfg(feature = "crossbeam")]
+impl ChannelNotifier {
+    /// Create a notifier and the receiver of its notifications.
+    pub fn new() -> (Self, Receiver<()>) {
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        (
+            ChannelNotifier {
+                sender,
+                armed: false,
+            },
+            receiver,
+        )
+    }
+
+    /// Create a notifier that sends on an existing channel.
+    ///
+    /// Notifiers of several buffers can share a channel. A bounded channel
+    /// with a capacity of one merges pending notifications.
+    pub fn with_sender(sender: Sender<()>) -> Self {
+        ChannelNotifier {
+            sender,
+            armed: false,
+        }
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+impl Notifier for ChannelNotifier {
+    fn arm(&mut self) {
+        self.armed = true;
+    }
+    fn notify(&mut self) {
+        if self.armed {
+            // a full channel already holds a notification and a disconnected
+            // one has nobody left to notify
+            let _ = self.sender.try_send(());
+            self.armed = false;
+        }
+    }
+}
+
+/// Non-blocking eventfd that counts notifications.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Debug)]
+pub(crate) struct EventFd(OwnedFd);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl EventFd {
+    pub(crate) fn new() -> io::Result<Self> {
+        let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(EventFd(unsafe { OwnedFd::from_raw_fd(fd) }))
+    }
+
+    /// Make the eventfd readable.
+    pub(crate) fn signal(&self) {
+        let v: u64 = 1;
+        unsafe {
+            libc::write(
+                self.0.as_raw_fd(),
+                (&v as *const u64).cast::<libc::c_void>(),
+                8,
+            );
+        }
+    }
+
+    /// Reset the counter. Returns `true`, if the eventfd was readable.
+    pub(crate) fn reset(&self) -> bool {
+        let mut v: u64 = 0;
+        let ret = unsafe {
+            libc::read(
+                self.0.as_raw_fd(),
+                (&mut v as *mut u64).cast::<libc::c_void>(),
+                8,
+            )
+        };
+        ret == 8
+    }
+
+    /// Wait until the eventfd becomes readable or the timeout expires.
+    #[cfg(feature = "sync")]
+    pub(crate) fn poll(&self, timeout: Option<Duration>) {
+        let mut pfd = libc::pollfd {
+            fd: self.0.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ms = match timeout {
+            // round up to not spin for sub-millisecond timeouts
+            Some(t) => t
+                .as_nanos()
+                .div_ceil(1_000_000)
+                .min(libc::c_int::MAX as u128) as libc::c_int,
+            None => -1,
+        };
+        unsafe {
+            libc::poll(&mut pfd, 1, ms);
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl AsRawFd for EventFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+/// [Notifier] that signals an eventfd.
+///
+/// The file descriptor becomes readable when the notifier fires and can,
+/// therefore, be registered in an existing `epoll`/`poll`/`select` loop next
+/// to sockets. It is non-blocking and has to be drained with
+/// [reset](EventFdNotifier::reset) before checking the buffer again. Clones
+/// share the eventfd.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone, Debug)]
+pub struct EventFdNotifier {
+    fd: Arc<EventFd>,
+    armed: bool,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl EventFdNotifier {
+    /// Create a notifier with a new eventfd.
+    pub fn new() -> io::Result<Self> {
+        Ok(EventFdNotifier {
+            fd: Arc::new(EventFd::new()?),
+            armed: false,
+        })
+    }
+
+    /// Drain the eventfd. Returns `true`, if it was readable.
+    #[inline]
+    pub fn reset(&self) -> bool {
+        self.fd.reset()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl AsRawFd for EventFdNotifier {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Notifier for EventFdNotifier {
+    fn arm(&mut self) {
+        self.armed = true;
+    }
+    fn notify(&mut self) {
+        if self.armed {
+            self.fd.signal();
+            self.armed = false;
+        }
+    }
+}
@@ -1,15 +1,292 @@
 //! Blocking Circular Buffer that blocks until data becomes available.
 
 use core::slice;
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::fmt;
+use std::io::{self, IoSlice, IoSliceMut};
+#[cfg(feature = "bytemuck")]
+use std::mem;
+use std::ops::{Deref, DerefMut};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
 
+#[cfg(feature = "bytemuck")]
+use bytemuck::PodCastError;
+#[cfg(all(feature = "tokio", any(target_os = "linux", target_os = "android")))]
+use tokio::io::unix::AsyncFd;
+#[cfg(all(feature = "tokio", any(target_os = "linux", target_os = "android")))]
+use tokio::io::Interest;
+
+use crate::double_mapped_buffer::BufferPool;
+use crate::double_mapped_buffer::Builder;
+#[cfg(doc)]
+use crate::double_mapped_buffer::DoubleMappedBuffer;
 use crate::generic;
 use crate::generic::CircularError;
+use crate::generic::ConsumeError;
 use crate::generic::NoMetadata;
 use crate::generic::Notifier;
+use crate::generic::Pod;
+use crate::generic::ProduceError;
+use crate::generic::ReaderOptions;
+use crate::generic::Stats;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::notifiers::EventFd;
+
+const SETUP_FAILED: &str = "vmcircbuffer: failed to set up the wait strategy";
+
+/// How blocking calls wait for data or space.
+///
+/// The strategy is set when creating the buffer with
+/// [with_wait_strategy](Circular::with_wait_strategy) and inherited by all
+/// readers and writers of the buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WaitStrategy {
+    /// Wait on a condition variable.
+    #[default]
+    Block,
+    /// Park the thread (see [ParkNotifier](crate::notifiers::ParkNotifier)).
+    ///
+    /// This avoids the mutex of the condition variable on the notification path.
+    Park,
+    /// Poll `spins` times, then yield the thread `yields` times, before waiting
+    /// on a condition variable.
+    ///
+    /// This trades CPU time for latency, if data or space usually becomes
+    /// available shortly after the call.
+    SpinThenBlock {
+        /// Number of busy-wait iterations.
+        spins: u32,
+        /// Number of [yields](std::thread::yield_now) after spinning.
+        yields: u32,
+    },
+    /// Busy-wait without ever blocking the thread.
+    ///
+    /// This has the lowest latency but occupies a core while waiting.
+    Spin,
+    /// Wait on a condition variable, but re-check the buffer at least every
+    /// given interval.
+    Timeout(Duration),
+    /// Wait on an eventfd that is exposed through `as_raw_fd` of readers and
+    /// writers.
+    ///
+    /// This allows registering the buffer in an existing `epoll`/`poll`/`select`
+    /// loop next to sockets. Once the file descriptor is readable, call
+    /// `try_slice`, which resets the eventfd and, if it returns no items,
+    /// requests a notification for when items become available. With the
+    /// `tokio` feature, `readable` and `writable` wait in the tokio reactor.
+    ///
+    /// # Errors
+    ///
+    /// Creating the buffer fails with [CircularError::WaitStrategy], if the
+    /// eventfd cannot be created. Readers and writers that are added later
+    /// get their own eventfd. The `try_` variants, e.g.,
+    /// [try_add_reader](Writer::try_add_reader), report this error, while the
+    /// others panic.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    EventFd,
+}
+
+/// Flag that is set by a [BlockingNotifier] and awaited by a reader or writer.
+struct Signal {
+    strategy: WaitStrategy,
+    flag: AtomicBool,
+    // number of threads waiting on the condition variable
+    waiters: AtomicUsize,
+    lock: Mutex<()>,
+    cond: Condvar,
+    thread: Mutex<Option<Thread>>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    eventfd: Option<EventFd>,
+}
+
+impl Signal {
+    fn new(strategy: WaitStrategy) -> io::Result<Self> {
+        Ok(Signal {
+            strategy,
+            flag: AtomicBool::new(false),
+            waiters: AtomicUsize::new(0),
+            lock: Mutex::new(()),
+            cond: Condvar::new(),
+            thread: Mutex::new(None),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            eventfd: if strategy == WaitStrategy::EventFd {
+                Some(EventFd::new()?)
+            } else {
+                None
+            },
+        })
+    }
+
+    fn notify(&self) {
+        // pairs with the waiter, which increments `waiters` before checking the
+        // flag, i.e., either the waiter sees the flag or we see the waiter
+        self.flag.store(true, Ordering::SeqCst);
+        match self.strategy {
+            WaitStrategy::Spin => {}
+            WaitStrategy::Block | WaitStrategy::SpinThenBlock { .. } | WaitStrategy::Timeout(_) => {
+                // avoid the syscall, if nobody waits
+                if self.waiters.load(Ordering::SeqCst) > 0 {
+                    let _guard = self.lock.lock().unwrap();
+                    self.cond.notify_one();
+                }
+            }
+            WaitStrategy::Park => {
+                if let Some(ref t) = *self.thread.lock().unwrap() {
+                    t.unpark();
+                }
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            WaitStrategy::EventFd => {
+                if let Some(ref fd) = self.eventfd {
+                    fd.signal();
+                }
+            }
+        }
+    }
+
+    fn take(&self) -> bool {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if let Some(ref fd) = self.eventfd {
+            return fd.reset();
+        }
+        self.flag.swap(false, Ordering::SeqCst)
+    }
+
+    /// Register the eventfd in the reactor of the current tokio runtime.
+    #[cfg(all(feature = "tokio", any(target_os = "linux", target_os = "android")))]
+    fn async_fd(&self) -> io::Result<AsyncFd<RawFd>> {
+        match self.eventfd {
+            Some(ref fd) => AsyncFd::with_interest(fd.as_raw_fd(), Interest::READABLE),
+            None => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "vmcircbuffer: the wait strategy is not EventFd",
+            )),
+        }
+    }
+
+    /// Prepare a non-blocking check of the buffer. Returns whether a
+    /// notification has to be requested, which is only the case for an
+    /// eventfd that is watched by an event loop.
+    fn prepare_poll(&self) -> bool {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if self.eventfd.is_some() {
+            self.take();
+            return true;
+        }
+        false
+    }
+
+    /// Poll the flag before blocking. Returns `true`, if it was set.
+    fn spin(&self, deadline: Option<Instant>) -> bool {
+        if let WaitStrategy::SpinThenBlock { spins, yields } = self.strategy {
+            for _ in 0..spins {
+                if self.take() {
+                    return true;
+                }
+                std::hint::spin_loop();
+            }
+            for _ in 0..yields {
+                if self.take() || deadline.is_some_and(|d| Instant::now() >= d) {
+                    return true;
+                }
+                thread::yield_now();
+            }
+        }
+        false
+    }
+
+    fn wait(&self, stats: Option<&Stats>) {
+        self.wait_recorded(None, stats);
+    }
+
+    fn wait_timeout(&self, timeout: Duration, stats: Option<&Stats>) {
+        // a timeout that cannot be represented is no timeout
+        self.wait_recorded(Instant::now().checked_add(timeout), stats);
+    }
+
+    fn wait_recorded(&self, deadline: Option<Instant>, stats: Option<&Stats>) {
+        match stats {
+            Some(stats) => {
+                let start = Instant::now();
+                self.wait_until(deadline);
+                stats.record_wait(start.elapsed());
+            }
+            None => self.wait_until(deadline),
+        }
+    }
+
+    /// Wait for a notification, the deadline, or a strategy-specific timeout.
+    ///
+    /// Returning without notification is fine, since callers check the state
+    /// of the buffer in a loop.
+    fn wait_until(&self, deadline: Option<Instant>) {
+        let expired = || deadline.is_some_and(|d| Instant::now() >= d);
+        let remaining = |max: Option<Duration>| {
+            let left = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+            match (left, max) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        };
+
+        match self.strategy {
+            WaitStrategy::Spin => {
+                while !self.take() && !expired() {
+                    std::hint::spin_loop();
+                }
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            WaitStrategy::EventFd => {
+                if let Some(ref fd) = self.eventfd {
+                    while !self.take() && !expired() {
+                        fd.poll(remaining(None));
+                    }
+                }
+            }
+            WaitStrategy::Park => {
+                *self.thread.lock().unwrap() = Some(thread::current());
+                while !self.take() && !expired() {
+                    match remaining(None) {
+                        Some(t) => thread::park_timeout(t),
+                        None => thread::park(),
+                    }
+                }
+            }
+            WaitStrategy::Block | WaitStrategy::SpinThenBlock { .. } | WaitStrategy::Timeout(_) => {
+                if self.spin(deadline) {
+                    return;
+                }
+                let max = match self.strategy {
+                    WaitStrategy::Timeout(t) => Some(t),
+                    _ => None,
+                };
+                let mut guard = self.lock.lock().unwrap();
+                self.waiters.fetch_add(1, Ordering::SeqCst);
+                while !self.take() && !expired() {
+                    match remaining(max) {
+                        Some(t) => {
+                            guard = self.cond.wait_timeout(guard, t).unwrap().0;
+                            if max.is_some() {
+                                break;
+                            }
+                        }
+                        None => guard = self.cond.wait(guard).unwrap(),
+                    }
+                }
+                self.waiters.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+}
 
+#[derive(Clone)]
 struct BlockingNotifier {
-    chan: Sender<()>,
+    signal: Arc<Signal>,
     armed: bool,
 }
 
@@ -19,7 +296,7 @@ impl Notifier for BlockingNotifier {
     }
     fn notify(&mut self) {
         if self.armed {
-            let _ = self.chan.send(());
+            self.signal.notify();
             self.armed = false;
         }
     }
@@ -41,57 +318,267 @@ impl Circular {
     ///
     /// The size is the least common multiple of the page size and the size of `T`.
     pub fn with_capacity<T>(min_items: usize) -> Result<Writer<T>, CircularError> {
+        Self::with_builder(&Builder::new(min_items))
+    }
+
+    /// Create a buffer, whose underlying [DoubleMappedBuffer] is configured by
+    /// a [Builder], e.g., to set the [temp directory](Builder::tmp_dir).
+    pub fn with_builder<T>(builder: &Builder) -> Result<Writer<T>, CircularError> {
+        let writer = generic::Circular::with_builder(builder)?;
+        Self::wrap(writer, WaitStrategy::default())
+    }
+
+    /// Create a buffer that can hold at least `min_items` items of type `T`,
+    /// whose [DoubleMappedBuffer] is taken from a [BufferPool].
+    ///
+    /// The buffer returns to the pool, once the writer and all readers are
+    /// dropped.
+    pub fn with_pool<T>(
+        pool: &BufferPool<T>,
+        min_items: usize,
+    ) -> Result<Writer<T>, CircularError> {
+        let writer = generic::Circular::with_pool(pool, min_items)?;
+        Self::wrap(writer, WaitStrategy::default())
+    }
+
+    /// Create a buffer that can hold at least `min_items` items of type `T`,
+    /// whose blocking calls wait according to `strategy`.
+    ///
+    /// The size is the least common multiple of the page size and the size of `T`.
+    pub fn with_wait_strategy<T>(
+        min_items: usize,
+        strategy: WaitStrategy,
+    ) -> Result<Writer<T>, CircularError> {
         let writer = generic::Circular::with_capacity(min_items)?;
+        Self::wrap(writer, strategy)
+    }
+
+    /// Create a buffer that can hold at least `min_items` items of type `T`,
+    /// where the writer overwrites the oldest data, instead of waiting for the
+    /// readers. It only waits for readers that hold a slice.
+    ///
+    /// All readers of the buffer are [lossy](crate::sync::Writer::add_lossy_reader).
+    ///
+    /// The size is the least common multiple of the page size and the size of `T`.
+    pub fn with_capacity_lossy<T>(min_items: usize) -> Result<Writer<T>, CircularError> {
+        let writer = generic::Circular::with_capacity_lossy(min_items)?;
+        Self::wrap(writer, WaitStrategy::default())
+    }
 
-        let (tx, rx) = channel();
+    fn wrap<T>(
+        writer: generic::Writer<T, BlockingNotifier, NoMetadata>,
+        strategy: WaitStrategy,
+    ) -> Result<Writer<T>, CircularError> {
+        let signal = Arc::new(Signal::new(strategy)?);
         Ok(Writer {
             writer,
-            writer_sender: tx,
-            chan: rx,
+            writer_signal: signal.clone(),
+            signal,
+            stats: None,
         })
     }
 }
 
 /// Writer for a blocking circular buffer with items of type `T`.
 pub struct Writer<T> {
-    writer_sender: Sender<()>,
-    chan: Receiver<()>,
+    writer_signal: Arc<Signal>,
+    signal: Arc<Signal>,
+    stats: Option<Arc<Stats>>,
     writer: generic::Writer<T, BlockingNotifier, NoMetadata>,
 }
 
+impl<T> fmt::Debug for Writer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Writer")
+            .field("wait_strategy", &self.signal.strategy)
+            .field("writer", &self.writer)
+            .finish()
+    }
+}
+
 impl<T> Writer<T> {
     /// Add a reader to the buffer.
     ///
     /// All readers can block the buffer, i.e., the writer will only overwrite
     /// data, if data was [consume](crate::sync::Reader::consume)ed by all
     /// readers.
+    ///
+    /// # Panics
+    ///
+    /// If the [wait strategy](WaitStrategy) cannot be set up for the reader.
+    /// See [try_add_reader](Writer::try_add_reader).
     pub fn add_reader(&self) -> Reader<T> {
+        self.try_add_reader().expect(SETUP_FAILED)
+    }
+
+    /// Add a reader to the buffer.
+    ///
+    /// Like [add_reader](Writer::add_reader), but fails with
+    /// [CircularError::WaitStrategy], if the wait strategy cannot be set up,
+    /// e.g., because the process ran out of file descriptors for the eventfd.
+    pub fn try_add_reader(&self) -> Result<Reader<T>, CircularError> {
+        let (r_notifier, w_notifier, signal) = self.notifiers()?;
+        let reader = self.writer.add_reader(r_notifier, w_notifier);
+        Ok(Reader {
+            reader,
+            signal,
+            stats: None,
+        })
+    }
+
+    /// Add a reader to the buffer that is configured by [ReaderOptions],
+    /// e.g., one that never blocks the writer.
+    ///
+    /// See [generic::Writer::add_reader_with_opts].
+    ///
+    /// # Panics
+    ///
+    /// If the [wait strategy](WaitStrategy) cannot be set up for the reader.
+    /// See [try_add_reader_with_opts](Writer::try_add_reader_with_opts).
+    pub fn add_reader_with_opts(&self, opts: &ReaderOptions) -> Reader<T> {
+        self.try_add_reader_with_opts(opts).expect(SETUP_FAILED)
+    }
+
+    /// Add a reader to the buffer that is configured by [ReaderOptions].
+    ///
+    /// Like [add_reader_with_opts](Writer::add_reader_with_opts), but fails
+    /// with [CircularError::WaitStrategy], if the wait strategy cannot be set
+    /// up.
+    pub fn try_add_reader_with_opts(
+        &self,
+        opts: &ReaderOptions,
+    ) -> Result<Reader<T>, CircularError> {
+        let (r_notifier, w_notifier, signal) = self.notifiers()?;
+        let reader = self
+            .writer
+            .add_reader_with_opts(r_notifier, w_notifier, opts);
+        Ok(Reader {
+            reader,
+            signal,
+            stats: None,
+        })
+    }
+
+    /// Add a lossy reader to the buffer.
+    ///
+    /// A lossy reader does not hold back the writer. If it falls behind, the
+    /// oldest items are dropped and the reader continues with the most recent
+    /// data that fits into the buffer. The number of dropped items can be
+    /// queried with [take_overrun](crate::sync::Reader::take_overrun). The
+    /// writer only waits for the reader while it holds a slice.
+    ///
+    /// # Panics
+    ///
+    /// If the [wait strategy](WaitStrategy) cannot be set up for the reader.
+    /// See [try_add_lossy_reader](Writer::try_add_lossy_reader).
+    pub fn add_lossy_reader(&self) -> Reader<T> {
+        self.try_add_lossy_reader().expect(SETUP_FAILED)
+    }
+
+    /// Add a lossy reader to the buffer.
+    ///
+    /// Like [add_lossy_reader](Writer::add_lossy_reader), but fails with
+    /// [CircularError::WaitStrategy], if the wait strategy cannot be set up.
+    pub fn try_add_lossy_reader(&self) -> Result<Reader<T>, CircularError> {
+        let (r_notifier, w_notifier, signal) = self.notifiers()?;
+        let reader = self.writer.add_lossy_reader(r_notifier, w_notifier);
+        Ok(Reader {
+            reader,
+            signal,
+            stats: None,
+        })
+    }
+
+    /// Add a shared reader to the buffer.
+    ///
+    /// Shared readers compete for the items, i.e., each item is delivered to
+    /// exactly one of them. Further readers that share the consumption cursor
+    /// are created with [share](crate::sync::Reader::share). A slice claims the
+    /// returned items for the reader until they are consumed.
+    ///
+    /// # Panics
+    ///
+    /// If the [wait strategy](WaitStrategy) cannot be set up for the reader.
+    /// See [try_add_shared_reader](Writer::try_add_shared_reader).
+    pub fn add_shared_reader(&self) -> Reader<T> {
+        self.try_add_shared_reader().expect(SETUP_FAILED)
+    }
+
+    /// Add a shared reader to the buffer.
+    ///
+    /// Like [add_shared_reader](Writer::add_shared_reader), but fails with
+    /// [CircularError::WaitStrategy], if the wait strategy cannot be set up.
+    pub fn try_add_shared_reader(&self) -> Result<Reader<T>, CircularError> {
+        let (r_notifier, w_notifier, signal) = self.notifiers()?;
+        let reader = self.writer.add_shared_reader(r_notifier, w_notifier);
+        Ok(Reader {
+            reader,
+            signal,
+            stats: None,
+        })
+    }
+
+    /// Add a writer that produces into the same buffer.
+    ///
+    /// Each slice reserves the returned space for the writer until it is
+    /// produced. Readers see the items in the order in which the space was
+    /// reserved. The buffer is finished, once all writers are dropped.
+    ///
+    /// # Panics
+    ///
+    /// If the [wait strategy](WaitStrategy) cannot be set up for the writer.
+    /// See [try_add_writer](Writer::try_add_writer).
+    pub fn add_writer(&mut self) -> Writer<T> {
+        self.try_add_writer().expect(SETUP_FAILED)
+    }
+
+    /// Add a writer that produces into the same buffer.
+    ///
+    /// Like [add_writer](Writer::add_writer), but fails with
+    /// [CircularError::WaitStrategy], if the wait strategy cannot be set up.
+    pub fn try_add_writer(&mut self) -> Result<Writer<T>, CircularError> {
+        let signal = Arc::new(Signal::new(self.writer_signal.strategy)?);
+        let notifier = BlockingNotifier {
+            signal: signal.clone(),
+            armed: false,
+        };
+        Ok(Writer {
+            writer_signal: self.writer_signal.clone(),
+            signal,
+            stats: None,
+            writer: self.writer.add_writer(notifier),
+        })
+    }
+
+    fn notifiers(&self) -> io::Result<(BlockingNotifier, BlockingNotifier, Arc<Signal>)> {
         let w_notifier = BlockingNotifier {
-            chan: self.writer_sender.clone(),
+            signal: self.writer_signal.clone(),
             armed: false,
         };
 
-        let (tx, rx) = channel();
+        let signal = Arc::new(Signal::new(self.writer_signal.strategy)?);
         let r_notififer = BlockingNotifier {
-            chan: tx,
+            signal: signal.clone(),
             armed: false,
         };
 
-        let reader = self.writer.add_reader(r_notififer, w_notifier);
-        Reader { reader, chan: rx }
+        Ok((r_notififer, w_notifier, signal))
     }
 
     /// Blocking call to get a slice to the available output space.
     ///
     /// The function returns as soon as any output space is available.
     /// The returned slice will never be empty.
-    pub fn slice(&mut self) -> &mut [T] {
+    pub fn slice(&mut self) -> &mut [T]
+    where
+        T: Pod,
+    {
         // ugly workaround for borrow-checker problem
         // https://github.com/rust-lang/rust/issues/21906
         let (p, s) = loop {
             match self.writer.slice(true) {
                 [] => {
-                    let _ = self.chan.recv();
+                    self.wait();
                 }
                 s => break (s.as_mut_ptr(), s.len()),
             }
@@ -99,74 +586,1302 @@ impl<T> Writer<T> {
         unsafe { slice::from_raw_parts_mut(p, s) }
     }
 
-    /// Get a slice to the free slots, available for writing.
+    fn wait(&self) {
+        match self.writer.stall_timeout() {
+            // wake up periodically to detect stalled readers
+            Some(timeout) => self.signal.wait_timeout(timeout, self.stats.as_deref()),
+            None => self.signal.wait(self.stats.as_deref()),
+        }
+    }
+
+    /// Like [wait](Writer::wait), but gives up after `timeout`.
+    fn wait_at_most(&self, timeout: Duration) {
+        let timeout = match self.writer.stall_timeout() {
+            // wake up periodically to detect stalled readers
+            Some(t) => std::cmp::min(t, timeout),
+            None => timeout,
+        };
+        self.signal.wait_timeout(timeout, self.stats.as_deref());
+    }
+
+    /// Blocking call to get a slice to at least `n` items of output space.
     ///
-    /// This function return immediately. The slice might be [empty](slice::is_empty).
-    #[inline]
-    pub fn try_slice(&mut self) -> &mut [T] {
-        self.writer.slice(false)
+    /// The function returns as soon as enough output space is available.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub fn wait_for_space(&mut self, n: usize) -> &mut [T]
+    where
+        T: Pod,
+    {
+        // ugly workaround for borrow-checker problem
+        // https://github.com/rust-lang/rust/issues/21906
+        let (p, s) = loop {
+            match self.writer.slice_at_least(n, true) {
+                [] => {
+                    self.wait();
+                }
+                s => break (s.as_mut_ptr(), s.len()),
+            }
+        };
+        unsafe { slice::from_raw_parts_mut(p, s) }
     }
 
-    /// Indicates that `n` items were written to the output buffer.
+    /// Blocking call to get a slice to a multiple of `n` items of output
+    /// space.
     ///
-    /// It is ok if `n` is zero.
+    /// Space that does not fit a complete chunk is held back.
     ///
     /// # Panics
     ///
-    /// If produced more than space was available in the last provided slice.
-    #[inline]
-    pub fn produce(&mut self, n: usize) {
-        self.writer.produce(n, Vec::new());
+    /// If `n` is zero or exceeds the capacity of the buffer.
+    pub fn slice_chunks(&mut self, n: usize) -> &mut [T]
+    where
+        T: Pod,
+    {
+        // ugly workaround for borrow-checker problem
+        // https://github.com/rust-lang/rust/issues/21906
+        let (p, s) = loop {
+            match self.writer.slice_chunks(n, true) {
+                [] => {
+                    self.wait();
+                }
+                s => break (s.as_mut_ptr(), s.len()),
+            }
+        };
+        unsafe { slice::from_raw_parts_mut(p, s) }
     }
-}
 
-/// Reader for a blocking circular buffer with items of type `T`.
-pub struct Reader<T> {
-    chan: Receiver<()>,
-    reader: generic::Reader<T, BlockingNotifier, NoMetadata>,
-}
+    /// Blocking call to reserve `n` items of output space.
+    ///
+    /// The returned guard produces the items when it is dropped.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    pub fn reserve(&mut self, n: usize) -> WriteGuard<'_, T>
+    where
+        T: Pod,
+    {
+        let ptr = self.wait_for_space(n).as_mut_ptr();
+        WriteGuard {
+            writer: self,
+            ptr,
+            len: n,
+            n,
+        }
+    }
 
-impl<T> Reader<T> {
-    /// Blocks until there is data to read or until the writer is dropped.
+    /// Blocking call to get a slice to the available output space that gives
+    /// up after `timeout`.
     ///
-    /// If all data is read and the writer is dropped, all following calls will
-    /// return `None`. If `Some` is returned, the contained slice is never empty.
-    pub fn slice(&mut self) -> Option<&[T]> {
+    /// The function returns as soon as any output space is available. If the
+    /// timeout expires before that, the returned slice is empty. A `timeout`
+    /// that is too large to be represented as [Instant] waits without limit.
+    #[inline]
+    pub fn slice_timeout(&mut self, timeout: Duration) -> &mut [T]
+    where
+        T: Pod,
+    {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.slice_deadline(deadline),
+            None => self.slice(),
+        }
+    }
+
+    /// Blocking call to get a slice to the available output space that gives
+    /// up at `deadline`.
+    ///
+    /// The function returns as soon as any output space is available. If the
+    /// deadline passes before that, the returned slice is empty.
+    pub fn slice_deadline(&mut self, deadline: Instant) -> &mut [T]
+    where
+        T: Pod,
+    {
         // ugly workaround for borrow-checker problem
         // https://github.com/rust-lang/rust/issues/21906
-        let r = loop {
-            match self.reader.slice(true) {
-                Some(([], _)) => {
-                    let _ = self.chan.recv();
+        let (p, s) = loop {
+            match self.writer.slice(true) {
+                [] => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break (std::ptr::NonNull::dangling().as_ptr(), 0);
+                    }
+                    self.wait_at_most(deadline - now);
                 }
-                Some((s, _)) => break Some((s.as_ptr(), s.len())),
-                None => break None,
+                s => break (s.as_mut_ptr(), s.len()),
             }
         };
-        if let Some((p, s)) = r {
-            unsafe { Some(slice::from_raw_parts(p, s)) }
-        } else {
+        unsafe { slice::from_raw_parts_mut(p, s) }
+    }
+
+    /// Get a slice to the free slots, available for writing.
+    ///
+    /// This function return immediately. The slice might be [empty](slice::is_empty).
+    #[inline]
+    pub fn try_slice(&mut self) -> &mut [T]
+    where
+        T: Pod,
+    {
+        let arm = self.signal.prepare_poll();
+        self.writer.slice(arm)
+    }
+
+    /// Get a slice to at least `n` free slots, if they are available right
+    /// now.
+    ///
+    /// Returns `None`, if less than `n` slots or no slots at all are free. In
+    /// contrast to [try_slice](Writer::try_slice), the writer never requests a
+    /// notification. This allows producers of fixed-size frames to poll,
+    /// without writing partial frames.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    #[inline]
+    pub fn try_slice_min(&mut self, n: usize) -> Option<&mut [T]>
+    where
+        T: Pod,
+    {
+        let s = self.writer.slice_at_least(n, false);
+        if s.len() < n.max(1) {
             None
+        } else {
+            Some(s)
         }
     }
 
-    /// Checks if there is data to read.
+    /// Copy as many `items` as fit into the free slots and produce them.
     ///
-    /// If all data is read and the writer is dropped, all following calls will
-    /// return `None`. If there is no data to read, `Some` is returned with an
-    /// empty slice.
+    /// This function returns immediately with the number of produced items.
+    pub fn produce_from_slice(&mut self, items: &[T]) -> usize
+    where
+        T: Pod,
+    {
+        let s = self.writer.slice(false);
+        let n = std::cmp::min(s.len(), items.len());
+        s[0..n].copy_from_slice(&items[0..n]);
+        self.writer.produce(n, Vec::new());
+        n
+    }
+
+    /// Blocks until output space is available, passes it to `f`, and produces
+    /// the number of items that `f` returns.
+    ///
+    /// Returns the number of produced items.
+    ///
+    /// # Panics
+    ///
+    /// If `f` returns more than the length of the slice.
+    pub fn write_with<F>(&mut self, f: F) -> usize
+    where
+        T: Pod,
+        F: FnOnce(&mut [T]) -> usize,
+    {
+        let n = f(self.slice());
+        self.produce(n);
+        n
+    }
+
+    /// Absolute number of items produced since the buffer was created.
+    ///
+    /// In contrast to the offsets in the circular buffer, the position does not
+    /// wrap and can be used to correlate items across buffers.
     #[inline]
-    pub fn try_slice(&mut self) -> Option<&[T]> {
-        self.reader.slice(false).map(|x| x.0)
+    pub fn position(&self) -> u64 {
+        self.writer.position()
     }
 
-    /// Indicates that `n` items were read.
+    /// Number of items the buffer can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.writer.capacity()
+    }
+
+    /// Grow the buffer, such that it can hold at least `min_items` items.
+    ///
+    /// See [generic::Writer::grow].
+    #[inline]
+    pub fn grow(&mut self, min_items: usize) -> Result<(), CircularError> {
+        self.writer.grow(min_items)
+    }
+
+    /// Only wake up the writer, once at least `n` items of space are free.
+    ///
+    /// Use `0` to wake up whenever space becomes available.
     ///
     /// # Panics
     ///
-    /// If consumed more than space was available in the last provided slice.
+    /// If `n` exceeds the capacity of the buffer.
     #[inline]
-    pub fn consume(&mut self, n: usize) {
-        self.reader.consume(n);
+    pub fn set_watermark(&mut self, n: usize) {
+        self.writer.set_watermark(n);
+    }
+
+    /// Enable [Stats] for this writer and return them.
+    ///
+    /// Besides the items and occupancy, the stats record the time spent in
+    /// blocking calls.
+    pub fn enable_stats(&mut self) -> Arc<Stats> {
+        let stats = self.writer.enable_stats();
+        self.stats = Some(stats.clone());
+        stats
+    }
+
+    /// [Stats] of this writer, if they were enabled.
+    #[inline]
+    pub fn stats(&self) -> Option<Arc<Stats>> {
+        self.writer.stats()
+    }
+
+    /// [WaitStrategy] of the blocking calls.
+    #[inline]
+    pub fn wait_strategy(&self) -> WaitStrategy {
+        self.signal.strategy
+    }
+
+    /// Waits in the reactor of the current tokio runtime until output space is
+    /// available.
+    ///
+    /// This requires the [EventFd](WaitStrategy::EventFd) strategy, whose
+    /// eventfd is registered with [AsyncFd] for the wait.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [Unsupported](io::ErrorKind::Unsupported) for other
+    /// strategies or if the eventfd cannot be registered.
+    ///
+    /// # Panics
+    ///
+    /// If called outside of a tokio runtime with IO enabled.
+    #[cfg(all(feature = "tokio", any(target_os = "linux", target_os = "android")))]
+    pub async fn writable(&mut self) -> io::Result<()> {
+        let fd = self.signal.async_fd()?;
+        loop {
+            let arm = self.signal.prepare_poll();
+            if self.writer.space(0, arm) > 0 {
+                return Ok(());
+            }
+            fd.readable().await?.clear_ready();
+        }
+    }
+
+    /// The eventfd of the [EventFd](WaitStrategy::EventFd) strategy, which
+    /// becomes readable, when output space might be available.
+    ///
+    /// Returns `None` for other strategies.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn as_raw_fd(&self) -> Option<RawFd> {
+        self.signal.eventfd.as_ref().map(|fd| fd.as_raw_fd())
+    }
+
+    /// Detach readers that block the writer for longer than `timeout`.
+    ///
+    /// See [generic::Writer::set_stall_timeout].
+    ///
+    /// While the writer blocks, it wakes up at least every `timeout` to detect
+    /// stalled readers.
+    ///
+    /// # Panics
+    ///
+    /// If `timeout` is set for a type that needs drop.
+    #[inline]
+    pub fn set_stall_timeout(&self, timeout: Option<Duration>) {
+        self.writer.set_stall_timeout(timeout)
+    }
+
+    /// Number of readers of the buffer.
+    ///
+    /// Shared readers count as one reader.
+    #[inline]
+    pub fn reader_count(&self) -> usize {
+        self.writer.reader_count()
+    }
+
+    /// Whether any reader is attached to the buffer.
+    #[inline]
+    pub fn has_readers(&self) -> bool {
+        self.writer.has_readers()
+    }
+
+    /// Number of produced items that each reader did not consume yet.
+    ///
+    /// Shared readers count as one reader.
+    #[inline]
+    pub fn reader_lags(&self) -> Vec<usize> {
+        self.writer.reader_lags()
+    }
+
+    /// [Identifier](crate::sync::Reader::id) and lag of the reader that lags
+    /// the most and, therefore, throttles the writer.
+    ///
+    /// Lossy readers are not considered, since they never block the writer.
+    #[inline]
+    pub fn slowest_reader(&self) -> Option<(usize, usize)> {
+        self.writer.slowest_reader()
+    }
+
+    /// Number of items that can be written, without getting a slice.
+    #[inline]
+    pub fn free_items(&self) -> usize {
+        self.writer.free_items()
+    }
+
+    /// Indicates that `n` items were written to the output buffer.
+    ///
+    /// It is ok if `n` is zero.
+    ///
+    /// # Panics
+    ///
+    /// If produced more than space was available in the last provided slice.
+    #[inline]
+    pub fn produce(&mut self, n: usize) {
+        self.writer.produce(n, Vec::new());
+    }
+
+    /// Indicates that `n` items were written to the output buffer.
+    ///
+    /// In contrast to [produce](Writer::produce), an error is returned if
+    /// produced more than space was available in the last provided slice.
+    #[inline]
+    pub fn try_produce(&mut self, n: usize) -> Result<(), ProduceError> {
+        self.writer.try_produce(n, Vec::new())
+    }
+}
+
+impl Writer<u8> {
+    /// Read once from `source` directly into the buffer and produce the read
+    /// bytes.
+    ///
+    /// Blocks until output space is available. Reads that are
+    /// [Interrupted](io::ErrorKind::Interrupted) are retried. Returns the
+    /// number of produced bytes, which is zero, if `source` reached its end.
+    pub fn fill_from<R: io::Read + ?Sized>(&mut self, source: &mut R) -> io::Result<usize> {
+        loop {
+            match source.read(self.slice()) {
+                Ok(n) => {
+                    self.produce(n);
+                    return Ok(n);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl Writer<u8> {
+    /// Blocking call to get output space for at least one `T`, as a slice of
+    /// `T`.
+    ///
+    /// Space that does not fit a complete item is held back. Produce the bytes
+    /// of the written items, i.e., multiples of their size.
+    ///
+    /// # Errors
+    ///
+    /// Fails, if the write position is not aligned for `T`, i.e., if a number
+    /// of bytes was produced before that is not a multiple of its alignment.
+    ///
+    /// # Panics
+    ///
+    /// If `T` is zero-sized or larger than the capacity of the buffer.
+    pub fn view_as_mut<T: bytemuck::Pod>(&mut self) -> Result<&mut [T], PodCastError> {
+        let n = mem::size_of::<T>();
+        bytemuck::try_cast_slice_mut(self.slice_chunks(n))
+    }
+}
+
+impl io::Write for Writer<u8> {
+    /// Blocks until output space is available and copies as many bytes as
+    /// fit into the buffer.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let s = self.slice();
+        let n = std::cmp::min(s.len(), buf.len());
+        s[0..n].copy_from_slice(&buf[0..n]);
+        self.produce(n);
+        Ok(n)
+    }
+
+    /// Blocks until output space is available and copies as many bytes of
+    /// `bufs` as fit into the buffer, in one pass.
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        if bufs.iter().all(|b| b.is_empty()) {
+            return Ok(0);
+        }
+        let n = generic::gather(self.slice(), bufs);
+        self.produce(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T: Pod> Extend<T> for Writer<T> {
+    /// Blocks until all items are produced.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut iter = iter.into_iter().peekable();
+        while iter.peek().is_some() {
+            let s = self.slice();
+            let mut n = 0;
+            for (slot, item) in s.iter_mut().zip(&mut iter) {
+                *slot = item;
+                n += 1;
+            }
+            self.produce(n);
+        }
+    }
+}
+
+/// Output space of a [Writer], which is produced when the guard is dropped.
+///
+/// The guard dereferences to the reserved slice. By default, all reserved
+/// items are produced. Use [commit](WriteGuard::commit) to produce less. If
+/// the guard is dropped during a panic, no items are produced.
+pub struct WriteGuard<'a, T> {
+    writer: &'a mut Writer<T>,
+    ptr: *mut T,
+    len: usize,
+    n: usize,
+}
+
+impl<T> WriteGuard<'_, T> {
+    /// Produce the first `n` items of the reserved slice.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the number of reserved items.
+    pub fn commit(mut self, n: usize) {
+        assert!(n <= self.len, "vmcircbuffer: produced too much");
+        self.n = n;
+    }
+}
+
+impl<T> Deref for WriteGuard<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T> DerefMut for WriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl<T> Drop for WriteGuard<'_, T> {
+    fn drop(&mut self) {
+        // do not publish a partially written slice
+        let n = if thread::panicking() { 0 } else { self.n };
+        self.writer.produce(n);
+    }
+}
+
+/// Reader for a blocking circular buffer with items of type `T`.
+pub struct Reader<T> {
+    signal: Arc<Signal>,
+    stats: Option<Arc<Stats>>,
+    reader: generic::Reader<T, BlockingNotifier, NoMetadata>,
+}
+
+impl<T> fmt::Debug for Reader<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reader")
+            .field("wait_strategy", &self.signal.strategy)
+            .field("reader", &self.reader)
+            .finish()
+    }
+}
+
+impl<T> Reader<T> {
+    /// Blocks until there is data to read or until the writer is dropped.
+    ///
+    /// If all data is read and the writer is dropped, all following calls will
+    /// return `None`. If `Some` is returned, the contained slice is never empty.
+    /// A reader that the writer [detached](Reader::is_detached) also gets
+    /// `None`, while the writer is still running.
+    ///
+    /// With a [history](generic::Reader::set_history), the slice starts with
+    /// the history items and the call blocks until there are new items.
+    #[inline]
+    pub fn slice(&mut self) -> Option<&[T]> {
+        self.wait_slice(0, 1).map(|x| x.0)
+    }
+
+    /// Blocks until there are new items to read, i.e., items after the
+    /// [history](generic::Reader::set_history), or until the writer is dropped.
+    ///
+    /// Returns the slice and the number of history items at its start.
+    fn wait_slice(&mut self, n: usize, chunk: usize) -> Option<(&[T], usize)> {
+        // ugly workaround for borrow-checker problem
+        // https://github.com/rust-lang/rust/issues/21906
+        let r = loop {
+            match self.reader.slice_parts(n, chunk, true) {
+                Some((s, h, _)) if s.len() == h => {
+                    self.signal.wait(self.stats.as_deref());
+                }
+                Some((s, h, _)) => break Some((s.as_ptr(), s.len(), h)),
+                None => break None,
+            }
+        };
+        r.map(|(p, s, h)| unsafe { (slice::from_raw_parts(p, s), h) })
+    }
+
+    /// Blocks until there are new items to read and returns them without the
+    /// [history](generic::Reader::set_history).
+    fn slice_new(&mut self) -> Option<&[T]> {
+        self.wait_slice(0, 1).map(|(s, h)| &s[h..])
+    }
+
+    /// Blocks until there are at least `n` items to read or until the writer
+    /// is dropped.
+    ///
+    /// If all data is read and the writer is dropped, all following calls will
+    /// return `None`. If `Some` is returned, the contained slice has at least
+    /// `n` items, unless the writer was dropped, in which case it contains the
+    /// remaining items.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer or the
+    /// [maximum chunk size](Reader::set_max_chunk).
+    #[inline]
+    pub fn slice_at_least(&mut self, n: usize) -> Option<&[T]> {
+        self.wait_slice(n, 1).map(|x| x.0)
+    }
+
+    /// Blocks until there is a multiple of `n` items to read.
+    ///
+    /// The remainder that does not form a complete chunk is held back. Returns
+    /// `None`, if the writer is dropped and less than `n` items are left. See
+    /// [generic::Reader::slice_chunks].
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero or exceeds the capacity of the buffer or the
+    /// [maximum chunk size](Reader::set_max_chunk).
+    #[inline]
+    pub fn slice_chunks(&mut self, n: usize) -> Option<&[T]> {
+        self.wait_slice(n, n).map(|x| x.0)
+    }
+
+    /// Blocks until there is data to read, the writer is dropped, or the
+    /// `timeout` expires.
+    ///
+    /// If all data is read and the writer is dropped, all following calls will
+    /// return `None`. If the timeout expires before data becomes available,
+    /// `Some` is returned with an empty slice. A `timeout` that is too large
+    /// to be represented as [Instant] waits without limit.
+    #[inline]
+    pub fn slice_timeout(&mut self, timeout: Duration) -> Option<&[T]> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.slice_deadline(deadline),
+            None => self.slice(),
+        }
+    }
+
+    /// Blocks until there is data to read, the writer is dropped, or the
+    /// `deadline` passes.
+    ///
+    /// If all data is read and the writer is dropped, all following calls will
+    /// return `None`. If the deadline passes before data becomes available,
+    /// `Some` is returned with a slice that holds only the
+    /// [history](generic::Reader::set_history), i.e., an empty slice without
+    /// history.
+    pub fn slice_deadline(&mut self, deadline: Instant) -> Option<&[T]> {
+        // ugly workaround for borrow-checker problem
+        // https://github.com/rust-lang/rust/issues/21906
+        let r = loop {
+            match self.reader.slice_parts(0, 1, true) {
+                Some((s, h, _)) if s.len() == h => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break Some((s.as_ptr(), s.len()));
+                    }
+                    self.signal
+                        .wait_timeout(deadline - now, self.stats.as_deref());
+                }
+                Some((s, _, _)) => break Some((s.as_ptr(), s.len())),
+                None => break None,
+            }
+        };
+        if let Some((p, s)) = r {
+            unsafe { Some(slice::from_raw_parts(p, s)) }
+        } else {
+            None
+        }
+    }
+
+    /// Checks if there is data to read.
+    ///
+    /// If all data is read and the writer is dropped, all following calls will
+    /// return `None`. If there is no data to read, `Some` is returned with an
+    /// empty slice, or with only the [history](generic::Reader::set_history).
+    #[inline]
+    pub fn try_slice(&mut self) -> Option<&[T]> {
+        let arm = self.signal.prepare_poll();
+        self.reader.slice(arm).map(|x| x.0)
+    }
+
+    /// Blocks until there is data to read and returns a guard that consumes
+    /// the items when it is dropped.
+    ///
+    /// Returns `None` if all data is read and the writer is dropped.
+    pub fn read(&mut self) -> Option<ReadGuard<'_, T>> {
+        let (ptr, len, history) = self
+            .wait_slice(0, 1)
+            .map(|(s, h)| (s.as_ptr(), s.len(), h))?;
+        Some(ReadGuard {
+            reader: self,
+            ptr,
+            len,
+            history,
+            n: len - history,
+        })
+    }
+
+    /// Blocking iterator over the items of the buffer.
+    ///
+    /// Items are consumed in batches, i.e., once all items of a slice are
+    /// returned or when the iterator is dropped. The
+    /// [history](generic::Reader::set_history) is skipped.
+    pub fn iter(&mut self) -> Iter<'_, T>
+    where
+        T: Clone,
+    {
+        Iter {
+            reader: self,
+            cursor: Cursor::new(),
+        }
+    }
+
+    /// Copy available items into `dst` and consume them.
+    ///
+    /// This function returns immediately with the number of copied items. The
+    /// [history](generic::Reader::set_history) is not copied.
+    pub fn read_into(&mut self, dst: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let n = match self.reader.slice_parts(0, 1, false) {
+            Some((s, h, _)) => {
+                let s = &s[h..];
+                let n = std::cmp::min(s.len(), dst.len());
+                dst[0..n].copy_from_slice(&s[0..n]);
+                n
+            }
+            None => 0,
+        };
+        self.reader.consume(n);
+        n
+    }
+
+    /// Blocks until there is data to read, passes it to `f`, and consumes the
+    /// number of items that `f` returns.
+    ///
+    /// Returns the number of consumed items or `None`, if all data is read and
+    /// the writer is dropped.
+    ///
+    /// The slice starts with the [history](generic::Reader::set_history), like
+    /// the one returned by [slice](Reader::slice).
+    ///
+    /// # Panics
+    ///
+    /// If `f` returns more than the number of new items of the slice.
+    pub fn read_with<F>(&mut self, f: F) -> Option<usize>
+    where
+        F: FnOnce(&[T]) -> usize,
+    {
+        let n = f(self.slice()?);
+        self.consume(n);
+        Some(n)
+    }
+
+    /// Absolute position of the reader in the stream, i.e., the number of
+    /// items produced before the first item that is read next.
+    ///
+    /// In contrast to the offsets in the circular buffer, the position does not
+    /// wrap and can be used to correlate items across buffers.
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.reader.position()
+    }
+
+    /// Number of items the buffer can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.reader.capacity()
+    }
+
+    /// Identifier of the reader.
+    ///
+    /// See [generic::Reader::id].
+    #[inline]
+    pub fn id(&self) -> usize {
+        self.reader.id()
+    }
+
+    /// [WaitStrategy] of the blocking calls.
+    #[inline]
+    pub fn wait_strategy(&self) -> WaitStrategy {
+        self.signal.strategy
+    }
+
+    /// Waits in the reactor of the current tokio runtime until there is data
+    /// to read.
+    ///
+    /// This requires the [EventFd](WaitStrategy::EventFd) strategy, whose
+    /// eventfd is registered with [AsyncFd] for the wait. It also returns, if
+    /// all data is read and the writer is dropped. The
+    /// [history](generic::Reader::set_history) does not count as data.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [Unsupported](io::ErrorKind::Unsupported) for other
+    /// strategies or if the eventfd cannot be registered.
+    ///
+    /// # Panics
+    ///
+    /// If called outside of a tokio runtime with IO enabled.
+    #[cfg(all(feature = "tokio", any(target_os = "linux", target_os = "android")))]
+    pub async fn readable(&mut self) -> io::Result<()> {
+        let fd = self.signal.async_fd()?;
+        loop {
+            let arm = self.signal.prepare_poll();
+            match self.reader.slice_parts(0, 1, arm) {
+                Some((s, h, _)) if s.len() == h => {}
+                _ => return Ok(()),
+            }
+            fd.readable().await?.clear_ready();
+        }
+    }
+
+    /// The eventfd of the [EventFd](WaitStrategy::EventFd) strategy, which
+    /// becomes readable, when data might be available.
+    ///
+    /// Returns `None` for other strategies.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn as_raw_fd(&self) -> Option<RawFd> {
+        self.signal.eventfd.as_ref().map(|fd| fd.as_raw_fd())
+    }
+
+    /// Whether all writers of the buffer are dropped.
+    ///
+    /// Items that were produced before can still be read.
+    #[inline]
+    pub fn writer_dropped(&self) -> bool {
+        self.reader.writer_dropped()
+    }
+
+    /// Whether the writer detached the reader, since it stalled.
+    ///
+    /// See [generic::Reader::is_detached].
+    #[inline]
+    pub fn is_detached(&self) -> bool {
+        self.reader.is_detached()
+    }
+
+    /// Pause the reader, such that it does not block the writer and skips
+    /// all items until it is resumed.
+    ///
+    /// See [generic::Reader::pause].
+    #[inline]
+    pub fn pause(&mut self) {
+        self.reader.pause()
+    }
+
+    /// Resume a paused reader. Returns the number of skipped items.
+    #[inline]
+    pub fn resume(&mut self) -> usize {
+        self.reader.resume()
+    }
+
+    /// Whether the reader is paused.
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.reader.is_paused()
+    }
+
+    /// Number of items that can be read, without getting a slice.
+    #[inline]
+    pub fn available(&self) -> usize {
+        self.reader.available()
+    }
+
+    /// Add a reader that shares the consumption cursor with this reader.
+    ///
+    /// See [add_shared_reader](crate::sync::Writer::add_shared_reader).
+    ///
+    /// # Panics
+    ///
+    /// If this reader is not a shared reader or if the
+    /// [wait strategy](WaitStrategy) cannot be set up for the new reader. See
+    /// [try_share](Reader::try_share).
+    pub fn share(&self) -> Reader<T> {
+        self.try_share().expect(SETUP_FAILED)
+    }
+
+    /// Add a reader that shares the consumption cursor with this reader.
+    ///
+    /// Like [share](Reader::share), but fails with
+    /// [CircularError::WaitStrategy], if the wait strategy cannot be set up.
+    ///
+    /// # Panics
+    ///
+    /// If this reader is not a shared reader.
+    pub fn try_share(&self) -> Result<Reader<T>, CircularError> {
+        let signal = Arc::new(Signal::new(self.signal.strategy)?);
+        let notifier = BlockingNotifier {
+            signal: signal.clone(),
+            armed: false,
+        };
+        Ok(Reader {
+            reader: self.reader.share(notifier),
+            signal,
+            stats: None,
+        })
+    }
+
+    /// Add a reader that starts at the current position of this reader.
+    ///
+    /// In contrast to [add_reader](crate::sync::Writer::add_reader), the new
+    /// reader sees all items that are not yet consumed by this reader.
+    ///
+    /// # Panics
+    ///
+    /// If the [wait strategy](WaitStrategy) cannot be set up for the new
+    /// reader. See [try_fork](Reader::try_fork).
+    pub fn fork(&self) -> Reader<T> {
+        self.try_fork().expect(SETUP_FAILED)
+    }
+
+    /// Add a reader that starts at the current position of this reader.
+    ///
+    /// Like [fork](Reader::fork), but fails with
+    /// [CircularError::WaitStrategy], if the wait strategy cannot be set up.
+    pub fn try_fork(&self) -> Result<Reader<T>, CircularError> {
+        let signal = Arc::new(Signal::new(self.signal.strategy)?);
+        let notifier = BlockingNotifier {
+            signal: signal.clone(),
+            armed: false,
+        };
+        Ok(Reader {
+            reader: self.reader.fork(notifier),
+            signal,
+            stats: None,
+        })
+    }
+
+    /// Number of items that were dropped since the last call, because the
+    /// reader fell behind.
+    ///
+    /// This is always zero for readers that are not
+    /// [lossy](crate::sync::Writer::add_lossy_reader).
+    #[inline]
+    pub fn take_overrun(&mut self) -> usize {
+        self.reader.take_overrun()
+    }
+
+    /// Limit the number of items returned by a single slice call.
+    ///
+    /// Even if more data is available, slices will contain at most `n` items.
+    /// Use `usize::MAX` to remove the limit.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero.
+    #[inline]
+    pub fn set_max_chunk(&mut self, n: usize) {
+        self.reader.set_max_chunk(n);
+    }
+
+    /// Only wake up the reader, once at least `n` items are available or the
+    /// writer is dropped.
+    ///
+    /// Use `0` to wake up whenever items become available.
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the capacity of the buffer.
+    #[inline]
+    pub fn set_watermark(&mut self, n: usize) {
+        self.reader.set_watermark(n);
+    }
+
+    /// Enable [Stats] for this reader and return them.
+    ///
+    /// Besides the items and occupancy, the stats record the time spent in
+    /// blocking calls.
+    pub fn enable_stats(&mut self) -> Arc<Stats> {
+        let stats = self.reader.enable_stats();
+        self.stats = Some(stats.clone());
+        stats
+    }
+
+    /// [Stats] of this reader, if they were enabled.
+    #[inline]
+    pub fn stats(&self) -> Option<Arc<Stats>> {
+        self.reader.stats()
+    }
+
+    /// Indicates that `n` items were read.
+    ///
+    /// # Panics
+    ///
+    /// If consumed more than space was available in the last provided slice.
+    #[inline]
+    pub fn consume(&mut self, n: usize) {
+        self.reader.consume(n);
+    }
+
+    /// Consume all items of the last provided slice.
+    ///
+    /// This saves storing the length of the slice, before calling
+    /// [consume](Reader::consume). Returns the number of consumed items.
+    #[inline]
+    pub fn consume_all(&mut self) -> usize {
+        self.reader.consume_all()
+    }
+
+    /// Copy all items that are available right now and consume them.
+    ///
+    /// Does not block. Returns an empty vector, if no items are available. The
+    /// [history](generic::Reader::set_history) is not copied.
+    pub fn drain(&mut self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let arm = self.signal.prepare_poll();
+        let v = self
+            .reader
+            .slice_parts(0, 1, arm)
+            .map(|(s, h, _)| s[h..].to_vec())
+            .unwrap_or_default();
+        self.consume_all();
+        v
+    }
+
+    /// Indicates that `n` items were read.
+    ///
+    /// In contrast to [consume](Reader::consume), an error is returned if
+    /// consumed more than space was available in the last provided slice.
+    #[inline]
+    pub fn try_consume(&mut self, n: usize) -> Result<(), ConsumeError> {
+        self.reader.try_consume(n)
+    }
+
+    /// Discard up to `n` items without getting a slice first.
+    ///
+    /// Returns the number of discarded items, which is less than `n`, if less
+    /// items are available.
+    #[inline]
+    pub fn discard(&mut self, n: usize) -> usize {
+        self.reader.discard(n)
+    }
+
+    /// Discard all available items, i.e., jump to the current position of the
+    /// writer.
+    ///
+    /// Returns the number of discarded items.
+    #[inline]
+    pub fn skip_to_latest(&mut self) -> usize {
+        self.reader.skip_to_latest()
+    }
+}
+
+impl Reader<u8> {
+    /// Write all data to `sink`, until the writer is dropped.
+    ///
+    /// Blocks until data is available and hands it to `sink` without copying
+    /// it out of the buffer. Bytes are consumed as `sink` accepts them, so
+    /// partial writes are continued with the next call. Returns the number of
+    /// written bytes, once all data is read. `sink` is flushed before
+    /// returning.
+    ///
+    /// On error, the bytes that were accepted by `sink` before are consumed.
+    /// A `sink` that does not accept any bytes results in
+    /// [WriteZero](io::ErrorKind::WriteZero).
+    pub fn drain_to<W: io::Write + ?Sized>(&mut self, sink: &mut W) -> io::Result<usize> {
+        let mut total = 0;
+        while let Some(s) = self.slice_new() {
+            match sink.write(s) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write whole buffer",
+                    ))
+                }
+                Ok(n) => {
+                    self.consume(n);
+                    total += n;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        sink.flush()?;
+        Ok(total)
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl Reader<u8> {
+    /// Blocks until there is at least one `T` to read and returns the bytes as
+    /// a slice of `T`.
+    ///
+    /// The slice only holds complete items, i.e., a trailing partial item is
+    /// held back, and does not include the
+    /// [history](generic::Reader::set_history). Consume the bytes of the
+    /// items, i.e., multiples of their size. Returns `None`, if the writer is
+    /// dropped and less than one `T` is left.
+    ///
+    /// # Errors
+    ///
+    /// Fails, if the read position is not aligned for `T`, i.e., if a number
+    /// of bytes was consumed before that is not a multiple of its alignment.
+    ///
+    /// # Panics
+    ///
+    /// If `T` is zero-sized or larger than the capacity of the buffer.
+    pub fn view_as<T: bytemuck::Pod>(&mut self) -> Result<Option<&[T]>, PodCastError> {
+        let n = mem::size_of::<T>();
+        self.wait_slice(n, n)
+            .map(|(s, h)| bytemuck::try_cast_slice(&s[h..]))
+            .transpose()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Reader<u8> {
+    /// Move up to `max` bytes into the pipe `fd`, without copying them out of
+    /// the buffer.
+    ///
+    /// Blocks until data is available and hands it to the kernel with
+    /// `vmsplice(2)`. The moved bytes are consumed. Returns zero, once the
+    /// writer is dropped and all data is read. To get the data to a socket or
+    /// file, `splice(2)` the other end of the pipe to it.
+    ///
+    /// The pipe references the pages of the buffer instead of holding a copy.
+    /// Once consumed, the writer may overwrite the bytes, even if they did not
+    /// yet leave the pipe. Applications have to drain the pipe, before the
+    /// writer can wrap around, e.g., by keeping the pipe small compared to the
+    /// buffer.
+    pub fn splice_to(&mut self, fd: RawFd, max: usize) -> io::Result<usize> {
+        if max == 0 {
+            return Ok(0);
+        }
+        let s = match self.slice_new() {
+            Some(s) => s,
+            None => return Ok(0),
+        };
+        let iov = libc::iovec {
+            iov_base: s.as_ptr() as *mut libc::c_void,
+            iov_len: std::cmp::min(s.len(), max),
+        };
+        let n = loop {
+            let ret = unsafe { libc::vmsplice(fd, &iov, 1, 0) };
+            if ret >= 0 {
+                break ret as usize;
+            }
+            let e = io::Error::last_os_error();
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(e);
+            }
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl io::Read for Reader<u8> {
+    /// Blocks until data is available and copies as many bytes as fit into
+    /// `buf`. Returns zero, once the writer is dropped and all data is read.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        match self.slice_new() {
+            Some(s) => {
+                let n = std::cmp::min(s.len(), buf.len());
+                buf[0..n].copy_from_slice(&s[0..n]);
+                self.consume(n);
+                Ok(n)
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Blocks until data is available and copies as many bytes as fit into
+    /// `bufs`, in one pass.
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        if bufs.iter().all(|b| b.is_empty()) {
+            return Ok(0);
+        }
+        match self.slice_new() {
+            Some(s) => {
+                let n = generic::scatter(s, bufs);
+                self.consume(n);
+                Ok(n)
+            }
+            None => Ok(0),
+        }
+    }
+}
+
+impl io::BufRead for Reader<u8> {
+    /// Blocks until data is available and returns it without copying. Returns
+    /// an empty slice, once the writer is dropped and all data is read.
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.slice_new().unwrap_or(&[]))
+    }
+
+    fn consume(&mut self, amt: usize) {
+        Reader::consume(self, amt);
+    }
+}
+
+struct Cursor<T> {
+    ptr: *const T,
+    len: usize,
+    index: usize,
+}
+
+impl<T: Clone> Cursor<T> {
+    fn new() -> Self {
+        Cursor {
+            ptr: std::ptr::null(),
+            len: 0,
+            index: 0,
+        }
+    }
+
+    fn next(&mut self, reader: &mut Reader<T>) -> Option<T> {
+        if self.index == self.len {
+            reader.consume(self.len);
+            self.index = 0;
+            self.len = 0;
+            let s = reader.slice_new()?;
+            self.ptr = s.as_ptr();
+            self.len = s.len();
+        }
+        let item = unsafe { (*self.ptr.add(self.index)).clone() };
+        self.index += 1;
+        Some(item)
+    }
+
+    fn finish(&mut self, reader: &mut Reader<T>) {
+        reader.consume(self.index);
+        self.len = 0;
+        self.index = 0;
+    }
+}
+
+/// Blocking iterator over the items of a [Reader], returned by
+/// [iter](Reader::iter).
+pub struct Iter<'a, T: Clone> {
+    reader: &'a mut Reader<T>,
+    cursor: Cursor<T>,
+}
+
+impl<T: Clone> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.cursor.next(self.reader)
+    }
+}
+
+impl<T: Clone> Drop for Iter<'_, T> {
+    fn drop(&mut self) {
+        self.cursor.finish(self.reader);
+    }
+}
+
+/// Blocking iterator that owns a [Reader].
+pub struct IntoIter<T: Clone> {
+    reader: Reader<T>,
+    cursor: Cursor<T>,
+}
+
+impl<T: Clone> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.cursor.next(&mut self.reader)
+    }
+}
+
+impl<T: Clone> IntoIterator for Reader<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            reader: self,
+            cursor: Cursor::new(),
+        }
+    }
+}
+
+impl<'a, T: Clone> IntoIterator for &'a mut Reader<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Items of a [Reader], which are consumed when the guard is dropped.
+///
+/// The guard dereferences to the readable slice, which starts with the
+/// [history](crate::generic::Reader::set_history) of the reader. By default,
+/// all new items are consumed. Use [consume](ReadGuard::consume) to consume
+/// less.
+pub struct ReadGuard<'a, T> {
+    reader: &'a mut Reader<T>,
+    ptr: *const T,
+    len: usize,
+    history: usize,
+    n: usize,
+}
+
+impl<T> ReadGuard<'_, T> {
+    /// Consume the first `n` new items of the slice, i.e., the items after
+    /// the [history](generic::Reader::set_history).
+    ///
+    /// # Panics
+    ///
+    /// If `n` exceeds the number of new items in the slice.
+    pub fn consume(mut self, n: usize) {
+        assert!(
+            n <= self.len - self.history,
+            "vmcircbuffer: consumed too much!"
+        );
+        self.n = n;
+    }
+}
+
+impl<T> Deref for ReadGuard<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T> Drop for ReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.reader.consume(self.n);
     }
 }
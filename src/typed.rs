@@ -0,0 +1,102 @@
+//! Blocking Circular Buffer for typed messages.
+//!
+//! Messages are serialized with `postcard` directly into the frames of a
+//! [framed] buffer and deserialized from the buffer, i.e., there
+//! is no intermediate allocation. Each reader receives all messages.
+
+use postcard::ser_flavors::Size;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+use thiserror::Error;
+
+use crate::framed;
+use crate::framed::FrameError;
+use crate::framed::FrameReader;
+use crate::framed::FrameWriter;
+use crate::generic::CircularError;
+
+/// Error sending or receiving a message.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TypedError {
+    /// Serialized message does not fit into the buffer.
+    #[error(transparent)]
+    Frame(#[from] FrameError),
+    /// Message could not be serialized or deserialized.
+    #[error("Failed to serialize or deserialize the message: {0}")]
+    Serde(#[from] postcard::Error),
+}
+
+/// Typed Circular Buffer Constructor
+pub struct Circular;
+
+impl Circular {
+    /// Create a buffer for messages of type `T`.
+    ///
+    /// The capacity is set to the page size.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<T>() -> Result<Writer<T>, CircularError> {
+        Self::with_capacity(0)
+    }
+
+    /// Create a buffer that can hold serialized messages with at least
+    /// `max_message` bytes.
+    pub fn with_capacity<T>(max_message: usize) -> Result<Writer<T>, CircularError> {
+        Ok(Writer {
+            writer: framed::Circular::with_capacity(max_message)?,
+            _p: PhantomData,
+        })
+    }
+}
+
+/// Writer for a typed circular buffer.
+#[derive(Debug)]
+pub struct Writer<T> {
+    writer: FrameWriter,
+    _p: PhantomData<fn(&T)>,
+}
+
+impl<T: Serialize> Writer<T> {
+    /// Add a reader to the buffer.
+    pub fn add_reader(&self) -> Reader<T> {
+        Reader {
+            reader: self.writer.add_reader(),
+            _p: PhantomData,
+        }
+    }
+
+    /// Maximum size of a serialized message.
+    pub fn max_message(&self) -> usize {
+        self.writer.max_frame()
+    }
+
+    /// Blocks until there is space for the serialized message and writes it
+    /// to the buffer.
+    pub fn send(&mut self, msg: &T) -> Result<(), TypedError> {
+        let size = postcard::serialize_with_flavor(msg, Size::default())?;
+        self.writer.send_with(size, |s| {
+            postcard::to_slice(msg, s)?;
+            Ok(())
+        })
+    }
+}
+
+/// Reader for a typed circular buffer.
+#[derive(Debug)]
+pub struct Reader<T> {
+    reader: FrameReader,
+    _p: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Reader<T> {
+    /// Blocks until a message is available or until the writer is dropped.
+    ///
+    /// If all messages are read and the writer is dropped, `None` is
+    /// returned. A message that cannot be deserialized is skipped and
+    /// reported as error.
+    pub fn recv(&mut self) -> Option<Result<T, TypedError>> {
+        self.reader
+            .recv()
+            .map(|s| postcard::from_bytes(s).map_err(TypedError::from))
+    }
+}
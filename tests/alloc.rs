@@ -0,0 +1,118 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use vmcircbuffer::nonblocking::Circular;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|a| a.set(a.get() + 1));
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|a| a.set(a.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Number of allocations of the current thread while running `f`.
+fn allocations<R>(f: impl FnOnce() -> R) -> (usize, R) {
+    let before = ALLOCATIONS.with(|a| a.get());
+    let r = f();
+    (ALLOCATIONS.with(|a| a.get()) - before, r)
+}
+
+#[test]
+fn add_reader() {
+    let w = Circular::with_capacity_and_readers::<f32>(0, 16).unwrap();
+
+    let mut readers = Vec::with_capacity(64);
+    let (n, _) = allocations(|| {
+        for _ in 0..16 {
+            readers.push(w.add_reader());
+        }
+    });
+    assert_eq!(n, 0);
+
+    readers.clear();
+    w.reserve_readers(48);
+    let (n, _) = allocations(|| {
+        for _ in 0..48 {
+            readers.push(w.add_reader());
+        }
+    });
+    assert_eq!(n, 0);
+}
+
+#[test]
+fn hot_path() {
+    let mut w = Circular::with_capacity::<f32>(0).unwrap();
+    let mut r = w.add_reader();
+    let mut l = w.add_lossy_reader();
+
+    let (n, _) = allocations(|| {
+        for _ in 0..1000 {
+            let s = w.try_slice();
+            let k = s.len().min(123);
+            s[..k].fill(1.0);
+            w.produce(k);
+
+            let k = r.try_slice().unwrap().len();
+            r.consume(k);
+            let k = l.try_slice().unwrap().len();
+            l.consume(k);
+        }
+    });
+    assert_eq!(n, 0);
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn sync_hot_path() {
+    let mut w = vmcircbuffer::sync::Circular::with_capacity::<f32>(0).unwrap();
+    let mut r = w.add_reader();
+
+    let (n, _) = allocations(|| {
+        for _ in 0..1000 {
+            let s = w.slice();
+            let k = s.len().min(123);
+            w.produce(k);
+
+            let k = r.slice().unwrap().len();
+            r.consume(k);
+        }
+    });
+    assert_eq!(n, 0);
+}
+
+#[test]
+fn heap_hot_path() {
+    use vmcircbuffer::double_mapped_buffer::{Backing, Builder};
+
+    let mut w = Circular::with_builder::<f32>(&Builder::new(0).backing(Backing::Heap)).unwrap();
+    let mut r = w.add_reader();
+
+    let (n, _) = allocations(|| {
+        for _ in 0..1000 {
+            let s = w.try_slice();
+            let k = s.len().min(123);
+            s[..k].fill(1.0);
+            w.produce(k);
+
+            let k = r.try_slice().unwrap().len();
+            r.consume(k);
+        }
+    });
+    assert_eq!(n, 0);
+}
@@ -1,7 +1,9 @@
 use rand::distributions::{Distribution, Uniform};
 use std::iter::repeat_with;
+use std::sync::Arc;
 
 use vmcircbuffer::asynchronous;
+use vmcircbuffer::generic::Metadata;
 
 #[test]
 fn wait_reader() {
@@ -90,3 +92,394 @@ fn fuzz_async() {
         }
     });
 }
+
+#[test]
+fn reader_timeout() {
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<f32>().unwrap();
+        let mut r = w.add_reader();
+
+        let now = std::time::Instant::now();
+        let timeout = std::time::Duration::from_millis(200);
+        assert!(r.slice_timeout(timeout).await.unwrap().is_empty());
+        assert!(now.elapsed() >= timeout);
+
+        let delay = std::time::Duration::from_millis(100);
+        smol::spawn(async move {
+            smol::Timer::after(delay).await;
+            let l = w.slice().await.len();
+            w.produce(l);
+        })
+        .detach();
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        assert!(!r.slice_deadline(deadline).await.unwrap().is_empty());
+        assert!(std::time::Instant::now() < deadline);
+    });
+}
+
+#[test]
+fn writer_timeout() {
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<f32>().unwrap();
+        let mut r = w.add_reader();
+
+        let l = w.slice().await.len();
+        w.produce(l);
+
+        let now = std::time::Instant::now();
+        let timeout = std::time::Duration::from_millis(200);
+        assert!(w.slice_timeout(timeout).await.is_empty());
+        assert!(now.elapsed() >= timeout);
+
+        let delay = std::time::Duration::from_millis(100);
+        smol::spawn(async move {
+            smol::Timer::after(delay).await;
+            let l = r.slice().await.unwrap().len();
+            r.consume(l);
+        })
+        .detach();
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        assert_eq!(w.slice_deadline(deadline).await.len(), l);
+        assert!(std::time::Instant::now() < deadline);
+    });
+}
+
+struct Positions(Vec<usize>);
+
+impl Metadata for Positions {
+    type Item = usize;
+    type View = Vec<usize>;
+
+    fn new() -> Self {
+        Positions(Vec::new())
+    }
+    fn add(&mut self, offset: usize, tags: Arc<[usize]>) {
+        self.0.extend(tags.iter().map(|t| t + offset));
+    }
+    fn get(&self) -> Vec<usize> {
+        self.0.clone()
+    }
+    fn consume(&mut self, items: usize) {
+        self.0.retain(|x| *x >= items);
+        for t in self.0.iter_mut() {
+            *t -= items;
+        }
+    }
+}
+
+#[test]
+fn tags() {
+    smol::block_on(async {
+        let mut w =
+            asynchronous::Circular::with_capacity_and_metadata::<u32, Positions>(0).unwrap();
+        let mut r = w.add_reader();
+
+        let _ = w.slice().await;
+        w.produce_with_meta(10, vec![2, 7]);
+        let _ = w.slice().await;
+        w.produce_with_meta(10, vec![0]);
+
+        let (s, tags) = r.slice_with_meta().await.unwrap();
+        assert_eq!(s.len(), 20);
+        assert_eq!(tags, vec![2, 7, 10]);
+
+        r.consume(5);
+        let (_, tags) = r.try_slice_with_meta().unwrap();
+        assert_eq!(tags, vec![2, 5]);
+    });
+}
+
+#[test]
+fn at_least() {
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<u32>().unwrap();
+        let mut r = w.add_reader();
+        let capacity = w.slice().await.len();
+
+        let delay = std::time::Duration::from_millis(100);
+        let now = std::time::Instant::now();
+
+        smol::spawn(async move {
+            for _ in 0..3 {
+                smol::Timer::after(delay).await;
+                let _ = w.slice().await;
+                w.produce(100);
+            }
+            let _ = w.space_at_least(capacity).await;
+        })
+        .detach();
+
+        let s = r.slice_at_least(250).await.unwrap();
+        assert_eq!(s.len(), 300);
+        assert!(now.elapsed() > 2 * delay);
+        r.consume(300);
+        assert!(r.slice_at_least(1).await.is_none());
+    });
+}
+
+#[test]
+fn guards() {
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<u32>().unwrap();
+        let mut r = w.add_reader();
+
+        let mut g = w.reserve(2).await;
+        g.copy_from_slice(&[7, 7]);
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+            let _g = g;
+            panic!("writer failed");
+        }));
+        assert!(res.is_err());
+
+        let mut g = w.reserve(2).await;
+        g.copy_from_slice(&[1, 2]);
+        drop(g);
+        drop(w);
+
+        let mut sum = 0;
+        while let Some(g) = r.read().await {
+            sum += g.iter().sum::<u32>();
+        }
+        assert_eq!(sum, 3);
+    });
+}
+
+#[test]
+fn async_io() {
+    use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<u8>().unwrap();
+        let mut r = w.add_reader();
+        let data: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+
+        let input = data.clone();
+        smol::spawn(async move {
+            w.write_all(&input).await.unwrap();
+        })
+        .detach();
+
+        let mut output = Vec::new();
+        r.read_to_end(&mut output).await.unwrap();
+        assert_eq!(output, data);
+    });
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn tokio_io() {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<u8>().unwrap();
+        let mut r = w.add_reader();
+        let data: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+
+        let input = data.clone();
+        smol::spawn(async move {
+            w.write_all(&input).await.unwrap();
+            w.write_all(b"\nend\n").await.unwrap();
+        })
+        .detach();
+
+        let mut output = vec![0; data.len()];
+        r.read_exact(&mut output).await.unwrap();
+        assert_eq!(output, data);
+
+        let mut line = String::new();
+        r.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "\n");
+        line.clear();
+        r.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "end\n");
+        assert_eq!(r.fill_buf().await.unwrap(), &[]);
+    });
+}
+
+#[test]
+fn stream() {
+    use futures::StreamExt;
+
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<u32>().unwrap();
+        let r = w.add_reader();
+
+        let s = w.slice().await;
+        for (i, v) in s.iter_mut().take(10).enumerate() {
+            *v = i as u32;
+        }
+        w.produce(10);
+        drop(w);
+
+        let chunks: Vec<Vec<u32>> = r.into_stream(4).collect().await;
+        assert_eq!(chunks, vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7], vec![8, 9]]);
+    });
+}
+
+#[test]
+fn frames() {
+    use futures::StreamExt;
+
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<u32>().unwrap();
+        let mut r = w.add_reader();
+
+        smol::spawn(async move {
+            for i in 0..10 {
+                let s = w.slice().await;
+                s[0] = i;
+                w.produce(1);
+                smol::Timer::after(std::time::Duration::from_millis(1)).await;
+            }
+        })
+        .detach();
+
+        let frames: Vec<Vec<u32>> = r.frames(4).collect().await;
+        assert_eq!(frames, vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7]]);
+        assert_eq!(r.slice().await.unwrap(), &[8, 9]);
+    });
+}
+
+#[test]
+fn async_io_vectored() {
+    use futures::io::{AsyncReadExt, AsyncWriteExt};
+    use std::io::{IoSlice, IoSliceMut};
+
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<u8>().unwrap();
+        let mut r = w.add_reader();
+
+        let n = w
+            .write_vectored(&[IoSlice::new(b"ab"), IoSlice::new(b"cd")])
+            .await
+            .unwrap();
+        assert_eq!(n, 4);
+        drop(w);
+
+        let mut a = [0u8; 3];
+        let mut b = [0u8; 3];
+        let n = r
+            .read_vectored(&mut [IoSliceMut::new(&mut a), IoSliceMut::new(&mut b)])
+            .await
+            .unwrap();
+        assert_eq!(n, 4);
+        assert_eq!(&a, b"abc");
+        assert_eq!(b[0], b'd');
+        assert_eq!(AsyncReadExt::read(&mut r, &mut a).await.unwrap(), 0);
+    });
+}
+
+#[test]
+fn async_drain_to() {
+    use futures::io::AsyncWriteExt;
+
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<u8>().unwrap();
+        let mut r = w.add_reader();
+
+        smol::spawn(async move {
+            for i in 0..10u8 {
+                w.write_all(&[i; 10]).await.unwrap();
+            }
+        })
+        .detach();
+
+        let mut sink = Vec::new();
+        assert_eq!(r.drain_to(&mut sink).await.unwrap(), 100);
+        assert_eq!(sink.len(), 100);
+        assert!(sink.chunks(10).enumerate().all(|(i, c)| c == [i as u8; 10]));
+    });
+}
+
+#[test]
+fn async_fill_from() {
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<u8>().unwrap();
+        let mut r = w.add_reader();
+
+        let mut source = futures::io::Cursor::new(vec![7u8; 50]);
+        assert_eq!(w.fill_from(&mut source).await.unwrap(), 50);
+        assert_eq!(w.fill_from(&mut source).await.unwrap(), 0);
+        assert_eq!(r.slice().await.unwrap(), &[7u8; 50]);
+    });
+}
+
+#[test]
+fn async_history() {
+    use futures::io::{AsyncReadExt, AsyncWriteExt};
+    use futures::StreamExt;
+    use vmcircbuffer::generic::ReaderOptions;
+
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<u8>().unwrap();
+        let mut r = w.add_reader_with_opts(&ReaderOptions::new().history(2));
+
+        w.write_all(&[1, 2, 3]).await.unwrap();
+        let mut buf = [0u8; 8];
+        assert_eq!(AsyncReadExt::read(&mut r, &mut buf).await.unwrap(), 3);
+        assert_eq!(&buf[0..3], &[1, 2, 3]);
+
+        w.write_all(&[4, 5, 6, 7]).await.unwrap();
+        drop(w);
+        assert_eq!(r.slice().await.unwrap(), &[2, 3, 4, 5, 6, 7]);
+        let frames: Vec<_> = r.frames(2).collect().await;
+        assert_eq!(frames, vec![vec![4, 5], vec![6, 7]]);
+        assert_eq!(AsyncReadExt::read(&mut r, &mut buf).await.unwrap(), 0);
+    });
+}
+
+#[test]
+fn slice_timeout_max() {
+    use std::time::Duration;
+
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<u8>().unwrap();
+        let mut r = w.add_reader();
+        let cap = w.capacity();
+        assert_eq!(w.slice_timeout(Duration::MAX).await.len(), cap);
+        w.produce(3);
+        assert_eq!(r.slice_timeout(Duration::MAX).await.unwrap().len(), 3);
+    });
+}
+
+#[test]
+fn detach_stalled_reader() {
+    use std::time::{Duration, Instant};
+
+    smol::block_on(async {
+        let mut w = asynchronous::Circular::new::<u32>().unwrap();
+        let mut stalled = w.add_reader();
+        let mut r = w.add_reader();
+        w.set_stall_timeout(Some(Duration::from_millis(50)));
+
+        let n = w.slice().await.len();
+        w.produce(n);
+        let l = r.slice().await.unwrap().len();
+        r.consume(l);
+
+        // resolves, once the writer woke up to detach the stalled reader
+        let now = Instant::now();
+        let n = w.slice().await.len();
+        assert!(now.elapsed() >= Duration::from_millis(50));
+        w.produce(n);
+
+        assert!(stalled.is_detached());
+        assert!(stalled.slice().await.is_none());
+        assert_eq!(r.slice().await.unwrap().len(), n);
+        r.consume(n);
+
+        // the same holds for reserve
+        let stalled = w.add_reader();
+        let n = w.slice().await.len();
+        w.produce(n);
+        let l = r.slice().await.unwrap().len();
+        r.consume(l);
+
+        let now = Instant::now();
+        drop(w.reserve(1).await);
+        assert!(now.elapsed() >= Duration::from_millis(50));
+        assert!(stalled.is_detached());
+    });
+}
@@ -0,0 +1,112 @@
+use vmcircbuffer::debug;
+use vmcircbuffer::generic::{self, NoMetadata};
+use vmcircbuffer::notifiers::ParkNotifier;
+
+#[test]
+fn registry() {
+    let mut w = generic::Circular::with_capacity::<u32, ParkNotifier, NoMetadata>(0).unwrap();
+    w.register("registry-test");
+    let mut r1 = w.add_reader(ParkNotifier::current(), ParkNotifier::current());
+    let _r2 = w.add_reader(ParkNotifier::current(), ParkNotifier::current());
+
+    w.slice(false);
+    w.produce(10, Vec::new());
+    r1.slice(false);
+    r1.consume(4);
+
+    let info = debug::buffers()
+        .into_iter()
+        .find(|b| b.name == "registry-test")
+        .unwrap();
+    assert_eq!(info.capacity, w.capacity());
+    assert_eq!(info.position, 10);
+    assert_eq!(info.occupancy, 10);
+    assert_eq!(info.reader_lags, vec![6, 10]);
+
+    drop(w);
+    drop(r1);
+    assert!(debug::buffers().iter().any(|b| b.name == "registry-test"));
+    drop(_r2);
+    assert!(!debug::buffers().iter().any(|b| b.name == "registry-test"));
+}
+
+#[test]
+fn registry_grow() {
+    use vmcircbuffer::double_mapped_buffer::Builder;
+
+    let b = Builder::new(0).power_of_two(true);
+    let mut w = generic::Circular::with_builder::<u32, ParkNotifier, NoMetadata>(&b).unwrap();
+    w.register("registry-grow-test");
+    let _r = w.add_reader(ParkNotifier::current(), ParkNotifier::current());
+
+    let cap = w.capacity();
+    w.grow(2 * cap + 1).unwrap();
+    // the builder settings are kept
+    assert_eq!(w.capacity(), 4 * cap);
+
+    let info = debug::buffers()
+        .into_iter()
+        .find(|b| b.name == "registry-grow-test")
+        .unwrap();
+    assert_eq!(info.capacity, w.capacity());
+}
+
+#[test]
+fn registry_history() {
+    let mut w = generic::Circular::with_capacity::<u32, ParkNotifier, NoMetadata>(0).unwrap();
+    w.register("registry-history-test");
+    let mut r = w.add_reader(ParkNotifier::current(), ParkNotifier::current());
+    r.set_history(4);
+
+    w.slice(false);
+    w.produce(10, Vec::new());
+    r.slice(false);
+    r.consume(6);
+
+    let info = debug::buffers()
+        .into_iter()
+        .find(|b| b.name == "registry-history-test")
+        .unwrap();
+    // the retained history does not count as lag
+    assert_eq!(info.reader_lags, vec![4]);
+    assert_eq!(info.reader_lags, w.reader_lags());
+}
+
+#[test]
+fn registry_drop_items() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let done = Arc::new(AtomicBool::new(false));
+    let inspector = {
+        let done = done.clone();
+        std::thread::spawn(move || {
+            while !done.load(Ordering::SeqCst) {
+                debug::buffers();
+            }
+        })
+    };
+
+    for _ in 0..1000 {
+        let mut w =
+            generic::Circular::with_capacity::<String, ParkNotifier, NoMetadata>(0).unwrap();
+        w.register("registry-drop-test");
+        let mut r = w.add_reader(ParkNotifier::current(), ParkNotifier::current());
+
+        let s = w.uninit_slice(false);
+        for (i, item) in s.iter_mut().take(10).enumerate() {
+            item.write(i.to_string());
+        }
+        unsafe { w.assume_init_produce(10, Vec::new()) };
+        r.slice(false);
+        r.consume(4);
+
+        // the inspector might hold the last reference to the state and drop
+        // the remaining items
+        drop(w);
+        drop(r);
+    }
+
+    done.store(true, Ordering::SeqCst);
+    inspector.join().unwrap();
+}
@@ -0,0 +1,51 @@
+use vmcircbuffer::framed::{Circular, FrameError};
+
+#[test]
+fn send_recv() {
+    let mut w = Circular::new().unwrap();
+    let mut r = w.add_reader();
+
+    w.send(b"foo").unwrap();
+    w.send(b"").unwrap();
+    w.send(b"barbaz").unwrap();
+    drop(w);
+
+    assert_eq!(r.recv(), Some(&b"foo"[..]));
+    assert_eq!(r.recv(), Some(&b""[..]));
+    assert_eq!(r.recv(), Some(&b"barbaz"[..]));
+    assert_eq!(r.recv(), None);
+}
+
+#[test]
+fn too_large() {
+    let mut w = Circular::new().unwrap();
+    let max = w.max_frame();
+    let frame = vec![0; max + 1];
+    assert_eq!(
+        w.send(&frame),
+        Err(FrameError::TooLarge { size: max + 1, max })
+    );
+}
+
+#[test]
+fn wrap() {
+    let mut w = Circular::new().unwrap();
+    let mut r = w.add_reader();
+    let max = w.max_frame();
+
+    let h = std::thread::spawn(move || {
+        for i in 0..1000usize {
+            let frame: Vec<u8> = (0..(i * 7) % max).map(|j| (i + j) as u8).collect();
+            w.send(&frame).unwrap();
+        }
+    });
+
+    let mut i = 0;
+    while let Some(frame) = r.recv() {
+        assert_eq!(frame.len(), (i * 7) % max);
+        assert!(frame.iter().enumerate().all(|(j, v)| *v == (i + j) as u8));
+        i += 1;
+    }
+    h.join().unwrap();
+    assert_eq!(i, 1000);
+}
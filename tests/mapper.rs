@@ -0,0 +1,73 @@
+use std::cell::UnsafeCell;
+use std::ptr;
+
+use vmcircbuffer::double_mapped_buffer::{DoubleMappedBuffer, MemoryMapper};
+use vmcircbuffer::generic::{Circular, NoMetadata, Notifier};
+
+struct MyNotifier;
+
+impl Notifier for MyNotifier {
+    fn arm(&mut self) {}
+    fn notify(&mut self) {}
+}
+
+const SIZE: usize = 256;
+
+#[repr(align(64))]
+struct StaticMemory(UnsafeCell<[u8; 2 * SIZE]>);
+
+unsafe impl Sync for StaticMemory {}
+
+static MEMORY: StaticMemory = StaticMemory(UnsafeCell::new([0; 2 * SIZE]));
+
+/// Static memory, where the second half is kept in sync by copying.
+struct StaticMapper;
+
+unsafe impl MemoryMapper for StaticMapper {
+    fn addr(&self) -> usize {
+        MEMORY.0.get() as usize
+    }
+
+    fn size_bytes(&self) -> usize {
+        SIZE
+    }
+
+    unsafe fn mirror(&self, offset: usize, len: usize) {
+        let base = self.addr() as *mut u8;
+        for i in offset..offset + len {
+            let src = base.add(i);
+            let dst = base.add((i + SIZE) % (2 * SIZE));
+            ptr::copy_nonoverlapping(src, dst, 1);
+        }
+    }
+}
+
+#[test]
+fn static_memory() {
+    let buffer = DoubleMappedBuffer::<u32>::from_mapper(StaticMapper).unwrap();
+    assert_eq!(buffer.capacity(), SIZE / 4);
+
+    let mut w = Circular::with_buffer::<u32, MyNotifier, NoMetadata>(buffer);
+    let mut r = w.add_reader(MyNotifier, MyNotifier);
+
+    let mut next_write = 0u32;
+    let mut next_read = 0u32;
+    for _ in 0..10 {
+        let s = w.slice(false);
+        let n = s.len().min(25);
+        for v in s[0..n].iter_mut() {
+            *v = next_write;
+            next_write += 1;
+        }
+        w.produce(n, Vec::new());
+
+        let (s, _) = r.slice(false).unwrap();
+        let n = s.len();
+        for v in s.iter() {
+            assert_eq!(*v, next_read);
+            next_read += 1;
+        }
+        r.consume(n);
+    }
+    assert_eq!(next_read, next_write);
+}
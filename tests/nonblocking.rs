@@ -17,6 +17,22 @@ fn zero_size() {
     assert!(!w.try_slice().is_empty());
 }
 
+#[cfg(unix)]
+#[test]
+fn allocation_error() {
+    use std::error::Error;
+    use vmcircbuffer::double_mapped_buffer::{Builder, DoubleMappedBufferError};
+    use vmcircbuffer::generic::CircularError;
+
+    let b = Builder::new(0).tmp_dir("/does/not/exist");
+    let e = Circular::with_builder::<u8>(&b).err().unwrap();
+    assert!(matches!(
+        e,
+        CircularError::Allocation(DoubleMappedBufferError::Create(_))
+    ));
+    assert!(e.source().is_some());
+}
+
 #[test]
 fn no_reader() {
     let mut w = Circular::new::<u8>().unwrap();
@@ -147,3 +163,712 @@ fn minimal() {
         assert_eq!(*v, 123);
     }
 }
+
+#[test]
+fn max_chunk() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+    r.set_max_chunk(10);
+
+    for (i, v) in w.try_slice().iter_mut().enumerate() {
+        *v = i as u32;
+    }
+    w.produce(25);
+
+    assert_eq!(r.try_slice().unwrap().len(), 10);
+    r.consume(10);
+    assert_eq!(r.try_slice().unwrap()[0], 10);
+    r.consume(10);
+    assert_eq!(r.try_slice().unwrap().len(), 5);
+    r.consume(5);
+    assert_eq!(r.try_slice().unwrap().len(), 0);
+}
+
+#[test]
+#[should_panic]
+fn consume_more_than_max_chunk() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+    r.set_max_chunk(10);
+
+    let _ = w.try_slice();
+    w.produce(25);
+
+    let _ = r.try_slice();
+    r.consume(11);
+}
+
+#[test]
+fn position() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r1 = w.add_reader();
+    let capacity = w.try_slice().len();
+
+    for _ in 0..3 {
+        let _ = w.try_slice();
+        w.produce(capacity - 1);
+        let _ = r1.try_slice();
+        r1.consume(capacity - 1);
+    }
+    assert_eq!(w.position(), 3 * (capacity as u64 - 1));
+    assert_eq!(r1.position(), w.position());
+
+    let mut r2 = w.add_reader();
+    assert_eq!(r2.position(), w.position());
+    let _ = w.try_slice();
+    w.produce(10);
+    let _ = r2.try_slice();
+    r2.consume(4);
+    assert_eq!(r2.position(), 3 * (capacity as u64 - 1) + 4);
+    assert_eq!(w.position(), 3 * (capacity as u64 - 1) + 10);
+}
+
+#[test]
+fn lossy_reader() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_lossy_reader();
+    let capacity = w.try_slice().len();
+
+    let mut i = 0;
+    for _ in 0..3 {
+        let s = w.try_slice();
+        assert_eq!(s.len(), capacity);
+        for v in s.iter_mut().take(capacity / 2) {
+            *v = i;
+            i += 1;
+        }
+        w.produce(capacity / 2);
+    }
+
+    let lost = 3 * (capacity / 2) - capacity;
+    assert_eq!(r.take_overrun(), lost);
+    assert_eq!(r.take_overrun(), 0);
+
+    let s = r.try_slice().unwrap();
+    assert_eq!(s.len(), capacity);
+    for (j, v) in s.iter().enumerate() {
+        assert_eq!(*v, (lost + j) as u32);
+    }
+}
+
+#[test]
+fn lossy_reader_consume_after_overrun() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_lossy_reader();
+    let capacity = w.try_slice().len();
+
+    for (i, v) in w.try_slice().iter_mut().enumerate() {
+        *v = i as u32;
+    }
+    w.produce(capacity);
+    assert_eq!(r.try_slice().unwrap().len(), capacity);
+
+    // the slice is not overwritten while the reader holds it
+    assert!(w.try_slice().is_empty());
+
+    r.consume(5);
+    let _ = w.try_slice();
+    w.produce(10);
+    assert_eq!(r.take_overrun(), 5);
+
+    assert_eq!(r.try_slice().unwrap().len(), capacity);
+    assert_eq!(r.position(), 10);
+    assert_eq!(r.try_slice().unwrap()[0], 10);
+}
+
+#[test]
+fn lossy_reader_behind_writer_slice() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_lossy_reader();
+    let capacity = w.try_slice().len();
+
+    for (i, v) in w.try_slice().iter_mut().enumerate() {
+        *v = i as u32;
+    }
+    w.produce(capacity);
+
+    // the writer holds a slice, before the reader gets its slice
+    assert_eq!(w.try_slice().len(), capacity);
+    let s = r.try_slice().unwrap();
+    assert!(s.is_empty());
+    assert_eq!(r.take_overrun(), capacity);
+    assert_eq!(r.position(), capacity as u64);
+
+    w.produce(10);
+    assert_eq!(r.try_slice().unwrap().len(), 10);
+    assert_eq!(r.take_overrun(), 0);
+}
+
+#[test]
+fn lossy_and_blocking_reader() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r1 = w.add_reader();
+    let mut r2 = w.add_lossy_reader();
+    let capacity = w.try_slice().len();
+
+    w.produce(capacity);
+    assert!(w.try_slice().is_empty());
+
+    let _ = r1.try_slice();
+    r1.consume(100);
+    assert_eq!(w.try_slice().len(), 100);
+    w.produce(100);
+    assert_eq!(r2.take_overrun(), 100);
+    assert_eq!(r2.try_slice().unwrap().len(), capacity);
+}
+
+#[test]
+fn shared_readers() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r1 = w.add_shared_reader();
+    let mut r2 = r1.share();
+    r1.set_max_chunk(100);
+
+    for (i, v) in w.try_slice().iter_mut().enumerate() {
+        *v = i as u32;
+    }
+    w.produce(300);
+
+    let s1 = r1.try_slice().unwrap();
+    assert_eq!(s1.len(), 100);
+    assert_eq!(s1[0], 0);
+    let s2 = r2.try_slice().unwrap();
+    assert_eq!(s2.len(), 200);
+    assert_eq!(s2[0], 100);
+
+    // r2 finishes first, but r1 still holds the oldest items
+    let capacity = w.try_slice().len() + 300;
+    r2.consume(200);
+    assert_eq!(w.try_slice().len(), capacity - 300);
+    assert_eq!(r1.position(), 0);
+    assert_eq!(r2.position(), 300);
+
+    r1.consume(50);
+    assert_eq!(r1.try_slice().unwrap()[0], 50);
+    r1.consume(50);
+    assert_eq!(r1.try_slice().unwrap().len(), 0);
+    assert_eq!(r2.try_slice().unwrap().len(), 0);
+}
+
+#[test]
+fn shared_readers_block_writer() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r1 = w.add_shared_reader();
+    let mut r2 = r1.share();
+    let capacity = w.try_slice().len();
+
+    w.produce(capacity);
+    r1.set_max_chunk(10);
+    let _ = r1.try_slice();
+    let _ = r2.try_slice();
+    r2.consume(capacity - 10);
+    assert!(w.try_slice().is_empty());
+
+    r1.consume(10);
+    assert_eq!(w.try_slice().len(), capacity);
+}
+
+#[test]
+fn shared_reader_drop_releases_claim() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r1 = w.add_shared_reader();
+    let mut r2 = r1.share();
+
+    for (i, v) in w.try_slice().iter_mut().enumerate() {
+        *v = i as u32;
+    }
+    w.produce(100);
+
+    assert_eq!(r1.try_slice().unwrap().len(), 100);
+    drop(r1);
+    let s = r2.try_slice().unwrap();
+    assert_eq!(s.len(), 100);
+    assert_eq!(s[0], 0);
+}
+
+#[test]
+fn shared_reader_drop_requeues_claim() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r1 = w.add_shared_reader();
+    let mut r2 = r1.share();
+    let mut r3 = r1.share();
+
+    for (i, v) in w.try_slice().iter_mut().enumerate() {
+        *v = i as u32;
+    }
+    w.produce(100);
+    assert_eq!(r1.try_slice().unwrap().len(), 100);
+    w.produce(50);
+    assert_eq!(r2.try_slice().unwrap()[0], 100);
+
+    // the claim of r1 is not the last one, but still goes to another reader
+    drop(r1);
+    assert_eq!(r3.available(), 100);
+    let s = r3.try_slice().unwrap();
+    assert_eq!(s.len(), 100);
+    assert_eq!(s[0], 0);
+    assert_eq!(s[99], 99);
+    r3.consume(100);
+
+    let s = r2.try_slice().unwrap();
+    assert_eq!(s.len(), 50);
+    assert_eq!(s[0], 100);
+    r2.consume(50);
+    assert_eq!(r2.try_slice().unwrap().len(), 0);
+    assert_eq!(r3.try_slice().unwrap().len(), 0);
+}
+
+#[test]
+fn multiple_writers() {
+    let mut w1 = Circular::new::<u32>().unwrap();
+    let mut r = w1.add_reader();
+    let capacity = w1.try_slice().len();
+    let mut w2 = w1.add_writer();
+
+    // w1 reserved all space
+    assert!(w2.try_slice().is_empty());
+    w1.try_slice()[0] = 1;
+    w1.produce(1);
+    assert_eq!(r.try_slice().unwrap(), &[1]);
+    r.consume(1);
+
+    // w1 holds the last reservation and gets the released slot
+    assert_eq!(w1.try_slice().len(), capacity);
+    w1.produce(capacity - 10);
+    assert_eq!(r.try_slice().unwrap().len(), capacity - 10);
+    r.consume(capacity - 10);
+
+    let s = w2.try_slice();
+    assert_eq!(s.len(), capacity - 10);
+    s[0] = 2;
+    w2.produce(1);
+    // items of w2 are only visible after the items reserved by w1
+    assert_eq!(r.try_slice().unwrap().len(), 0);
+
+    // readers skip the remaining reservation of w1
+    drop(w1);
+    assert_eq!(r.try_slice().unwrap(), &[2]);
+    r.consume(1);
+
+    drop(w2);
+    assert!(r.try_slice().is_none());
+}
+
+#[test]
+fn fork() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r1 = w.add_reader();
+    let capacity = w.try_slice().len();
+
+    for (i, v) in w.try_slice().iter_mut().enumerate() {
+        *v = i as u32;
+    }
+    w.produce(100);
+    r1.try_slice();
+    r1.consume(30);
+
+    let mut r2 = r1.fork();
+    assert_eq!(r2.position(), 30);
+    let s = r2.try_slice().unwrap();
+    assert_eq!(s.len(), 70);
+    assert_eq!(s[0], 30);
+    r2.consume(70);
+
+    // the fork is independent of the original reader
+    assert_eq!(r1.try_slice().unwrap().len(), 70);
+    assert_eq!(w.try_slice().len(), capacity - 70);
+    r1.consume(70);
+    drop(r2);
+    assert!(r1.try_slice().unwrap().is_empty());
+}
+
+#[test]
+fn skip_to_latest() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+    let capacity = w.try_slice().len();
+
+    for (i, v) in w.try_slice().iter_mut().enumerate() {
+        *v = i as u32;
+    }
+    w.produce(capacity);
+    assert!(w.try_slice().is_empty());
+
+    assert_eq!(r.try_slice().unwrap().len(), capacity);
+    assert_eq!(r.skip_to_latest(), capacity);
+    assert_eq!(r.position(), capacity as u64);
+    assert!(r.try_slice().unwrap().is_empty());
+    assert_eq!(w.try_slice().len(), capacity);
+
+    w.try_slice()[0] = 123;
+    w.produce(1);
+    assert_eq!(r.try_slice().unwrap(), &[123]);
+}
+
+#[test]
+fn discard() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+
+    for (i, v) in w.try_slice().iter_mut().enumerate() {
+        *v = i as u32;
+    }
+    w.produce(100);
+
+    assert_eq!(r.discard(10), 10);
+    assert_eq!(r.position(), 10);
+    assert_eq!(r.try_slice().unwrap()[0], 10);
+    assert_eq!(r.discard(100), 90);
+    assert!(r.try_slice().unwrap().is_empty());
+
+    // discarded items of the last slice count as consumed
+    w.produce(20);
+    assert_eq!(r.try_slice().unwrap().len(), 20);
+    assert_eq!(r.discard(5), 5);
+    r.consume(20);
+    assert_eq!(r.position(), 120);
+    assert!(r.try_slice().unwrap().is_empty());
+}
+
+#[test]
+fn discard_lossy_history() {
+    use vmcircbuffer::generic::ReaderOptions;
+
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader_with_opts(&ReaderOptions::new().non_blocking(true).history(2));
+    let capacity = w.try_slice().len();
+
+    w.produce(capacity);
+    assert_eq!(r.try_slice().unwrap().len(), capacity);
+    r.consume(capacity);
+
+    // only the history is left, which the discard does not hold
+    assert_eq!(r.discard(10), 0);
+    assert_eq!(w.try_slice().len(), capacity);
+    w.produce(10);
+    assert_eq!(r.discard(4), 4);
+    assert_eq!(r.try_slice().unwrap().len(), 2 + 6);
+}
+
+#[test]
+fn write_guard() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+    let capacity = w.try_slice().len();
+
+    {
+        let mut g = w.try_reserve(3).unwrap();
+        assert_eq!(g.len(), 3);
+        g.copy_from_slice(&[1, 2, 3]);
+    }
+    assert_eq!(r.try_slice().unwrap(), &[1, 2, 3]);
+
+    let mut g = w.try_reserve(3).unwrap();
+    g[0] = 4;
+    g.commit(1);
+    assert_eq!(r.try_slice().unwrap(), &[1, 2, 3, 4]);
+
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let mut g = w.try_reserve(2).unwrap();
+        g[0] = 7;
+        panic!("writer failed");
+    }));
+    assert!(res.is_err());
+    assert_eq!(r.try_slice().unwrap(), &[1, 2, 3, 4]);
+
+    assert!(w.try_reserve(capacity).is_none());
+    r.consume(4);
+    assert!(w.try_reserve(capacity).is_some());
+}
+
+#[test]
+fn read_guard() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+
+    w.try_slice()[0..3].copy_from_slice(&[1, 2, 3]);
+    w.produce(3);
+
+    let g = r.try_read().unwrap();
+    assert_eq!(&*g, &[1, 2, 3]);
+    g.consume(1);
+    assert_eq!(&*r.try_read().unwrap(), &[2, 3]);
+    assert!(r.try_read().unwrap().is_empty());
+
+    drop(w);
+    assert!(r.try_read().is_none());
+}
+
+#[test]
+fn try_produce_consume() {
+    use vmcircbuffer::generic::{ConsumeError, ProduceError};
+
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+
+    let l = w.try_slice().len();
+    assert_eq!(
+        w.try_produce(l + 1),
+        Err(ProduceError::TooMany {
+            requested: l + 1,
+            available: l
+        })
+    );
+    assert_eq!(w.try_produce(10), Ok(()));
+
+    assert_eq!(r.try_slice().unwrap().len(), 10);
+    assert_eq!(
+        r.try_consume(11),
+        Err(ConsumeError::TooMany {
+            requested: 11,
+            available: 10
+        })
+    );
+    assert_eq!(r.try_consume(10), Ok(()));
+}
+
+#[test]
+fn occupancy() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+    let capacity = w.capacity();
+    assert_eq!(r.capacity(), capacity);
+
+    assert_eq!(w.free_items(), capacity);
+    assert_eq!(r.available(), 0);
+
+    w.try_slice();
+    w.produce(10);
+    assert_eq!(w.free_items(), capacity - 10);
+    assert_eq!(r.available(), 10);
+
+    r.try_slice();
+    r.consume(4);
+    assert_eq!(w.free_items(), capacity - 6);
+    assert_eq!(r.available(), 6);
+}
+
+#[test]
+fn reader_lags() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r1 = w.add_reader();
+    let r2 = w.add_reader();
+    assert_eq!(w.reader_count(), 2);
+
+    w.try_slice();
+    w.produce(10);
+    r1.try_slice();
+    r1.consume(4);
+    assert_eq!(w.reader_lags(), vec![6, 10]);
+
+    drop(r2);
+    assert_eq!(w.reader_count(), 1);
+    assert_eq!(w.reader_lags(), vec![6]);
+}
+
+#[test]
+fn liveness() {
+    let mut w = Circular::new::<u32>().unwrap();
+    assert!(!w.has_readers());
+    let mut r = w.add_reader();
+    assert!(w.has_readers());
+    assert!(!r.writer_dropped());
+
+    w.try_slice();
+    w.produce(1);
+    drop(w);
+    assert!(r.writer_dropped());
+    assert_eq!(r.try_slice().unwrap().len(), 1);
+
+    let w = Circular::new::<u32>().unwrap();
+    let r = w.add_reader();
+    drop(r);
+    assert!(!w.has_readers());
+}
+
+#[test]
+fn produce_from_slice() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+    let capacity = w.capacity();
+
+    assert_eq!(w.produce_from_slice(&[1, 2, 3]), 3);
+    assert_eq!(r.try_slice().unwrap(), &[1, 2, 3]);
+
+    let items = vec![0; capacity];
+    assert_eq!(w.produce_from_slice(&items), capacity - 3);
+    assert_eq!(w.produce_from_slice(&items), 0);
+}
+
+#[test]
+fn read_into() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+
+    w.produce_from_slice(&[1, 2, 3, 4, 5]);
+    let mut dst = [0; 3];
+    assert_eq!(r.read_into(&mut dst), 3);
+    assert_eq!(dst, [1, 2, 3]);
+    assert_eq!(r.read_into(&mut dst), 2);
+    assert_eq!(dst[0..2], [4, 5]);
+    assert_eq!(r.read_into(&mut dst), 0);
+}
+
+#[test]
+fn write_with() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+
+    let n = w.write_with(|out| {
+        out[0..2].copy_from_slice(&[1, 2]);
+        2
+    });
+    assert_eq!(n, 2);
+    assert_eq!(r.try_slice().unwrap(), &[1, 2]);
+}
+
+#[test]
+fn read_with() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+
+    w.produce_from_slice(&[1, 2, 3]);
+    let mut sum = 0;
+    assert_eq!(
+        r.read_with(|input| {
+            sum = input.iter().sum();
+            input.len()
+        }),
+        Some(3)
+    );
+    assert_eq!(sum, 6);
+    assert_eq!(r.read_with(|input| input.len()), Some(0));
+
+    drop(w);
+    assert_eq!(r.read_with(|input| input.len()), None);
+}
+
+#[test]
+fn heap_fallback() {
+    use vmcircbuffer::double_mapped_buffer::{Backing, Builder};
+
+    let b = Builder::new(0).backing(Backing::Heap);
+    let mut w = Circular::with_builder::<u32>(&b).unwrap();
+    let mut r = w.add_reader();
+    let capacity = w.capacity();
+
+    let mut next_write = 0u32;
+    let mut next_read = 0u32;
+    for _ in 0..20 {
+        let s = w.try_slice();
+        let n = std::cmp::min(s.len(), capacity / 3);
+        for v in s[0..n].iter_mut() {
+            *v = next_write;
+            next_write += 1;
+        }
+        w.produce(n);
+
+        let s = r.try_slice().unwrap();
+        let n = s.len();
+        for v in s.iter() {
+            assert_eq!(*v, next_read);
+            next_read += 1;
+        }
+        r.consume(n);
+    }
+    assert_eq!(next_read, next_write);
+}
+
+#[test]
+fn drain() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+
+    let s = w.try_slice();
+    s[..3].copy_from_slice(&[1, 2, 3]);
+    w.produce(3);
+    assert_eq!(r.drain(), vec![1, 2, 3]);
+
+    let s = w.try_slice();
+    s[0] = 4;
+    w.produce(1);
+    assert_eq!(r.try_slice().unwrap(), &[4]);
+    assert_eq!(r.consume_all(), 1);
+    assert!(r.drain().is_empty());
+
+    drop(w);
+    assert!(r.drain().is_empty());
+}
+
+#[test]
+fn try_slice_min() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+    let cap = w.capacity();
+
+    assert_eq!(w.try_slice_min(cap).unwrap().len(), cap);
+    w.produce(cap - 10);
+    assert!(w.try_slice_min(11).is_none());
+    assert_eq!(w.try_slice_min(10).unwrap().len(), 10);
+    w.produce(10);
+    assert!(w.try_slice_min(0).is_none());
+
+    r.try_slice().unwrap();
+    r.consume(64);
+    assert_eq!(w.try_slice_min(64).unwrap().len(), 64);
+}
+
+#[test]
+fn slice_chunks() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+    let cap = w.capacity();
+
+    let n = w.try_slice_chunks(100).len();
+    assert_eq!(n, cap - cap % 100);
+    assert!(w.try_produce(n + 1).is_err());
+    w.produce(250);
+
+    let s = r.try_slice_chunks(100).unwrap();
+    assert_eq!(s.len(), 200);
+    assert!(r.try_consume(201).is_err());
+    r.consume(200);
+    assert_eq!(r.try_slice_chunks(100).unwrap().len(), 0);
+    assert_eq!(r.try_slice().unwrap().len(), 50);
+
+    drop(w);
+    assert!(r.try_slice_chunks(100).is_none());
+    assert_eq!(r.try_slice().unwrap().len(), 50);
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn bytes_buf() {
+    use bytes::{Buf, BufMut};
+
+    let mut w = Circular::new::<u8>().unwrap();
+    let mut r = w.add_reader();
+    let cap = w.capacity();
+
+    assert_eq!(w.remaining_mut(), cap);
+    w.put_u32(0xdead_beef);
+    w.put_slice(b"hello");
+    assert_eq!(w.remaining_mut(), cap - 9);
+
+    // the buf covers the last slice
+    assert_eq!(r.remaining(), 0);
+    r.try_slice();
+    assert_eq!(r.remaining(), 9);
+    assert_eq!(r.get_u32(), 0xdead_beef);
+
+    // advancing gets new bytes
+    w.put_slice(b" world");
+    assert_eq!(r.remaining(), 5);
+    assert_eq!(r.copy_to_bytes(5).as_ref(), b"hello");
+    assert_eq!(r.remaining(), 6);
+    assert_eq!(r.chunk(), b" world");
+    r.advance(6);
+    assert_eq!(r.remaining(), 0);
+    assert_eq!(r.position(), 15);
+    assert_eq!(w.remaining_mut(), cap);
+}
@@ -1,6 +1,9 @@
 use rand::distributions::{Distribution, Uniform};
 use std::iter::repeat_with;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
+use vmcircbuffer::double_mapped_buffer::BufferPool;
 use vmcircbuffer::sync::Circular;
 
 #[test]
@@ -186,3 +189,1867 @@ fn fuzz_sync() {
         r_off += l;
     }
 }
+
+#[test]
+fn max_chunk() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+    r.set_max_chunk(100);
+
+    let l = w.slice().len();
+    w.produce(l);
+
+    assert_eq!(r.slice().unwrap().len(), 100);
+    r.set_max_chunk(usize::MAX);
+    assert_eq!(r.slice().unwrap().len(), l);
+}
+
+#[test]
+fn reader_timeout() {
+    let mut w = Circular::new::<f32>().unwrap();
+    let mut r = w.add_reader();
+
+    let now = std::time::Instant::now();
+    let timeout = std::time::Duration::from_millis(200);
+    assert!(r.slice_timeout(timeout).unwrap().is_empty());
+    assert!(now.elapsed() >= timeout);
+
+    let delay = std::time::Duration::from_millis(100);
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let l = w.slice().len();
+        w.produce(l);
+    });
+
+    let now = std::time::Instant::now();
+    assert!(!r
+        .slice_timeout(std::time::Duration::from_secs(10))
+        .unwrap()
+        .is_empty());
+    assert!(now.elapsed() < std::time::Duration::from_secs(10));
+
+    let l = r.slice().unwrap().len();
+    r.consume(l);
+    assert!(r.slice_timeout(timeout).is_none());
+}
+
+#[test]
+fn writer_timeout() {
+    let mut w = Circular::new::<f32>().unwrap();
+    let mut r = w.add_reader();
+
+    let l = w.slice().len();
+    w.produce(l);
+
+    let now = std::time::Instant::now();
+    let timeout = std::time::Duration::from_millis(200);
+    assert!(w.slice_timeout(timeout).is_empty());
+    assert!(now.elapsed() >= timeout);
+
+    let delay = std::time::Duration::from_millis(100);
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let l = r.slice().unwrap().len();
+        r.consume(l);
+    });
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    assert_eq!(w.slice_deadline(deadline).len(), l);
+    assert!(std::time::Instant::now() < deadline);
+}
+
+#[test]
+fn reader_at_least() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+
+    let now = std::time::Instant::now();
+    let delay = std::time::Duration::from_millis(100);
+
+    std::thread::spawn(move || {
+        for _ in 0..4 {
+            std::thread::sleep(delay);
+            let _ = w.slice();
+            w.produce(100);
+        }
+    });
+
+    assert_eq!(r.slice_at_least(250).unwrap().len(), 300);
+    assert!(now.elapsed() > 3 * delay);
+    r.consume(250);
+
+    assert_eq!(r.slice_at_least(200).unwrap().len(), 150);
+    r.consume(150);
+    assert!(r.slice_at_least(200).is_none());
+}
+
+#[test]
+fn writer_wait_for_space() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+
+    let l = w.slice().len();
+    w.produce(l);
+
+    let now = std::time::Instant::now();
+    let delay = std::time::Duration::from_millis(100);
+
+    std::thread::spawn(move || {
+        for _ in 0..4 {
+            std::thread::sleep(delay);
+            let _ = r.slice();
+            r.consume(100);
+        }
+    });
+
+    assert!(w.wait_for_space(250).len() >= 250);
+    assert!(now.elapsed() > 3 * delay);
+}
+
+#[test]
+fn lossy_buffer() {
+    let mut w = Circular::with_capacity_lossy::<u32>(0).unwrap();
+    let mut r1 = w.add_reader();
+    let mut r2 = w.add_reader();
+    let capacity = w.slice().len();
+
+    for i in 0..5 {
+        let s = w.slice();
+        assert_eq!(s.len(), capacity);
+        for v in s.iter_mut() {
+            *v = i;
+        }
+        w.produce(capacity);
+    }
+
+    assert_eq!(r1.take_overrun(), 4 * capacity);
+    assert_eq!(r2.take_overrun(), 4 * capacity);
+    assert!(r1.slice().unwrap().iter().all(|v| *v == 4));
+}
+
+#[test]
+fn lossy_buffer_held_slice() {
+    let mut w = Circular::with_capacity_lossy::<u32>(0).unwrap();
+    let mut r = w.add_reader();
+    let capacity = w.slice().len();
+
+    w.slice().fill(1);
+    w.produce(capacity);
+    let s = r.slice().unwrap();
+    assert_eq!(s.len(), capacity);
+
+    let writer = std::thread::spawn(move || {
+        // waits, until the reader releases its slice
+        let s = w.slice();
+        let n = s.len();
+        s.fill(2);
+        w.produce(n);
+        n
+    });
+
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    assert!(s.iter().all(|v| *v == 1));
+    r.consume(capacity);
+
+    assert_eq!(writer.join().unwrap(), capacity);
+    assert_eq!(r.take_overrun(), 0);
+    assert!(r.slice().unwrap().iter().all(|v| *v == 2));
+}
+
+#[test]
+fn shared_readers() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let r = w.add_shared_reader();
+    let n_items = 1_000_000u64;
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let mut r = r.share();
+            r.set_max_chunk(123);
+            std::thread::spawn(move || {
+                let mut sum = 0u64;
+                let mut n = 0u64;
+                while let Some(s) = r.slice() {
+                    sum += s.iter().map(|v| *v as u64).sum::<u64>();
+                    n += s.len() as u64;
+                    let l = s.len();
+                    r.consume(l);
+                }
+                (n, sum)
+            })
+        })
+        .collect();
+    drop(r);
+
+    let mut i = 0u64;
+    while i < n_items {
+        let s = w.slice();
+        let l = std::cmp::min(s.len() as u64, n_items - i) as usize;
+        for v in s.iter_mut().take(l) {
+            *v = i as u32;
+            i += 1;
+        }
+        w.produce(l);
+    }
+    drop(w);
+
+    let (n, sum) = handles
+        .into_iter()
+        .map(|h| h.join().unwrap())
+        .fold((0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+    assert_eq!(n, n_items);
+    assert_eq!(sum, n_items * (n_items - 1) / 2);
+}
+
+#[test]
+fn multiple_writers() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+    let n_items = 200_000u32;
+
+    let handles: Vec<_> = (0..4u32)
+        .map(|id| {
+            let mut w = w.add_writer();
+            std::thread::spawn(move || {
+                let mut i = 0;
+                while i < n_items {
+                    let s = w.slice();
+                    let l = std::cmp::min(s.len() as u32, std::cmp::min(n_items - i, 64));
+                    for v in s.iter_mut().take(l as usize) {
+                        *v = id << 24 | i;
+                        i += 1;
+                    }
+                    w.produce(l as usize);
+                }
+            })
+        })
+        .collect();
+    drop(w);
+
+    let mut next = [0u32; 4];
+    while let Some(s) = r.slice() {
+        for v in s.iter() {
+            let id = (v >> 24) as usize;
+            assert_eq!(v & 0xff_ffff, next[id]);
+            next[id] += 1;
+        }
+        let l = s.len();
+        r.consume(l);
+    }
+
+    for h in handles {
+        h.join().unwrap();
+    }
+    assert_eq!(next, [n_items; 4]);
+}
+
+#[test]
+fn write_guard() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let mut g = w.reserve(2);
+        g[0] = 1;
+        g[1] = 2;
+        panic!("writer failed");
+    }));
+    assert!(res.is_err());
+    assert_eq!(r.try_slice().unwrap(), &[]);
+
+    {
+        let mut g = w.reserve(2);
+        g[0] = 3;
+        g[1] = 4;
+    }
+    assert_eq!(r.slice().unwrap(), &[3, 4]);
+}
+
+#[test]
+fn io() {
+    use std::io::{Read, Write};
+
+    let mut w = Circular::new::<u8>().unwrap();
+    let mut r = w.add_reader();
+    let data: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+
+    let input = data.clone();
+    let h = std::thread::spawn(move || {
+        w.write_all(&input).unwrap();
+    });
+
+    let mut output = Vec::new();
+    r.read_to_end(&mut output).unwrap();
+    h.join().unwrap();
+    assert_eq!(output, data);
+}
+
+#[test]
+fn buf_read() {
+    use std::io::{BufRead, Write};
+
+    let mut w = Circular::new::<u8>().unwrap();
+    let r = w.add_reader();
+
+    w.write_all(b"foo\nbar\nbaz").unwrap();
+    drop(w);
+
+    let lines: Vec<String> = r.lines().map(|l| l.unwrap()).collect();
+    assert_eq!(lines, vec!["foo", "bar", "baz"]);
+}
+
+#[test]
+fn iter() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+
+    let s = w.slice();
+    for (i, v) in s.iter_mut().take(10).enumerate() {
+        *v = i as u32;
+    }
+    w.produce(10);
+
+    let first: Vec<u32> = r.iter().take(3).collect();
+    assert_eq!(first, vec![0, 1, 2]);
+    assert_eq!(r.position(), 3);
+
+    drop(w);
+    let rest: Vec<u32> = r.into_iter().collect();
+    assert_eq!(rest, (3..10).collect::<Vec<u32>>());
+}
+
+#[test]
+fn extend() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let r = w.add_reader();
+    let n = 3 * w.capacity() as u32;
+
+    let h = std::thread::spawn(move || r.into_iter().collect::<Vec<u32>>());
+    w.extend(0..n);
+    drop(w);
+
+    assert_eq!(h.join().unwrap(), (0..n).collect::<Vec<u32>>());
+}
+
+fn transfer(strategy: vmcircbuffer::sync::WaitStrategy, n_items: u32) {
+    let mut w = Circular::with_wait_strategy::<u32>(0, strategy).unwrap();
+    let mut r = w.add_reader();
+    assert_eq!(r.wait_strategy(), strategy);
+
+    let handle = std::thread::spawn(move || {
+        let mut next = 0;
+        while next < n_items {
+            let s = w.slice();
+            let n = std::cmp::min(s.len(), (n_items - next) as usize);
+            for v in s[0..n].iter_mut() {
+                *v = next;
+                next += 1;
+            }
+            w.produce(n);
+        }
+    });
+
+    let mut next = 0;
+    while let Some(s) = r.slice() {
+        for v in s.iter() {
+            assert_eq!(*v, next);
+            next += 1;
+        }
+        let l = s.len();
+        r.consume(l);
+    }
+    assert_eq!(next, n_items);
+    handle.join().unwrap();
+}
+
+#[test]
+fn wait_park() {
+    transfer(vmcircbuffer::sync::WaitStrategy::Park, 1_000_000);
+}
+
+#[test]
+fn park_notifier() {
+    use vmcircbuffer::generic::{self, NoMetadata};
+    use vmcircbuffer::notifiers::ParkNotifier;
+
+    let mut w = generic::Circular::with_capacity::<u32, ParkNotifier, NoMetadata>(0).unwrap();
+    let mut r = w.add_reader(ParkNotifier::current(), ParkNotifier::current());
+
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let s = w.slice(false);
+        s[0] = 123;
+        w.produce(1, Vec::new());
+    });
+
+    loop {
+        match r.slice(true) {
+            Some(([], _)) => std::thread::park(),
+            Some((s, _)) => {
+                assert_eq!(s[0], 123);
+                break;
+            }
+            None => panic!("writer dropped"),
+        }
+    }
+    handle.join().unwrap();
+}
+
+#[cfg(feature = "crossbeam")]
+#[test]
+fn channel_notifier() {
+    use vmcircbuffer::generic::{self, NoMetadata};
+    use vmcircbuffer::notifiers::ChannelNotifier;
+
+    let (tx, rx) = crossbeam_channel::bounded(1);
+    let mut w = generic::Circular::with_capacity::<u32, ChannelNotifier, NoMetadata>(0).unwrap();
+    let mut r1 = w.add_reader(
+        ChannelNotifier::with_sender(tx.clone()),
+        ChannelNotifier::new().0,
+    );
+    let mut r2 = w.add_reader(ChannelNotifier::with_sender(tx), ChannelNotifier::new().0);
+
+    // not armed
+    w.slice(false)[0] = 1;
+    w.produce(1, Vec::new());
+    assert!(rx.try_recv().is_err());
+
+    r1.slice(false);
+    r1.consume(1);
+    r2.slice(false);
+    r2.consume(1);
+    assert!(r1.slice(true).unwrap().0.is_empty());
+    assert!(r2.slice(true).unwrap().0.is_empty());
+
+    // both readers fire, but the notifications are merged
+    w.slice(false)[0] = 2;
+    w.produce(1, Vec::new());
+    assert!(rx.try_recv().is_ok());
+    assert!(rx.try_recv().is_err());
+    assert_eq!(r1.slice(false).unwrap().0, &[2]);
+    assert_eq!(r2.slice(false).unwrap().0, &[2]);
+}
+
+#[cfg(feature = "bytemuck")]
+#[test]
+fn view_as() {
+    let mut w = Circular::new::<u8>().unwrap();
+    let mut r = w.add_reader();
+    let capacity = w.capacity();
+
+    let s = w.view_as_mut::<f32>().unwrap();
+    assert_eq!(s.len(), capacity / 4);
+    s[0] = 1.5;
+    s[1] = -2.0;
+    w.produce(8);
+
+    assert_eq!(r.view_as::<f32>().unwrap().unwrap(), &[1.5, -2.0]);
+    r.consume(2);
+    assert!(r.view_as::<f32>().is_err());
+    assert_eq!(r.view_as::<i16>().unwrap().unwrap().len(), 3);
+    r.consume(6);
+
+    w.slice()[0] = 1;
+    w.produce(1);
+    assert!(w.view_as_mut::<u32>().is_err());
+    w.produce(3);
+    w.view_as_mut::<u32>().unwrap()[0] = 23;
+    w.produce(4);
+    drop(w);
+
+    r.slice().unwrap();
+    r.consume(4);
+    assert_eq!(r.view_as::<u32>().unwrap().unwrap(), &[23]);
+    r.consume(4);
+    assert_eq!(r.view_as::<u32>().unwrap(), None);
+}
+
+#[test]
+fn wait_spin_then_block() {
+    transfer(
+        vmcircbuffer::sync::WaitStrategy::SpinThenBlock {
+            spins: 100,
+            yields: 10,
+        },
+        1_000_000,
+    );
+}
+
+#[test]
+fn wait_spin() {
+    // keep it short, spinning is slow without a core per thread
+    transfer(vmcircbuffer::sync::WaitStrategy::Spin, 10_000);
+}
+
+#[test]
+fn wait_timeout_strategy() {
+    transfer(
+        vmcircbuffer::sync::WaitStrategy::Timeout(std::time::Duration::from_millis(1)),
+        1_000_000,
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn wait_eventfd() {
+    transfer(vmcircbuffer::sync::WaitStrategy::EventFd, 1_000_000);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn eventfd_exhausted() {
+    use vmcircbuffer::generic::CircularError;
+    use vmcircbuffer::sync::WaitStrategy;
+
+    // the file descriptor limit applies to the whole process, i.e., the test
+    // runs in a child process to not affect the other tests
+    if std::env::var_os("VMCIRCBUFFER_EVENTFD_CHILD").is_none() {
+        let status = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "eventfd_exhausted"])
+            .env("VMCIRCBUFFER_EVENTFD_CHILD", "1")
+            .status()
+            .unwrap();
+        assert!(status.success());
+        return;
+    }
+
+    let mut w = Circular::with_wait_strategy::<u32>(0, WaitStrategy::EventFd).unwrap();
+    let r = w.add_shared_reader();
+
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    unsafe {
+        assert_eq!(libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit), 0);
+        limit.rlim_cur = 0;
+        assert_eq!(libc::setrlimit(libc::RLIMIT_NOFILE, &limit), 0);
+    }
+
+    assert!(matches!(
+        w.try_add_reader(),
+        Err(CircularError::WaitStrategy(_))
+    ));
+    assert!(matches!(
+        w.try_add_lossy_reader(),
+        Err(CircularError::WaitStrategy(_))
+    ));
+    assert!(matches!(
+        w.try_add_writer(),
+        Err(CircularError::WaitStrategy(_))
+    ));
+    assert!(matches!(r.try_share(), Err(CircularError::WaitStrategy(_))));
+    assert!(matches!(r.try_fork(), Err(CircularError::WaitStrategy(_))));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn eventfd_poll() {
+    let mut w =
+        Circular::with_wait_strategy::<u32>(0, vmcircbuffer::sync::WaitStrategy::EventFd).unwrap();
+    let mut r = w.add_reader();
+    assert!(w.as_raw_fd().is_some());
+    let fd = r.as_raw_fd().unwrap();
+
+    let readable = |timeout: i32| {
+        let mut pfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut pfd, 1, timeout) == 1 }
+    };
+
+    assert_eq!(r.try_slice().unwrap().len(), 0);
+    assert!(!readable(0));
+
+    let handle = std::thread::spawn(move || {
+        w.slice()[0] = 23;
+        w.produce(1);
+        w
+    });
+
+    assert!(readable(10_000));
+    assert_eq!(r.try_slice().unwrap(), &[23]);
+    r.consume(1);
+    assert_eq!(r.try_slice().unwrap().len(), 0);
+    assert!(!readable(0));
+    drop(handle.join().unwrap());
+
+    assert!(readable(10_000));
+    assert!(r.try_slice().is_none());
+
+    let w = Circular::with_capacity::<u32>(0).unwrap();
+    assert!(w.add_reader().as_raw_fd().is_none());
+}
+
+#[cfg(all(feature = "tokio", target_os = "linux"))]
+#[test]
+fn eventfd_tokio() {
+    use vmcircbuffer::sync::WaitStrategy;
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        let mut w = Circular::with_wait_strategy::<u32>(0, WaitStrategy::EventFd).unwrap();
+        let mut r = w.add_reader();
+
+        w.writable().await.unwrap();
+        let l = w.try_slice().len();
+        w.produce(l);
+
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            let n = r.slice().unwrap().len();
+            r.consume(n);
+            r
+        });
+        w.writable().await.unwrap();
+        assert_eq!(w.try_slice().len(), l);
+        let mut r = handle.join().unwrap();
+
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            w.slice()[0] = 23;
+            w.produce(1);
+            w
+        });
+        r.readable().await.unwrap();
+        assert_eq!(r.try_slice().unwrap(), &[23]);
+        r.consume(1);
+        drop(handle.join().unwrap());
+
+        r.readable().await.unwrap();
+        assert!(r.try_slice().is_none());
+
+        let mut w = Circular::with_capacity::<u32>(0).unwrap();
+        let e = w.writable().await.unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::Unsupported);
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn eventfd_notifier() {
+    use std::os::unix::io::AsRawFd;
+    use vmcircbuffer::generic::{self, NoMetadata};
+    use vmcircbuffer::notifiers::EventFdNotifier;
+
+    let mut w = generic::Circular::with_capacity::<u32, EventFdNotifier, NoMetadata>(0).unwrap();
+    let r_notifier = EventFdNotifier::new().unwrap();
+    let fd = r_notifier.as_raw_fd();
+    let mut r = w.add_reader(r_notifier.clone(), EventFdNotifier::new().unwrap());
+
+    assert_eq!(r.slice(true).unwrap().0.len(), 0);
+    assert!(!r_notifier.reset());
+    w.slice(false)[0] = 1;
+    w.produce(1, Vec::new());
+
+    let mut pfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    assert_eq!(unsafe { libc::poll(&mut pfd, 1, 0) }, 1);
+    assert!(r_notifier.reset());
+    assert!(!r_notifier.reset());
+    assert_eq!(r.slice(false).unwrap().0, &[1]);
+}
+
+#[derive(Clone)]
+struct CountingNotifier {
+    count: Arc<AtomicUsize>,
+    last: Arc<AtomicUsize>,
+    armed: bool,
+}
+
+impl CountingNotifier {
+    fn new() -> Self {
+        CountingNotifier {
+            count: Arc::new(AtomicUsize::new(0)),
+            last: Arc::new(AtomicUsize::new(0)),
+            armed: false,
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    fn last(&self) -> usize {
+        self.last.load(Ordering::SeqCst)
+    }
+}
+
+impl vmcircbuffer::generic::Notifier for CountingNotifier {
+    fn arm(&mut self) {
+        self.armed = true;
+    }
+    fn notify(&mut self) {
+        if self.armed {
+            self.count.fetch_add(1, Ordering::SeqCst);
+            self.armed = false;
+        }
+    }
+    fn notify_available(&mut self, n: usize) {
+        if self.armed {
+            self.last.store(n, Ordering::SeqCst);
+        }
+        self.notify();
+    }
+}
+
+#[test]
+fn watermark() {
+    use vmcircbuffer::generic::{self, NoMetadata};
+
+    let mut w = generic::Circular::with_capacity::<u32, CountingNotifier, NoMetadata>(0).unwrap();
+    let r_notifier = CountingNotifier::new();
+    let w_notifier = CountingNotifier::new();
+    let mut r = w.add_reader(r_notifier.clone(), w_notifier.clone());
+    r.set_watermark(10);
+    w.set_watermark(5);
+
+    assert_eq!(r.slice(true).unwrap().0.len(), 0);
+    for i in 1..10 {
+        w.slice(false);
+        w.produce(1, Vec::new());
+        assert_eq!(r_notifier.count(), 0, "notified after {i} items");
+    }
+    w.slice(false);
+    w.produce(1, Vec::new());
+    assert_eq!(r_notifier.count(), 1);
+
+    let cap = w.capacity();
+    let n = w.slice(false).len();
+    w.produce(n, Vec::new());
+    assert_eq!(w.slice(true).len(), 0);
+    r.slice(false);
+    for _ in 0..4 {
+        r.consume(1);
+        assert_eq!(w_notifier.count(), 0);
+    }
+    r.consume(1);
+    assert_eq!(w_notifier.count(), 1);
+    assert_eq!(w.slice(false).len(), 5);
+    assert_eq!(r.slice(false).unwrap().0.len(), cap - 5);
+
+    // dropping the writer notifies regardless of the watermark
+    r.consume(cap - 5);
+    assert_eq!(r.slice(true).unwrap().0.len(), 0);
+    drop(w);
+    assert_eq!(r_notifier.count(), 2);
+}
+
+#[test]
+fn split_notifiers() {
+    use vmcircbuffer::double_mapped_buffer::Builder;
+    use vmcircbuffer::generic::{self, NoMetadata};
+    use vmcircbuffer::notifiers::ParkNotifier;
+
+    let mut w =
+        generic::Circular::with_notifiers::<u32, ParkNotifier, CountingNotifier, NoMetadata>(
+            &Builder::new(0),
+        )
+        .unwrap();
+    let w_notifier = CountingNotifier::new();
+    let mut r = w.add_reader(ParkNotifier::current(), w_notifier.clone());
+
+    let n = w.slice(false).len();
+    w.produce(n, Vec::new());
+    assert_eq!(w.slice(true).len(), 0);
+    assert_eq!(r.slice(false).unwrap().0.len(), n);
+    r.consume(n);
+    assert_eq!(w_notifier.count(), 1);
+
+    assert_eq!(r.slice(true).unwrap().0.len(), 0);
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        w.slice(false)[0] = 123;
+        w.produce(1, Vec::new());
+    });
+    loop {
+        match r.slice(true) {
+            Some(([], _)) => std::thread::park(),
+            Some((s, _)) => {
+                assert_eq!(s, &[123]);
+                break;
+            }
+            None => panic!("writer dropped"),
+        }
+    }
+    handle.join().unwrap();
+}
+
+#[test]
+fn dyn_notifiers() {
+    use vmcircbuffer::generic::{self, DynNotifier, DynReader, DynWriter};
+    use vmcircbuffer::notifiers::ParkNotifier;
+
+    let mut w: DynWriter<u32> = generic::Circular::with_capacity(0).unwrap();
+    let counting = CountingNotifier::new();
+    let mut r1: DynReader<u32> = w.add_reader(
+        Box::new(counting.clone()),
+        Box::new(ParkNotifier::current()) as DynNotifier,
+    );
+    let mut r2 = w.add_reader(
+        Box::new(ParkNotifier::current()),
+        Box::new(CountingNotifier::new()),
+    );
+
+    assert_eq!(r1.slice(true).unwrap().0.len(), 0);
+    assert_eq!(r2.slice(true).unwrap().0.len(), 0);
+    w.slice(false)[0] = 1;
+    w.produce(1, Vec::new());
+    assert_eq!(counting.count(), 1);
+    assert_eq!(r1.slice(false).unwrap().0, &[1]);
+    assert_eq!(r2.slice(false).unwrap().0, &[1]);
+}
+
+#[test]
+fn notify_counts() {
+    use vmcircbuffer::generic::{self, NoMetadata};
+
+    let mut w = generic::Circular::with_capacity::<u32, CountingNotifier, NoMetadata>(0).unwrap();
+    let r_notifier = CountingNotifier::new();
+    let w_notifier = CountingNotifier::new();
+    let mut r = w.add_reader(r_notifier.clone(), w_notifier.clone());
+    let cap = w.capacity();
+
+    assert_eq!(r.slice(true).unwrap().0.len(), 0);
+    w.slice(false);
+    w.produce(3, Vec::new());
+    assert_eq!(r_notifier.last(), 3);
+
+    let n = w.slice(false).len();
+    w.produce(n, Vec::new());
+    assert_eq!(w.slice(true).len(), 0);
+    r.slice(false);
+    r.consume(7);
+    assert_eq!(w_notifier.count(), 1);
+    assert_eq!(w_notifier.last(), 7);
+
+    r.consume(cap - 7);
+    assert_eq!(r.slice(true).unwrap().0.len(), 0);
+    drop(w);
+    assert_eq!(r_notifier.count(), 2);
+    assert_eq!(r_notifier.last(), 0);
+}
+
+#[test]
+fn hooks() {
+    use vmcircbuffer::generic::{self, NoMetadata};
+    use vmcircbuffer::notifiers::ParkNotifier;
+
+    let mut w = generic::Circular::with_capacity::<u32, ParkNotifier, NoMetadata>(0).unwrap();
+    let mut r = w.add_reader(ParkNotifier::current(), ParkNotifier::current());
+
+    let produced = Arc::new(AtomicUsize::new(0));
+    let consumed = Arc::new(Mutex::new(Vec::new()));
+    let p = produced.clone();
+    w.set_on_produce(move |n| {
+        p.fetch_add(n, Ordering::SeqCst);
+    });
+    let c = consumed.clone();
+    w.set_on_consume(move |id, n| c.lock().unwrap().push((id, n)));
+
+    w.slice(false);
+    w.produce(5, Vec::new());
+    w.slice(false);
+    w.produce(3, Vec::new());
+    assert_eq!(produced.load(Ordering::SeqCst), 8);
+
+    r.slice(false);
+    r.consume(2);
+    assert_eq!(r.skip_to_latest(), 6);
+    assert_eq!(*consumed.lock().unwrap(), vec![(r.id(), 2), (r.id(), 6)]);
+}
+
+#[test]
+fn stats() {
+    let mut w = Circular::with_capacity::<u32>(0).unwrap();
+    let mut r = w.add_reader();
+    assert!(w.stats().is_none());
+    let w_stats = w.enable_stats();
+    let r_stats = r.enable_stats();
+    let cap = w.capacity();
+
+    w.slice();
+    w.produce(10);
+    assert_eq!(w_stats.items(), 10);
+    assert_eq!(w_stats.occupancy(), 10);
+
+    assert_eq!(r.slice().unwrap().len(), 10);
+    r.consume(4);
+    assert_eq!(r_stats.items(), 4);
+    assert_eq!(r_stats.occupancy(), 6);
+    r.consume(6);
+    assert_eq!(r_stats.wakeups(), 0);
+
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        w.slice();
+        w.produce(1);
+        w
+    });
+    assert_eq!(r.slice().unwrap().len(), 1);
+    assert!(r_stats.wakeups() >= 1);
+    assert!(r_stats.blocked() > std::time::Duration::ZERO);
+
+    let w = handle.join().unwrap();
+    assert_eq!(w.stats().unwrap().items(), 11);
+    assert!(w_stats.occupancy() <= cap);
+}
+
+#[test]
+fn debug() {
+    let mut w = Circular::with_capacity::<u32>(0).unwrap();
+    let mut r = w.add_reader();
+    w.slice();
+    w.produce(3);
+    r.slice();
+    r.consume(1);
+
+    let s = format!("{w:?}");
+    assert!(s.contains("writer_position: 3"), "{s}");
+    assert!(s.contains("lag: 2"), "{s}");
+    assert!(s.contains("writer_done: false"), "{s}");
+    let s = format!("{r:#?}");
+    assert!(s.contains("position: 1"), "{s}");
+    drop(w);
+    assert!(format!("{r:?}").contains("writer_done: true"));
+}
+
+#[test]
+fn stats_high_water() {
+    let mut w = Circular::with_capacity::<u32>(0).unwrap();
+    let mut r = w.add_reader();
+    let w_stats = w.enable_stats();
+    let r_stats = r.enable_stats();
+    let cap = w.capacity();
+
+    assert_eq!(r.try_slice().unwrap().len(), 0);
+    assert_eq!(r_stats.empty_slices(), 1);
+
+    w.slice();
+    w.produce(10);
+    assert_eq!(r.slice().unwrap().len(), 10);
+    r.consume(10);
+    w.slice();
+    w.produce(4);
+    assert_eq!(w_stats.occupancy(), 4);
+    assert_eq!(w_stats.max_occupancy(), 10);
+    assert_eq!(w_stats.min_free(), cap - 10);
+    assert_eq!(r_stats.max_occupancy(), 10);
+
+    let n = w.try_slice().len();
+    w.produce(n);
+    assert_eq!(w.try_slice().len(), 0);
+    assert_eq!(w_stats.empty_slices(), 1);
+    assert_eq!(w_stats.min_free(), 0);
+}
+
+#[test]
+fn history() {
+    use vmcircbuffer::generic::{self, NoMetadata, Tag, Tags};
+
+    let mut w = generic::Circular::with_capacity::<u32, CountingNotifier, Tags<u32>>(0).unwrap();
+    let cap = w.capacity();
+    let mut r = w.add_reader(CountingNotifier::new(), CountingNotifier::new());
+    r.set_history(3);
+
+    let s = w.slice(false);
+    for (i, v) in s.iter_mut().take(5).enumerate() {
+        *v = i as u32;
+    }
+    w.produce(5, vec![Tag { item: 1, data: 7 }]);
+
+    // no history before the first items are consumed
+    assert_eq!(r.slice(false).unwrap().0, &[0, 1, 2, 3, 4]);
+    r.consume(2);
+    let (s, tags) = r.slice(false).unwrap();
+    assert_eq!(s, &[0, 1, 2, 3, 4]);
+    assert_eq!(tags, vec![Tag { item: 1, data: 7 }]);
+    assert_eq!(r.position(), 2);
+    assert_eq!(r.available(), 3);
+    r.consume(3);
+    let (s, tags) = r.slice(false).unwrap();
+    assert_eq!(s, &[2, 3, 4]);
+    assert!(tags.is_empty());
+    assert_eq!(w.reader_lags(), vec![0]);
+
+    // the writer does not overwrite the history
+    assert_eq!(w.slice(false).len(), cap - 3);
+    let n = cap - 3;
+    let s = w.slice(false);
+    for (i, v) in s.iter_mut().enumerate() {
+        *v = 5 + i as u32;
+    }
+    w.produce(n, Vec::new());
+    assert_eq!(w.slice(false).len(), 0);
+    let s = r.slice(false).unwrap().0;
+    assert_eq!(s.len(), cap);
+    assert_eq!(&s[0..4], &[2, 3, 4, 5]);
+    assert!(r.try_consume(n + 1).is_err());
+    r.consume(1);
+    assert_eq!(w.slice(false).len(), 1);
+
+    // reducing the history frees space
+    r.set_history(0);
+    assert_eq!(w.slice(false).len(), 4);
+    assert_eq!(r.slice(false).unwrap().0[0], 6);
+
+    let mut w = generic::Circular::with_capacity::<u32, CountingNotifier, NoMetadata>(0).unwrap();
+    let mut r = w.add_lossy_reader(CountingNotifier::new(), CountingNotifier::new());
+    r.set_history(2);
+    w.slice(false);
+    w.produce(2, Vec::new());
+    r.slice(false);
+    r.consume(2);
+    // a lossy reader loses the history first
+    w.slice(false);
+    w.produce(cap - 1, Vec::new());
+    assert_eq!(r.take_overrun(), 0);
+    assert_eq!(r.slice(false).unwrap().0.len(), cap);
+    // the writer does not overwrite the slice, while the reader holds it
+    assert_eq!(w.slice(false).len(), 0);
+    r.consume(1);
+    w.slice(false);
+    w.produce(3, Vec::new());
+    assert_eq!(r.take_overrun(), 1);
+    assert_eq!(r.slice(false).unwrap().0.len(), cap);
+    assert_eq!(r.available(), cap);
+}
+
+#[test]
+fn multi_channel() {
+    use vmcircbuffer::generic::{self, NoMetadata};
+
+    let w = generic::Circular::new_multi::<u32, CountingNotifier, NoMetadata>(3, 1).unwrap();
+    check_multi_channel(w);
+}
+
+#[test]
+fn multi_channel_heap() {
+    use vmcircbuffer::double_mapped_buffer::{Backing, Builder};
+    use vmcircbuffer::generic::{self, NoMetadata};
+
+    // heap-backed planes have to be mirrored explicitly
+    let builder = Builder::new(1).backing(Backing::Heap);
+    let w =
+        generic::Circular::new_multi_with_builder::<u32, CountingNotifier, NoMetadata>(3, &builder)
+            .unwrap();
+    check_multi_channel(w);
+}
+
+fn check_multi_channel(
+    mut w: vmcircbuffer::generic::MultiWriter<
+        u32,
+        CountingNotifier,
+        vmcircbuffer::generic::NoMetadata,
+    >,
+) {
+    let cap = w.capacity();
+    let mut r = w.add_reader(CountingNotifier::new(), CountingNotifier::new());
+    assert_eq!(w.channels(), 3);
+    assert_eq!(r.channels(), 3);
+
+    let mut next = 0;
+    for _ in 0..5 {
+        let n = cap * 2 / 3;
+        let slices = w.slice(false);
+        assert_eq!(slices.len(), 3);
+        for (c, s) in slices.into_iter().enumerate() {
+            assert!(s.len() >= n);
+            for (i, v) in s[0..n].iter_mut().enumerate() {
+                *v = ((next + i) * 10 + c) as u32;
+            }
+        }
+        w.produce(n, Vec::new());
+
+        // read in two steps, so that items written past the wrap point are
+        // also read from the start of the buffer
+        let mut len = n;
+        for m in [n / 2, n - n / 2] {
+            let (slices, _) = r.slice(false).unwrap();
+            for (c, s) in slices.iter().enumerate() {
+                assert_eq!(s.len(), len);
+                for (i, v) in s.iter().enumerate() {
+                    assert_eq!(*v, ((next + i) * 10 + c) as u32);
+                }
+            }
+            r.consume(m);
+            next += m;
+            len -= m;
+        }
+    }
+
+    drop(w);
+    assert!(r.slice(false).is_none());
+}
+
+struct DropCounter(Arc<AtomicUsize>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn uninit_drop_items() {
+    use vmcircbuffer::generic::{self, NoMetadata, ProduceError};
+
+    let drops = Arc::new(AtomicUsize::new(0));
+    let mut w =
+        generic::Circular::with_capacity::<DropCounter, CountingNotifier, NoMetadata>(0).unwrap();
+    let cap = w.capacity();
+    let mut r = w.add_reader(CountingNotifier::new(), CountingNotifier::new());
+
+    let mut produced = 0;
+    for _ in 0..3 {
+        let s = w.uninit_slice(false);
+        assert_eq!(s.len(), cap);
+        for v in s[0..cap - 1].iter_mut() {
+            v.write(DropCounter(drops.clone()));
+        }
+        assert_eq!(
+            w.try_produce(1, Vec::new()),
+            Err(ProduceError::TooMany {
+                requested: 1,
+                available: 0
+            })
+        );
+        unsafe { w.assume_init_produce(cap - 1, Vec::new()) };
+        produced += cap - 1;
+
+        assert_eq!(r.slice(false).unwrap().0.len(), cap - 1);
+        r.consume(cap - 1);
+        // consumed items are dropped when their space is handed out again
+        assert_eq!(drops.load(Ordering::SeqCst), produced - (cap - 1));
+    }
+
+    let s = w.uninit_slice(false);
+    assert_eq!(drops.load(Ordering::SeqCst), produced);
+    s[0].write(DropCounter(drops.clone()));
+    unsafe { w.assume_init_produce(1, Vec::new()) };
+    produced += 1;
+
+    // the remaining items are dropped with the buffer
+    drop(w);
+    assert_eq!(drops.load(Ordering::SeqCst), produced - 1);
+    drop(r);
+    assert_eq!(drops.load(Ordering::SeqCst), produced);
+}
+
+#[test]
+fn power_of_two() {
+    use vmcircbuffer::double_mapped_buffer::{pagesize, Builder};
+    use vmcircbuffer::generic::{self, NoMetadata};
+
+    for power_of_two in [false, true] {
+        let b = Builder::new(3 * pagesize()).power_of_two(power_of_two);
+        let mut w =
+            generic::Circular::with_builder::<[u8; 3], CountingNotifier, NoMetadata>(&b).unwrap();
+        assert_eq!(w.capacity().is_power_of_two(), power_of_two);
+        let mut r = w.add_reader(CountingNotifier::new(), CountingNotifier::new());
+
+        let mut next = 0u32;
+        let mut expected = 0u32;
+        for _ in 0..10 {
+            let s = w.slice(false);
+            let n = std::cmp::min(s.len(), 7777);
+            for v in s[0..n].iter_mut() {
+                *v = [next as u8, (next >> 8) as u8, (next >> 16) as u8];
+                next += 1;
+            }
+            w.produce(n, Vec::new());
+
+            let s = r.slice(false).unwrap().0;
+            assert_eq!(s.len(), n);
+            for v in s {
+                assert_eq!(
+                    *v,
+                    [
+                        expected as u8,
+                        (expected >> 8) as u8,
+                        (expected >> 16) as u8
+                    ]
+                );
+                expected += 1;
+            }
+            r.consume(n);
+        }
+    }
+}
+
+#[test]
+fn alignment() {
+    use vmcircbuffer::double_mapped_buffer::{pagesize, Builder, DoubleMappedBufferError};
+    use vmcircbuffer::generic::{self, ConsumeError, NoMetadata, ProduceError};
+
+    assert!(matches!(
+        Builder::new(0).alignment(48).build::<f32>(),
+        Err(DoubleMappedBufferError::Alignment)
+    ));
+    assert!(matches!(
+        Builder::new(0).alignment(64).build::<[u8; 3]>(),
+        Err(DoubleMappedBufferError::Alignment)
+    ));
+    assert!(matches!(
+        Builder::new(0).alignment(2 * pagesize()).build::<f32>(),
+        Err(DoubleMappedBufferError::Alignment)
+    ));
+
+    let b = Builder::new(0).alignment(64);
+    let mut w = generic::Circular::with_builder::<f32, CountingNotifier, NoMetadata>(&b).unwrap();
+    let mut r = w.add_reader(CountingNotifier::new(), CountingNotifier::new());
+    r.set_max_chunk(40);
+
+    for _ in 0..(w.capacity() / 16) {
+        let s = w.slice(false);
+        assert_eq!(s.as_ptr() as usize % 64, 0);
+        let n = s.len();
+        assert_eq!(
+            w.try_produce(5, Vec::new()),
+            Err(ProduceError::Unaligned {
+                requested: 5,
+                granularity: 16
+            })
+        );
+        w.produce(std::cmp::min(n, 48), Vec::new());
+
+        let s = r.slice(false).unwrap().0;
+        assert_eq!(s.as_ptr() as usize % 64, 0);
+        assert_eq!(s.len(), 32);
+        assert_eq!(
+            r.try_consume(3),
+            Err(ConsumeError::Unaligned {
+                requested: 3,
+                granularity: 16
+            })
+        );
+        r.consume(16);
+        assert_eq!(r.discard(40), 32);
+    }
+}
+
+#[test]
+#[should_panic(expected = "partial chunk")]
+fn produce_unaligned() {
+    use vmcircbuffer::double_mapped_buffer::Builder;
+    use vmcircbuffer::generic::{self, NoMetadata};
+
+    let b = Builder::new(0).alignment(64);
+    let mut w = generic::Circular::with_builder::<f32, CountingNotifier, NoMetadata>(&b).unwrap();
+    w.slice(false);
+    w.produce(5, Vec::new());
+}
+
+#[test]
+fn grow() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+    let cap = w.capacity();
+
+    let s = w.slice();
+    for (i, v) in s.iter_mut().enumerate() {
+        *v = i as u32;
+    }
+    w.produce(cap - 10);
+    assert_eq!(r.slice().unwrap().len(), cap - 10);
+    r.consume(100);
+
+    w.grow(cap + 1).unwrap();
+    assert!(w.capacity() > cap);
+    assert_eq!(r.capacity(), w.capacity());
+
+    let s = r.slice().unwrap();
+    assert_eq!(s.len(), cap - 110);
+    for (i, v) in s.iter().enumerate() {
+        assert_eq!(*v, (i + 100) as u32);
+    }
+
+    let mut f = r.fork();
+    assert_eq!(f.capacity(), w.capacity());
+    assert_eq!(f.slice().unwrap()[0], 100);
+    drop(f);
+
+    let mut next = (cap - 10) as u32;
+    let mut expected = 100u32;
+    for _ in 0..4 {
+        let s = w.slice();
+        let n = s.len();
+        for v in s.iter_mut() {
+            *v = next;
+            next += 1;
+        }
+        w.produce(n);
+
+        let s = r.slice().unwrap();
+        let n = s.len();
+        for v in s.iter() {
+            assert_eq!(*v, expected);
+            expected += 1;
+        }
+        r.consume(n);
+    }
+    assert_eq!(expected, next);
+
+    let cap = w.capacity();
+    w.grow(cap).unwrap();
+    assert_eq!(w.capacity(), cap);
+}
+
+#[test]
+fn grow_lossy_reader() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_lossy_reader();
+    let cap = w.capacity();
+
+    for (i, v) in w.slice().iter_mut().enumerate() {
+        *v = i as u32;
+    }
+    w.produce(cap);
+
+    // the slice of the writer overlaps the items of the reader
+    w.slice().fill(u32::MAX);
+    w.grow(cap + 1).unwrap();
+    assert_eq!(r.try_slice().unwrap().len(), 0);
+    assert_eq!(r.take_overrun(), cap);
+
+    let s = w.slice();
+    s[0] = 42;
+    w.produce(1);
+    assert_eq!(r.slice().unwrap(), &[42]);
+}
+
+#[test]
+fn grow_detached_reader() {
+    use std::time::Duration;
+
+    let mut w = Circular::new::<u32>().unwrap();
+    let cap = w.capacity();
+    let mut r = w.add_reader();
+    w.set_stall_timeout(Some(Duration::ZERO));
+
+    // the reader is detached and lags behind the items that the writer overwrites
+    for i in 0..3 {
+        let s = w.slice();
+        assert_eq!(s.len(), cap);
+        s.fill(i);
+        w.produce(cap);
+    }
+    assert_eq!(w.slice().len(), cap);
+
+    // the overwritten items are not copied for the detached reader
+    w.grow(cap + 1).unwrap();
+    assert!(r.slice().is_none());
+    assert!(r.is_detached());
+    assert_eq!(w.slice().len(), w.capacity());
+}
+
+#[test]
+fn buffer_pool() {
+    let pool = BufferPool::<u32>::new().max_idle(1);
+
+    let mut w = Circular::with_pool(&pool, 1000).unwrap();
+    let mut r = w.add_reader();
+    let cap = w.capacity();
+    let addr = w.slice().as_ptr();
+    w.slice()[0] = 123;
+    w.produce(1);
+    assert_eq!(r.slice().unwrap(), &[123]);
+    assert_eq!(pool.len(), 1);
+    assert_eq!(pool.idle(), 0);
+
+    let mut w2 = Circular::with_pool(&pool, 10).unwrap();
+    assert_ne!(w2.slice().as_ptr(), addr);
+    assert_eq!(pool.len(), 2);
+
+    drop(w);
+    assert_eq!(pool.idle(), 0);
+    drop(r);
+    assert_eq!(pool.idle(), 1);
+
+    let mut w = Circular::with_pool(&pool, cap).unwrap();
+    let _r = w.add_reader();
+    assert_eq!(w.slice().as_ptr(), addr);
+    assert!(w.slice().iter().all(|&x| x == 0));
+    assert_eq!(pool.idle(), 0);
+
+    drop(w2);
+    let mut w3 = Circular::with_pool(&pool, 2 * cap).unwrap();
+    assert!(w3.capacity() >= 2 * cap);
+    assert_ne!(w3.slice().as_ptr(), addr);
+
+    drop(w3);
+    assert_eq!(pool.idle(), 2);
+    pool.trim();
+    assert_eq!(pool.idle(), 0);
+    assert_eq!(pool.len(), 1);
+}
+
+#[test]
+fn state_snapshot() {
+    use vmcircbuffer::generic::{self, NoMetadata, ReaderSnapshot};
+
+    let mut w = generic::Circular::with_capacity::<u32, CountingNotifier, NoMetadata>(0).unwrap();
+    let cap = w.capacity();
+    let mut r1 = w.add_reader(CountingNotifier::new(), CountingNotifier::new());
+    let r2 = w.add_lossy_reader(CountingNotifier::new(), CountingNotifier::new());
+
+    w.slice(false);
+    w.produce(cap - 10, Vec::new());
+    r1.slice(false);
+    r1.consume(cap - 20);
+    w.slice(false);
+    w.produce(15, Vec::new());
+
+    let s = w.state_snapshot();
+    assert_eq!(s.capacity, cap);
+    assert_eq!(s.writer_offset, 5);
+    assert_eq!(s.writer_position, (cap + 5) as u64);
+    assert!(!s.done);
+    assert_eq!(
+        s.readers,
+        vec![
+            ReaderSnapshot {
+                id: r1.id(),
+                offset: cap - 20,
+                position: (cap - 20) as u64,
+                lag: 25,
+                lossy: false,
+                shared: false,
+            },
+            ReaderSnapshot {
+                id: r2.id(),
+                offset: 5,
+                position: 5,
+                lag: cap,
+                lossy: true,
+                shared: false,
+            },
+        ]
+    );
+
+    drop(w);
+    drop(r2);
+    let s = r1.state_snapshot();
+    assert!(s.done);
+    assert_eq!(s.readers.len(), 1);
+}
+
+#[test]
+fn slowest_reader() {
+    let mut w = Circular::new::<u32>().unwrap();
+    assert_eq!(w.slowest_reader(), None);
+
+    let mut r1 = w.add_reader();
+    let mut r2 = w.add_reader();
+    let _l = w.add_lossy_reader();
+    assert_ne!(r1.id(), r2.id());
+
+    let n = w.slice().len();
+    w.produce(n);
+    r1.slice().unwrap();
+    r1.consume(10);
+    r2.slice().unwrap();
+    r2.consume(20);
+    assert_eq!(w.slowest_reader(), Some((r1.id(), n - 10)));
+
+    r1.consume(20);
+    assert_eq!(w.slowest_reader(), Some((r2.id(), n - 20)));
+
+    drop(r1);
+    drop(r2);
+    assert_eq!(w.slowest_reader(), None);
+}
+
+#[test]
+fn detach_stalled_reader() {
+    use std::time::{Duration, Instant};
+
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut stalled = w.add_reader();
+    let mut r = w.add_reader();
+    w.set_stall_timeout(Some(Duration::from_millis(50)));
+
+    let n = w.slice().len();
+    w.produce(n);
+    assert_eq!(r.slice().unwrap().len(), n);
+    r.consume(n);
+    assert!(!stalled.is_detached());
+
+    // blocks, until the stalled reader is detached
+    let now = Instant::now();
+    let n = w.slice().len();
+    assert!(now.elapsed() >= Duration::from_millis(50));
+    w.produce(n);
+
+    assert!(stalled.is_detached());
+    assert!(stalled.slice().is_none());
+    assert!(!r.is_detached());
+    assert_eq!(r.slice().unwrap().len(), n);
+    assert_eq!(w.reader_lags().len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "need drop")]
+fn stall_timeout_drop_items() {
+    let w = Circular::new::<String>().unwrap();
+    w.set_stall_timeout(Some(std::time::Duration::from_millis(50)));
+}
+
+#[test]
+fn detach_stalled_reader_deadline() {
+    use std::time::{Duration, Instant};
+
+    let mut w = Circular::new::<u32>().unwrap();
+    let stalled = w.add_reader();
+    w.set_stall_timeout(Some(Duration::from_millis(50)));
+
+    let n = w.slice().len();
+    w.produce(n);
+
+    // the writer wakes up to detach the reader, before the deadline passes
+    let now = Instant::now();
+    assert_eq!(w.slice_timeout(Duration::from_secs(60)).len(), n);
+    assert!(now.elapsed() < Duration::from_secs(30));
+    assert!(stalled.is_detached());
+}
+
+#[test]
+fn detach_stalled_reader_with_slice() {
+    use std::time::Duration;
+
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut stalled = w.add_reader();
+    w.set_stall_timeout(Some(Duration::from_millis(20)));
+
+    let n = w.slice().len();
+    w.slice().fill(1);
+    w.produce(n);
+    let s = stalled.slice().unwrap();
+    assert_eq!(s.len(), n);
+
+    let writer = std::thread::spawn(move || {
+        let s = w.slice();
+        let n = s.len();
+        s.fill(2);
+        w.produce(n);
+        n
+    });
+
+    // the held slice is not overwritten, after the reader is detached
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(!writer.is_finished());
+    assert!(s.iter().all(|v| *v == 1));
+
+    assert!(stalled.slice().is_none());
+    assert!(stalled.is_detached());
+    assert_eq!(writer.join().unwrap(), n);
+}
+
+#[test]
+fn detached_reader_consumes_held_slice() {
+    use std::time::Duration;
+
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut stalled = w.add_reader();
+    w.set_stall_timeout(Some(Duration::from_millis(20)));
+
+    let n = w.slice().len();
+    w.produce(n);
+    assert_eq!(stalled.slice().unwrap().len(), n);
+
+    let writer = std::thread::spawn(move || {
+        let n = w.slice().len();
+        w.produce(n);
+        w
+    });
+    while !stalled.is_detached() {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    // the reader is lossy now, i.e., it can release the held slice, although
+    // the writer overtook it
+    stalled.consume(n);
+    assert_eq!(stalled.position(), n as u64);
+    drop(writer.join().unwrap());
+    assert!(stalled.slice().is_none());
+}
+
+#[test]
+fn pause_reader() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+    let cap = w.capacity();
+
+    w.slice()[0] = 1;
+    w.produce(10);
+    r.pause();
+    assert!(r.is_paused());
+    assert_eq!(w.reader_lags(), vec![0]);
+
+    // the paused reader does not block the writer
+    for _ in 0..3 {
+        let n = w.slice().len();
+        assert_eq!(n, cap);
+        w.produce(n);
+    }
+    assert_eq!(r.try_slice().unwrap().len(), 0);
+
+    assert_eq!(r.resume(), 10 + 3 * cap);
+    assert!(!r.is_paused());
+
+    let s = w.slice();
+    s[0] = 42;
+    w.produce(1);
+    assert_eq!(r.slice().unwrap(), &[42]);
+    r.consume(1);
+}
+
+#[test]
+fn reader_options() {
+    use vmcircbuffer::generic::ReaderOptions;
+
+    let mut w = Circular::new::<u32>().unwrap();
+    let cap = w.capacity();
+    let mut r = w.add_reader_with_opts(&ReaderOptions::new().max_chunk(100));
+    let mut tap = w.add_reader_with_opts(&ReaderOptions::new().non_blocking(true));
+
+    let n = w.slice().len();
+    w.produce(n);
+    assert_eq!(r.slice().unwrap().len(), 100);
+    r.consume(100);
+
+    // the tap does not block the writer, the normal reader does
+    assert_eq!(w.try_slice().len(), 100);
+    w.produce(100);
+    assert_eq!(w.try_slice().len(), 0);
+    assert_eq!(tap.take_overrun(), 100);
+    assert_eq!(tap.try_slice().unwrap().len(), cap);
+}
+
+#[test]
+fn consume_all() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+
+    w.extend([1, 2, 3]);
+    assert_eq!(r.slice().unwrap(), &[1, 2, 3]);
+    assert_eq!(r.consume_all(), 3);
+    assert_eq!(r.consume_all(), 0);
+
+    w.extend([4, 5]);
+    assert_eq!(r.drain(), vec![4, 5]);
+    assert_eq!(r.drain(), Vec::<u32>::new());
+}
+
+#[test]
+fn slice_chunks() {
+    let mut w = Circular::new::<u32>().unwrap();
+    let mut r = w.add_reader();
+
+    let h = std::thread::spawn(move || {
+        for i in 0..10 {
+            let s = w.slice_chunks(3);
+            assert_eq!(s.len() % 3, 0);
+            s[0] = i;
+            w.produce(1);
+        }
+    });
+
+    let mut frames = 0;
+    while let Some(s) = r.slice_chunks(3) {
+        assert_eq!(s.len() % 3, 0);
+        assert_eq!(s[1], s[0] + 1);
+        let n = s.len();
+        frames += n / 3;
+        r.consume(n);
+    }
+    h.join().unwrap();
+    assert_eq!(frames, 3);
+    assert_eq!(r.slice().unwrap(), &[9]);
+}
+
+#[test]
+fn io_vectored() {
+    use std::io::{IoSlice, IoSliceMut, Read, Write};
+
+    let mut w = Circular::new::<u8>().unwrap();
+    let mut r = w.add_reader();
+
+    let n = w
+        .write_vectored(&[
+            IoSlice::new(b"abc"),
+            IoSlice::new(b""),
+            IoSlice::new(b"defg"),
+        ])
+        .unwrap();
+    assert_eq!(n, 7);
+
+    let mut a = [0u8; 2];
+    let mut b = [0u8; 3];
+    let n = r
+        .read_vectored(&mut [IoSliceMut::new(&mut a), IoSliceMut::new(&mut b)])
+        .unwrap();
+    assert_eq!(n, 5);
+    assert_eq!(&a, b"ab");
+    assert_eq!(&b, b"cde");
+    assert_eq!(r.slice().unwrap(), b"fg");
+
+    // the free space limits the number of written bytes
+    let cap = w.capacity();
+    let big = vec![1u8; cap];
+    let n = w
+        .write_vectored(&[IoSlice::new(&big[..10]), IoSlice::new(&big)])
+        .unwrap();
+    assert_eq!(n, cap - 2);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn splice_to_pipe() {
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+
+    let (mut rx, tx) = std::io::pipe().unwrap();
+    let mut w = Circular::new::<u8>().unwrap();
+    let mut r = w.add_reader();
+
+    w.write_all(b"hello world").unwrap();
+    assert_eq!(r.splice_to(tx.as_raw_fd(), 5).unwrap(), 5);
+    assert_eq!(r.splice_to(tx.as_raw_fd(), 100).unwrap(), 6);
+    assert_eq!(r.try_slice().unwrap().len(), 0);
+
+    let mut buf = [0u8; 11];
+    rx.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello world");
+
+    drop(w);
+    assert_eq!(r.splice_to(tx.as_raw_fd(), 100).unwrap(), 0);
+}
+
+#[test]
+fn drain_to() {
+    use std::io::Write;
+
+    // accepts at most three bytes per call
+    struct Trickle(Vec<u8>);
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let n = std::cmp::min(buf.len(), 3);
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut w = Circular::new::<u8>().unwrap();
+    let mut r = w.add_reader();
+
+    let input: Vec<u8> = (0..100).collect();
+    let expected = input.clone();
+    let h = std::thread::spawn(move || {
+        for c in input.chunks(7) {
+            w.write_all(c).unwrap();
+        }
+    });
+
+    let mut sink = Trickle(Vec::new());
+    assert_eq!(r.drain_to(&mut sink).unwrap(), 100);
+    assert_eq!(sink.0, expected);
+    h.join().unwrap();
+
+    let mut w = Circular::new::<u8>().unwrap();
+    let mut r = w.add_reader();
+    w.write_all(&[1, 2, 3]).unwrap();
+    let mut full = [0u8; 2];
+    let e = r.drain_to(&mut &mut full[..]).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::WriteZero);
+    assert_eq!(full, [1, 2]);
+    assert_eq!(r.try_slice().unwrap(), &[3]);
+}
+
+#[test]
+fn fill_from() {
+    let mut w = Circular::new::<u8>().unwrap();
+    let mut r = w.add_reader();
+
+    let input: Vec<u8> = (0..100).collect();
+    let mut source = &input[..];
+    let mut total = 0;
+    loop {
+        match w.fill_from(&mut source).unwrap() {
+            0 => break,
+            n => total += n,
+        }
+    }
+    assert_eq!(total, 100);
+    assert_eq!(r.slice().unwrap(), &input[..]);
+}
+
+#[test]
+fn wrapper_history() {
+    use std::io::{Read, Write};
+    use std::time::Duration;
+    use vmcircbuffer::generic::ReaderOptions;
+
+    let mut w = Circular::new::<u8>().unwrap();
+    let mut r = w.add_reader_with_opts(&ReaderOptions::new().history(2));
+
+    w.write_all(&[1, 2, 3, 4]).unwrap();
+    let mut buf = [0u8; 8];
+    assert_eq!(Read::read(&mut r, &mut buf).unwrap(), 4);
+    assert_eq!(&buf[0..4], &[1, 2, 3, 4]);
+
+    // only the history is left, so blocking calls time out
+    assert_eq!(r.try_slice().unwrap(), &[3, 4]);
+    assert_eq!(r.slice_timeout(Duration::from_millis(10)).unwrap(), &[3, 4]);
+
+    // slices start with the history, copies skip it
+    w.write_all(&[5, 6, 7]).unwrap();
+    assert_eq!(r.slice().unwrap(), &[3, 4, 5, 6, 7]);
+    let mut dst = [0u8; 2];
+    assert_eq!(r.read_into(&mut dst), 2);
+    assert_eq!(dst, [5, 6]);
+    assert_eq!(r.drain(), vec![7]);
+
+    w.write_all(&[8, 9]).unwrap();
+    {
+        let g = r.read().unwrap();
+        assert_eq!(&*g, &[6, 7, 8, 9]);
+        g.consume(1);
+    }
+    drop(w);
+    assert_eq!(r.iter().collect::<Vec<_>>(), vec![9]);
+    assert_eq!(r.slice(), None);
+}
+
+#[test]
+fn writer_slice_timeout_max() {
+    use std::time::Duration;
+
+    let mut w = Circular::new::<u8>().unwrap();
+    let cap = w.capacity();
+    assert_eq!(w.slice_timeout(Duration::MAX).len(), cap);
+}
+
+#[test]
+fn reader_slice_timeout_max() {
+    use std::time::Duration;
+
+    let mut w = Circular::new::<u8>().unwrap();
+    let mut r = w.add_reader();
+    w.extend([1u8, 2, 3]);
+    assert_eq!(r.slice_timeout(Duration::MAX).unwrap().len(), 3);
+    drop(w);
+    r.consume(3);
+    assert_eq!(r.slice_timeout(Duration::MAX), None);
+}
@@ -1,3 +1,7 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use vmcircbuffer::generic;
 use vmcircbuffer::generic::Circular;
 use vmcircbuffer::generic::Metadata;
 use vmcircbuffer::generic::Notifier;
@@ -21,15 +25,18 @@ struct MyMetadata {
 
 impl Metadata for MyMetadata {
     type Item = Tag;
+    type View = Vec<Tag>;
 
     fn new() -> Self {
         MyMetadata { tags: Vec::new() }
     }
-    fn add(&mut self, offset: usize, mut tags: Vec<Self::Item>) {
-        for t in tags.iter_mut() {
-            t.item += offset;
+    fn add(&mut self, offset: usize, tags: Arc<[Self::Item]>) {
+        for t in tags.iter() {
+            self.tags.push(Tag {
+                item: t.item + offset,
+                data: t.data.clone(),
+            });
         }
-        self.tags.append(&mut tags);
     }
     fn get(&self) -> Vec<Self::Item> {
         self.tags.clone()
@@ -85,3 +92,181 @@ fn tags() {
     assert_eq!(tags[0].data, String::from("tenth"));
     assert_eq!(tags[0].item, 5);
 }
+
+#[test]
+fn default_tags() {
+    let mut w = Circular::with_capacity::<u32, MyNotifier, generic::Tags<u32>>(1).unwrap();
+    let mut r = w.add_reader(MyNotifier, MyNotifier);
+
+    let _ = w.slice(false);
+    w.produce(10, vec![generic::Tag { item: 3, data: 1 }]);
+    let _ = w.slice(false);
+    w.produce(
+        10,
+        vec![
+            generic::Tag { item: 0, data: 2 },
+            generic::Tag { item: 9, data: 3 },
+        ],
+    );
+
+    let (i, tags) = r.slice(false).unwrap();
+    assert_eq!(i.len(), 20);
+    assert_eq!(
+        tags,
+        vec![
+            generic::Tag { item: 3, data: 1 },
+            generic::Tag { item: 10, data: 2 },
+            generic::Tag { item: 19, data: 3 }
+        ]
+    );
+
+    r.consume(4);
+    let (_, tags) = r.slice(false).unwrap();
+    assert_eq!(
+        tags,
+        vec![
+            generic::Tag { item: 6, data: 2 },
+            generic::Tag { item: 15, data: 3 }
+        ]
+    );
+}
+
+#[test]
+fn shared_tags() {
+    let mut w = Circular::with_capacity::<u32, MyNotifier, generic::Tags<u32>>(1).unwrap();
+    let mut r1 = w.add_reader(MyNotifier, MyNotifier);
+
+    let _ = w.slice(false);
+    w.produce(10, vec![generic::Tag { item: 5, data: 1 }]);
+
+    let mut r2 = w.add_reader(MyNotifier, MyNotifier);
+    let _ = w.slice(false);
+    w.produce(10, vec![generic::Tag { item: 5, data: 2 }]);
+
+    let _ = r1.slice(false);
+    r1.consume(8);
+    let (_, tags) = r1.slice(false).unwrap();
+    assert_eq!(tags, vec![generic::Tag { item: 7, data: 2 }]);
+
+    let (_, tags) = r2.slice(false).unwrap();
+    assert_eq!(tags, vec![generic::Tag { item: 5, data: 2 }]);
+    r2.consume(6);
+    let (_, tags) = r2.slice(false).unwrap();
+    assert!(tags.is_empty());
+}
+
+#[test]
+fn tags_of_readable_items() {
+    let mut w = Circular::with_capacity::<u32, MyNotifier, generic::Tags<u32>>(1).unwrap();
+    let mut r = w.add_reader(MyNotifier, MyNotifier);
+    r.set_max_chunk(10);
+
+    let _ = w.slice(false);
+    w.produce(
+        20,
+        vec![
+            generic::Tag { item: 9, data: 1 },
+            generic::Tag { item: 10, data: 2 },
+        ],
+    );
+
+    let (i, tags) = r.slice(false).unwrap();
+    assert_eq!(i.len(), 10);
+    assert_eq!(tags, vec![generic::Tag { item: 9, data: 1 }]);
+
+    r.consume(10);
+    let (_, tags) = r.slice(false).unwrap();
+    assert_eq!(tags, vec![generic::Tag { item: 0, data: 2 }]);
+}
+
+#[test]
+fn timestamps() {
+    let mut w = Circular::with_capacity::<u32, MyNotifier, generic::Timestamps>(1).unwrap();
+    let mut r = w.add_reader(MyNotifier, MyNotifier);
+
+    let _ = w.slice(false);
+    let first = generic::Timestamps::now();
+    w.produce(10, first.clone());
+    let _ = w.slice(false);
+    let second = generic::Timestamps::now();
+    w.produce(10, second.clone());
+
+    let (i, tags) = r.slice(false).unwrap();
+    assert_eq!(i.len(), 20);
+    assert_eq!(tags.len(), 2);
+    assert_eq!(tags[0].data, first[0].data);
+    assert_eq!(tags[1].item, 10);
+
+    r.consume(5);
+    let (_, tags) = r.slice(false).unwrap();
+    assert_eq!(tags[0].item, 0);
+    assert_eq!(tags[0].data, first[0].data);
+    assert_eq!(tags[1].item, 5);
+
+    r.consume(5);
+    let (_, tags) = r.slice(false).unwrap();
+    assert_eq!(tags.len(), 1);
+    assert_eq!(tags[0].item, 0);
+    assert_eq!(tags[0].data, second[0].data);
+
+    let mut latencies = generic::Latencies::new();
+    latencies.record_tags(&tags);
+    assert_eq!(latencies.len(), 1);
+    assert!(latencies.percentile(50.0).is_some());
+}
+
+#[test]
+fn latency_percentiles() {
+    let mut l = generic::Latencies::new();
+    assert_eq!(l.percentile(50.0), None);
+    for i in (1..=100).rev() {
+        l.record(std::time::Duration::from_millis(i));
+    }
+    assert_eq!(l.percentile(0.0), Some(std::time::Duration::from_millis(1)));
+    assert_eq!(
+        l.percentile(100.0),
+        Some(std::time::Duration::from_millis(100))
+    );
+    assert_eq!(
+        l.percentile(50.0),
+        Some(std::time::Duration::from_millis(51))
+    );
+}
+
+struct Counted(Arc<AtomicUsize>);
+
+impl Clone for Counted {
+    fn clone(&self) -> Self {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Counted(self.0.clone())
+    }
+}
+
+#[test]
+fn tags_are_not_cloned() {
+    let clones = Arc::new(AtomicUsize::new(0));
+    let mut w = Circular::with_capacity::<u32, MyNotifier, generic::Tags<Counted>>(1).unwrap();
+    let mut r1 = w.add_reader(MyNotifier, MyNotifier);
+    let mut r2 = w.add_reader(MyNotifier, MyNotifier);
+
+    let _ = w.slice(false);
+    w.produce(
+        10,
+        vec![generic::Tag {
+            item: 5,
+            data: Counted(clones.clone()),
+        }],
+    );
+
+    for r in [&mut r1, &mut r2] {
+        for _ in 0..3 {
+            let (_, tags) = r.slice(false).unwrap();
+            assert_eq!(tags.len(), 1);
+            assert_eq!(tags.get(0).unwrap().item, 5);
+        }
+        r.consume(2);
+        let (_, tags) = r.slice(false).unwrap();
+        assert_eq!(tags.get(0).unwrap().item, 3);
+    }
+    assert_eq!(clones.load(Ordering::SeqCst), 0);
+}
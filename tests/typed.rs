@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use vmcircbuffer::framed::FrameError;
+use vmcircbuffer::typed::{Circular, TypedError};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+enum Msg {
+    Start { id: u32, name: String },
+    Samples(Vec<f32>),
+    Stop,
+}
+
+#[test]
+fn send_recv() {
+    let mut w = Circular::new::<Msg>().unwrap();
+    let mut r1 = w.add_reader();
+    let mut r2 = w.add_reader();
+
+    let msgs = vec![
+        Msg::Start {
+            id: 1,
+            name: "rx".to_string(),
+        },
+        Msg::Samples(vec![1.0, -0.5]),
+        Msg::Stop,
+    ];
+    for m in &msgs {
+        w.send(m).unwrap();
+    }
+    drop(w);
+
+    for r in [&mut r1, &mut r2] {
+        for m in &msgs {
+            assert_eq!(r.recv(), Some(Ok(m.clone())));
+        }
+        assert_eq!(r.recv(), None);
+    }
+}
+
+#[test]
+fn too_large() {
+    let mut w = Circular::new::<Vec<u8>>().unwrap();
+    let max = w.max_message();
+    // the length of the vector takes two bytes
+    assert!(w.send(&vec![0; max - 2]).is_ok());
+    assert_eq!(
+        w.send(&vec![0; max - 1]),
+        Err(TypedError::Frame(FrameError::TooLarge {
+            size: max + 1,
+            max
+        }))
+    );
+}
+
+#[test]
+fn wrap() {
+    let mut w = Circular::new::<Vec<u32>>().unwrap();
+    let mut r = w.add_reader();
+
+    let h = std::thread::spawn(move || {
+        for i in 0..1000u32 {
+            w.send(&(0..i % 100).map(|j| i + j).collect()).unwrap();
+        }
+    });
+
+    for i in 0..1000u32 {
+        let m = r.recv().unwrap().unwrap();
+        assert_eq!(m, (0..i % 100).map(|j| i + j).collect::<Vec<u32>>());
+    }
+    assert_eq!(r.recv(), None);
+    h.join().unwrap();
+}